@@ -0,0 +1,211 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Changing a value in the text of a configuration file, so comments and formatting are kept
+
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+
+/// Set `key` (a path like `store.lock-timeout`) to `value` in the configuration file `content`
+///
+/// If the key is set in its table already, only the value on its line is replaced. Otherwise the
+/// key is added after the last value of its table, or the table is appended to the file. All other
+/// lines are kept as they are.
+///
+/// Returns None if the value cannot be set this way, for example because it is a table, because
+/// the table is written inline or because the old value spans several lines. The changed file is
+/// parsed again and compared to the expected configuration to detect these cases.
+pub fn set_value(content: &str, key: &str, value: &Value) -> Option<String> {
+    if let Value::Table(_) = *value {
+        return None;
+    }
+
+    let mut expected = match ::toml::de::from_str::<Value>(content) {
+        Ok(doc) => doc,
+        Err(_)  => return None,
+    };
+    if expected.insert(key, value.clone()).is_err() {
+        return None;
+    }
+
+    let mut section : Vec<String> = key.split('.').map(String::from).collect();
+    let name = match section.pop() {
+        Some(name) => name,
+        None       => return None,
+    };
+
+    let mut lines : Vec<String> = content.lines().map(String::from).collect();
+
+    // The table the current line is in, None in an array of tables
+    let mut current   = Some(vec![]);
+    let mut found     = None;
+    let mut insert_at = if section.is_empty() { Some(0) } else { None };
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            current = table_header(trimmed);
+            if current.as_ref() == Some(&section) {
+                insert_at = Some(i + 1);
+            }
+            continue;
+        }
+
+        if current.as_ref() != Some(&section) || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if line_key(trimmed) == Some(&name[..]) {
+            found = Some(i);
+            break;
+        }
+        insert_at = Some(i + 1);
+    }
+
+    match (found, insert_at) {
+        (Some(i), _) => {
+            let eq      = lines[i].find('=').unwrap(); // the line has a key, so it has a '='
+            let changed = format!("{} {}", &lines[i][..eq + 1], value);
+            lines[i]    = changed;
+        },
+        (None, Some(i)) => lines.insert(i, format!("{} = {}", quote_key(&name), value)),
+        (None, None)    => {
+            if lines.last().map(|l| !l.trim().is_empty()).unwrap_or(false) {
+                lines.push(String::new());
+            }
+            let header = section.iter().map(|p| quote_key(p)).collect::<Vec<_>>().join(".");
+            lines.push(format!("[{}]", header));
+            lines.push(format!("{} = {}", quote_key(&name), value));
+        },
+    }
+
+    let mut changed = lines.join("\n");
+    changed.push('\n');
+
+    match ::toml::de::from_str::<Value>(&changed) {
+        Ok(ref doc) if *doc == expected => Some(changed),
+        _                               => None,
+    }
+}
+
+/// Get the path of the table in the header line `line`, None for an array of tables
+fn table_header(line: &str) -> Option<Vec<String>> {
+    if line.starts_with("[[") {
+        return None;
+    }
+
+    line[1..]
+        .split(']')
+        .next()
+        .map(|name| name.split('.').map(|p| String::from(unquote(p))).collect())
+}
+
+/// Get the key of the `key = value` line `line`
+fn line_key(line: &str) -> Option<&str> {
+    line.find('=').map(|eq| unquote(&line[..eq]))
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim().trim_matches('"').trim_matches('\'')
+}
+
+fn quote_key(key: &str) -> String {
+    let bare = !key.is_empty() &&
+        key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if bare {
+        String::from(key)
+    } else {
+        format!("{}", Value::String(String::from(key)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value;
+    use toml_query::read::TomlValueReadExt;
+
+    use super::set_value;
+
+    const CONFIG : &'static str = "# The user configuration\n\
+                                   \n\
+                                   [store]\n\
+                                   # Wait for locked entries\n\
+                                   lock-wait    = true\n\
+                                   lock-timeout = 5000\n\
+                                   \n\
+                                   # The user interface\n\
+                                   [ui]\n\
+                                   editor = \"vim\"\n";
+
+    fn read(content: &str, key: &str) -> Value {
+        let doc : Value = ::toml::de::from_str(content).unwrap();
+        doc.read(key).unwrap().unwrap().clone()
+    }
+
+    #[test]
+    fn test_set_existing_value_keeps_comments() {
+        let changed = set_value(CONFIG, "store.lock-timeout", &Value::Integer(100)).unwrap();
+
+        assert_eq!(changed, CONFIG.replace("lock-timeout = 5000", "lock-timeout = 100"));
+        assert_eq!(read(&changed, "store.lock-timeout"), Value::Integer(100));
+    }
+
+    #[test]
+    fn test_set_new_value_in_existing_table() {
+        let changed = set_value(CONFIG, "store.implicit-create", &Value::Boolean(true)).unwrap();
+
+        assert!(changed.contains("lock-timeout = 5000\nimplicit-create = true\n\n# The user"));
+        assert!(changed.starts_with("# The user configuration\n"));
+        assert_eq!(read(&changed, "store.implicit-create"), Value::Boolean(true));
+    }
+
+    #[test]
+    fn test_set_value_in_new_table() {
+        let value   = Value::String(String::from("/tmp/index"));
+        let changed = set_value(CONFIG, "search.index-path", &value).unwrap();
+
+        assert!(changed.starts_with(CONFIG));
+        assert!(changed.ends_with("\n\n[search]\nindex-path = \"/tmp/index\"\n"));
+        assert_eq!(read(&changed, "search.index-path"), value);
+    }
+
+    #[test]
+    fn test_set_value_in_empty_file() {
+        let changed = set_value("", "store.lock-wait", &Value::Boolean(false)).unwrap();
+        assert_eq!(changed, "[store]\nlock-wait = false\n");
+    }
+
+    #[test]
+    fn test_set_value_in_example_config() {
+        let config  = include_str!("../../../../imagrc.toml");
+        let changed = set_value(config, "store.lock-timeout", &Value::Integer(100)).unwrap();
+
+        assert_eq!(read(&changed, "store.lock-timeout"), Value::Integer(100));
+        assert_eq!(changed.lines().count(), config.lines().count());
+        assert!(changed.contains("# Set to false if you do not want imag to create the directory"));
+    }
+
+    #[test]
+    fn test_inline_tables_are_not_changed() {
+        let config = "store = { lock-wait = true }\n";
+        assert_eq!(set_value(config, "store.lock-wait", &Value::Boolean(false)), None);
+    }
+
+}
@@ -0,0 +1,422 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+#[macro_use] extern crate version;
+extern crate toml;
+extern crate toml_query;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+
+extern crate libimagrt;
+extern crate libimagerror;
+extern crate libimagstore;
+extern crate libimagutil;
+extern crate libimagentrylist;
+extern crate libimaginteraction;
+extern crate libimagsearch;
+extern crate libimagdiary;
+
+use std::env;
+use std::fs::{File, create_dir_all, read_dir, remove_file, rename};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use toml::Value;
+use toml::value::Table;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadExt;
+
+use libimagrt::configuration::{Configuration, ConfigOrigin, SYSTEM_CONFIG_FILE};
+use libimagrt::configuration::{env_config_key, env_layer, parse_config_value};
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagentrylist::serializer::print_items;
+use libimagutil::schema::{ConfigSchema, key_line};
+use libimagutil::warn_exit::warn_exit;
+
+mod edit;
+mod ui;
+
+use ui::build_ui;
+
+fn main() {
+    let rt = generate_runtime_setup("imag-config",
+                                    &version!()[..],
+                                    "Show and change the configuration",
+                                    build_ui);
+
+    match rt.cli().subcommand_name() {
+        Some("get")   => get(&rt),
+        Some("set")   => set(&rt),
+        Some("dump")  => dump(&rt),
+        Some("check") => check(&rt),
+        _             => warn_exit("No command", 1),
+    }
+}
+
+/// A configuration value as it is listed
+#[derive(Serialize)]
+struct ConfigValue<'a> {
+    key:    String,
+    value:  &'a Value,
+    origin: Option<String>,
+}
+
+impl<'a> ConfigValue<'a> {
+
+    fn new(key: String, value: &'a Value, config: &Configuration) -> ConfigValue<'a> {
+        let origin = config.origin(&key).map(|origin| format!("{}", origin));
+        ConfigValue {
+            key:    key,
+            value:  value,
+            origin: origin,
+        }
+    }
+
+    /// The line which is printed for the value in the text format
+    fn text(&self, with_key: bool, with_origin: bool) -> String {
+        let line = if with_key {
+            format!("{} = {}", self.key, self.value)
+        } else {
+            display(self.value)
+        };
+
+        match (with_origin, self.origin.as_ref()) {
+            (true, Some(origin)) => format!("{}  # {}", line, origin),
+            _                    => line,
+        }
+    }
+
+}
+
+fn get(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("get").unwrap();
+    let key    = scmd.value_of("key").unwrap(); // safe by clap
+    let origin = scmd.is_present("origin");
+    let config = get_config(rt);
+
+    let (values, with_key) = match config.read(key).map_err_trace_exit(1).unwrap() { // exits on error
+        Some(&Value::Table(ref t)) => {
+            let values = flatten(key, t)
+                .into_iter()
+                .map(|(k, v)| ConfigValue::new(k, v, config))
+                .collect::<Vec<_>>();
+            (values, true)
+        },
+        Some(value) => (vec![ConfigValue::new(String::from(key), value, config)], false),
+        None        => warn_exit(&format!("'{}' is not set", key), 1),
+    };
+
+    let _ = print_items(rt.output_format(), "values", &values, |v| v.text(with_key, origin))
+        .map_err_trace_exit(1);
+}
+
+fn set(rt: &Runtime) {
+    let scmd  = rt.cli().subcommand_matches("set").unwrap();
+    let key   = scmd.value_of("key").unwrap(); // safe by clap
+    let value = parse_config_value(scmd.value_of("value").unwrap()); // safe by clap
+    let path  = target_file(rt, scmd.value_of("module"));
+
+    let mut content = String::new();
+    if path.exists() {
+        let _ = File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut content))
+            .map_err_trace_exit(1);
+    }
+
+    // Report errors in the file and the key before the file is changed
+    let mut doc = ::toml::de::from_str::<Value>(&content).map_err_trace_exit(1).unwrap(); // exits on error
+    let _ = doc.insert(key, value.clone()).map_err_trace_exit(1);
+
+    // Only the line of the value is changed, so comments and formatting are kept
+    let s = match edit::set_value(&content, key, &value) {
+        Some(s) => s,
+        None    => warn_exit(&format!("'{}' cannot be set in {} without rewriting the file, \
+                                       please set it by hand", key, path.display()), 1),
+    };
+
+    if let Some(parent) = path.parent() {
+        let _ = create_dir_all(parent).map_err_trace_exit(1);
+    }
+    let _ = write_file(&path, &s).map_err_trace_exit(1);
+    info!("Set '{}' in {}", key, path.display());
+
+    // Values from layers after the changed file still override the new value
+    if let Some(config) = rt.config() {
+        let overriding_origin = config
+            .origin(key)
+            .and_then(|origin| if overrides(config, origin, &path, scmd.is_present("module")) {
+                Some(origin)
+            } else {
+                None
+            });
+
+        if let Some(origin) = overriding_origin {
+            warn!("'{}' is overridden by {}", key, origin);
+        }
+    }
+}
+
+/// Whether a value from `origin` overrides values from the file `path`
+///
+/// This is the case if `origin` is a later layer of the configuration than `path`. If `path` is
+/// not a layer (yet), it is placed where it would be loaded: the user configuration file after
+/// the system configuration file, a module configuration file after all other files.
+fn overrides(config: &Configuration, origin: &ConfigOrigin, path: &PathBuf, is_module: bool) -> bool {
+    let layers = config.layers();
+    let target = ConfigOrigin::File(path.clone());
+
+    let first_later = match layers.iter().position(|o| *o == target) {
+        Some(i) => i + 1,
+        None if is_module => layers
+            .iter()
+            .position(|o| match *o {
+                ConfigOrigin::File(_) => false,
+                _                     => true,
+            })
+            .unwrap_or(layers.len()),
+        None => layers
+            .iter()
+            .take_while(|o| **o == ConfigOrigin::File(PathBuf::from(SYSTEM_CONFIG_FILE)))
+            .count(),
+    };
+
+    match layers.iter().position(|o| o == origin) {
+        Some(i) => i >= first_later,
+        None    => true, // `--override-config` is applied after all layers
+    }
+}
+
+fn dump(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("dump").unwrap();
+    let origin = scmd.is_present("origin");
+    let format = rt.output_format();
+    let config = get_config(rt);
+
+    // Without origins, the text format is the configuration file itself
+    if format.is_text() && !origin {
+        let s = ::toml::ser::to_string(config.config()).map_err_trace_exit(1).unwrap(); // exits on error
+        print!("{}", s);
+        return;
+    }
+
+    let values = match **config {
+        Value::Table(ref t) => flatten("", t)
+            .into_iter()
+            .map(|(k, v)| ConfigValue::new(k, v, config))
+            .collect::<Vec<_>>(),
+        _ => vec![],
+    };
+
+    let _ = print_items(format, "values", &values, |v| v.text(true, origin)).map_err_trace_exit(1);
+}
+
+fn check(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("check").unwrap();
+    let schema = schema();
+    let mut ok = true;
+
+    let files = match scmd.values_of("file") {
+        Some(files) => files.map(PathBuf::from).collect(),
+        None        => config_files(rt),
+    };
+
+    for path in files {
+        let mut content = String::new();
+        let _ = File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut content))
+            .map_err_trace_exit(1);
+
+        let value = match ::toml::de::from_str::<Value>(&content) {
+            Ok(value) => value,
+            Err(e)    => {
+                let line = e.line_col().map(|(line, _)| line + 1).unwrap_or(0);
+                println!("{}:{}: {}", path.display(), line, e);
+                ok = false;
+                continue;
+            },
+        };
+
+        for violation in schema.check(&value) {
+            ok = false;
+            match key_line(&content, violation.key()) {
+                Some(line) => println!("{}:{}: {}", path.display(), line, violation),
+                None       => println!("{}: {}", path.display(), violation),
+            }
+        }
+    }
+
+    if !scmd.is_present("file") {
+        for origin in get_config(rt).layers() {
+            if let ConfigOrigin::Env(ref var) = *origin {
+                let key   = env_config_key(var).unwrap(); // only valid variables are layers
+                let value = env::var(var).unwrap_or_default();
+                for violation in schema.check(&env_layer(&key, &value)) {
+                    ok = false;
+                    println!("{}: {}", var, violation);
+                }
+            }
+        }
+    }
+
+    if ok {
+        info!("Configuration is valid");
+    } else {
+        ::std::process::exit(1);
+    }
+}
+
+/// The schema of all libraries which read the configuration
+///
+/// Libraries which read the configuration expose their schema with a `config_schema()` function,
+/// which has to be added here.
+fn schema() -> ConfigSchema {
+    ::libimagrt::configuration::config_schema()
+        .merge(::libimagstore::configuration::config_schema())
+        .merge(::libimaginteraction::config::config_schema())
+        .merge(::libimagsearch::config::config_schema())
+        .merge(::libimagdiary::config::config_schema())
+}
+
+/// Get the configuration files: the files the configuration was loaded from and the
+/// configuration files of all modules
+fn config_files(rt: &Runtime) -> Vec<PathBuf> {
+    let config    = get_config(rt);
+    let mut files = config
+        .layers()
+        .iter()
+        .filter_map(|origin| match *origin {
+            ConfigOrigin::File(ref path) => Some(path.clone()),
+            _                            => None,
+        })
+        .collect::<Vec<PathBuf>>();
+
+    if let Some(dir) = config.config_dir() {
+        let mut modules = read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .map(|name| name.starts_with("imag-") && name.ends_with(".toml"))
+                            .unwrap_or(false)
+                    })
+                    .filter(|path| !files.contains(path))
+                    .collect::<Vec<PathBuf>>()
+            })
+            .unwrap_or_default();
+
+        modules.sort();
+        files.append(&mut modules);
+    }
+
+    files
+}
+
+fn get_config<'a>(rt: &'a Runtime) -> &'a Configuration {
+    match rt.config() {
+        Some(config) => config,
+        None         => warn_exit("No configuration found", 1),
+    }
+}
+
+/// Get the file which `imag config set` changes: the configuration file of the module `module`
+/// or the user configuration file
+fn target_file(rt: &Runtime, module: Option<&str>) -> PathBuf {
+    let config   = rt.config();
+    let dir      = config.and_then(|c| c.config_dir()).cloned().unwrap_or_else(|| rt.rtp().clone());
+    let userfile = config.and_then(|c| c.user_config_file()).cloned();
+
+    match module {
+        Some(m) if m.starts_with("imag-") => dir.join(format!("{}.toml", m)),
+        Some(m)                           => dir.join(format!("imag-{}.toml", m)),
+        None                              => userfile.unwrap_or_else(|| dir.join("imagrc.toml")),
+    }
+}
+
+/// Write `content` to a temporary file next to `path` and move it in place, so the file is never
+/// left half written
+fn write_file(path: &PathBuf, content: &str) -> ::std::io::Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp  = path.with_file_name(format!(".{}.tmp", name));
+
+    let res = File::create(&tmp)
+        .and_then(|mut f| f.write_all(content.as_bytes()).and_then(|_| f.sync_all()))
+        .and_then(|_| rename(&tmp, path));
+
+    if res.is_err() {
+        let _ = remove_file(&tmp);
+    }
+    res
+}
+
+/// Get all values in `table` which are no tables, with their path
+fn flatten<'a>(prefix: &str, table: &'a Table) -> Vec<(String, &'a Value)> {
+    let mut values = vec![];
+    for (k, v) in table.iter() {
+        let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+        match *v {
+            Value::Table(ref t) => values.append(&mut flatten(&key, t)),
+            _                   => values.push((key, v)),
+        }
+    }
+    values
+}
+
+/// Strings are printed without quotes, so they can be used in scripts
+fn display(value: &Value) -> String {
+    match *value {
+        Value::String(ref s) => s.clone(),
+        ref other            => format!("{}", other),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value;
+    use toml::de::from_str;
+
+    use super::schema;
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let config : Value = from_str(include_str!("../../../../imagrc.toml")).unwrap();
+        assert_eq!(schema().check(&config), vec![]);
+    }
+
+}
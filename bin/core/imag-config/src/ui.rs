@@ -0,0 +1,85 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use clap::{Arg, App, SubCommand};
+
+pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+    app
+        .subcommand(SubCommand::with_name("get")
+                    .about("Print a configuration value")
+                    .version("0.1")
+                    .arg(Arg::with_name("key")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("The path of the value, for example 'store.implicit-create'")
+                         .value_name("KEY"))
+                    .arg(Arg::with_name("origin")
+                         .long("origin")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Also print where the value comes from"))
+                    )
+
+        .subcommand(SubCommand::with_name("set")
+                    .about("Set a configuration value in the user configuration file")
+                    .version("0.1")
+                    .arg(Arg::with_name("key")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("The path of the value, for example 'store.implicit-create'")
+                         .value_name("KEY"))
+                    .arg(Arg::with_name("value")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .help("The value, as TOML value ('true', '5000', '[ \"a\" ]'). Anything which is no TOML value is used as string")
+                         .value_name("VALUE"))
+                    .arg(Arg::with_name("module")
+                         .long("module")
+                         .short("m")
+                         .takes_value(true)
+                         .required(false)
+                         .help("Set the value in the configuration file of a module (for example 'diary' for 'imag-diary.toml')")
+                         .value_name("MODULE"))
+                    )
+
+        .subcommand(SubCommand::with_name("check")
+                    .about("Check the configuration files for unknown keys, values of the wrong type and deprecated keys")
+                    .version("0.1")
+                    .arg(Arg::with_name("file")
+                         .index(1)
+                         .takes_value(true)
+                         .multiple(true)
+                         .required(false)
+                         .help("Check these files instead of the files the configuration is loaded from")
+                         .value_name("FILE"))
+                    )
+
+        .subcommand(SubCommand::with_name("dump")
+                    .about("Print the complete configuration")
+                    .version("0.1")
+                    .arg(Arg::with_name("origin")
+                         .long("origin")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Print each value with where it comes from"))
+                    )
+}
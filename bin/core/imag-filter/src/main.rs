@@ -0,0 +1,100 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+extern crate filters;
+#[macro_use] extern crate log;
+#[macro_use] extern crate version;
+
+extern crate libimagstore;
+extern crate libimagrt;
+extern crate libimagerror;
+extern crate libimagentryfilter;
+
+use std::process::exit;
+
+use filters::filter::Filter;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagentryfilter::cli::EntryFilter;
+use libimagstore::storeid::StoreId;
+
+mod ui;
+
+use ui::build_ui;
+
+fn main() {
+    let mut rt = generate_runtime_setup("imag-filter",
+                                        &version!()[..],
+                                        "Filter a store read from stdin and write it to stdout",
+                                        build_ui);
+
+    let filter = rt.cli()
+        .value_of("filter")
+        .map(EntryFilter::parse)
+        .unwrap() // enforced by clap
+        .map_err_trace_exit(1)
+        .unwrap(); // exits on error
+    let invert = rt.cli().is_present("invert");
+
+    rt.store_backend_to_stdio().map_err_trace_exit(1).unwrap(); // exits on error
+
+    let drop_ids = rt
+        .store()
+        .entries()
+        .map_err_trace_exit(1)
+        .unwrap() // exits on error
+        .filter(|id| is_dropped(&rt, id, &filter, invert))
+        .collect::<Vec<StoreId>>();
+
+    for id in drop_ids {
+        debug!("Dropping '{}'", id);
+        rt.store().delete(id).map_err_trace_exit(1).unwrap(); // exits on error
+    }
+
+    // The remaining entries are written to stdout when the store is dropped
+}
+
+fn is_dropped(rt: &Runtime, id: &StoreId, filter: &EntryFilter, invert: bool) -> bool {
+    match rt.store().get(id.clone()).map_err_trace_exit(1).unwrap() { // exits on error
+        Some(entry) => filter.filter(&entry) == invert,
+        None => {
+            error!("Entry '{}' vanished while filtering", id);
+            exit(1)
+        },
+    }
+}
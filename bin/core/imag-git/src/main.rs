@@ -0,0 +1,116 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+#[macro_use] extern crate version;
+
+extern crate libimagstore;
+extern crate libimagstorestdhook;
+extern crate libimagrt;
+extern crate libimagerror;
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::exit;
+
+use clap::ArgMatches;
+
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagstore::storeid::StoreId;
+use libimagstorestdhook::vcs::git::runner::GitRunner;
+
+mod ui;
+
+use ui::build_ui;
+
+fn main() {
+    let rt = generate_runtime_setup("imag-git",
+                                    &version!()[..],
+                                    "Call git on the store directory",
+                                    build_ui);
+
+    let git = GitRunner::new(rt.store().path().clone());
+    let is_init = rt.cli().subcommand_name() == Some("init");
+    if !is_init && !git.is_repository() {
+        error!("The store at {:?} is not a git repository", rt.store().path());
+        exit(1)
+    }
+
+    let command = match rt.cli().subcommand() {
+        (name @ "log", Some(scmd)) |
+        (name @ "diff", Some(scmd)) |
+        (name @ "blame", Some(scmd)) => entry_command(&git, name, scmd),
+        (name, scmd) => forward_command(&git, name, scmd),
+    };
+
+    run(command)
+}
+
+/// Build a `git <name> <args> -- <path of the entry>` command
+fn entry_command(git: &GitRunner, name: &str, scmd: &ArgMatches) -> Command {
+    let id = scmd.value_of("id").unwrap(); // enforced by clap
+    let id = StoreId::new_baseless(PathBuf::from(id)).map_err_trace_exit(1).unwrap(); // exits on error
+
+    let mut cmd = git.command();
+    cmd.arg(name)
+        .args(scmd.values_of("git-args").map(|v| v.collect()).unwrap_or_else(Vec::new))
+        .arg("--")
+        .arg(id.local());
+    cmd
+}
+
+/// Build a `git <name> <args>` command with the arguments passed to `imag-git`
+fn forward_command(git: &GitRunner, name: &str, scmd: Option<&ArgMatches>) -> Command {
+    let args : Vec<&str> = scmd
+        .and_then(|m| m.values_of(""))
+        .map(|v| v.collect())
+        .unwrap_or_else(Vec::new);
+
+    let mut cmd = git.command();
+    cmd.arg(name).args(&args);
+    cmd
+}
+
+fn run(mut cmd: Command) -> ! {
+    debug!("Calling: {:?}", cmd);
+    match cmd.status() {
+        Ok(status) => exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            error!("Failed to call git: {}", e);
+            exit(1)
+        },
+    }
+}
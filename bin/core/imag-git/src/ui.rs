@@ -0,0 +1,50 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use clap::{Arg, App, AppSettings, SubCommand};
+
+pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+    app
+        .setting(AppSettings::AllowExternalSubcommands)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+
+        .subcommand(entry_subcommand("log", "Show the git history of an entry"))
+        .subcommand(entry_subcommand("diff", "Show uncommitted changes of an entry"))
+        .subcommand(entry_subcommand("blame", "Show which commit last changed each line of an entry"))
+}
+
+fn entry_subcommand<'a>(name: &'a str, about: &'a str) -> App<'a, 'a> {
+    SubCommand::with_name(name)
+        .about(about)
+        .version("0.1")
+        .arg(Arg::with_name("id")
+             .index(1)
+             .takes_value(true)
+             .required(true)
+             .help("The id of the entry in the store")
+             .value_name("ID"))
+        .arg(Arg::with_name("git-args")
+             .index(2)
+             .takes_value(true)
+             .multiple(true)
+             .required(false)
+             .last(true)
+             .help("Arguments passed to git, after '--'")
+             .value_name("ARGS"))
+}
@@ -0,0 +1,46 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use libimagentrylink::internal::store_check::StoreLinkConsistentExt;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+
+pub fn handle_check_consistency(rt: &Runtime) {
+    let scmd  = rt.cli().subcommand_matches("check-consistency").unwrap();
+    let store = rt.store();
+
+    let repairs = store.link_repairs().map_err_trace_exit(1).unwrap(); // exits on error
+
+    if repairs.is_empty() {
+        info!("Store is consistent");
+        return;
+    }
+
+    for repair in repairs.iter() {
+        println!("{}", repair);
+    }
+
+    if scmd.is_present("fix") {
+        store.repair_links(&repairs).map_err_trace_exit(1).unwrap(); // exits on error
+        info!("Repaired {} links", repairs.len());
+    } else {
+        info!("Run with --fix to repair the links");
+        ::std::process::exit(1);
+    }
+}
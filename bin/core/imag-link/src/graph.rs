@@ -0,0 +1,74 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use libimagentrylink::graph::LinkGraph;
+use libimagentrylink::graph::export::{to_dot, to_graphml};
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+pub fn handle_graph(rt: &Runtime) {
+    let scmd  = rt.cli().subcommand_matches("graph").unwrap();
+    let store = rt.store();
+
+    let mut graph = if scmd.is_present("cached") {
+        LinkGraph::load_cached(store, &LinkGraph::index_path_for_rtp(rt.rtp()))
+    } else {
+        LinkGraph::load(store)
+    }
+    .map_err_trace_exit(1)
+    .unwrap(); // exits on error
+
+    if let Some(collections) = scmd.values_of("collection") {
+        let collections = collections
+            .map(|c| c.split('/').collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+
+        graph = graph.filtered(|id| collections.iter().any(|c| id.is_in_collection(c)));
+    }
+
+    if scmd.is_present("external") {
+        graph.load_external_links(store).map_err_trace_exit(1).unwrap(); // exits on error
+    }
+
+    let label = |id: &StoreId| label(store, id);
+    match scmd.value_of("format") {
+        Some("graphml") => print!("{}", to_graphml(&graph, label)),
+        _               => print!("{}", to_dot(&graph, label)),
+    }
+}
+
+/// The label of an entry in the graph
+///
+/// The modules which have titles for their entries are asked for the title of the entry, entries
+/// without title are labelled with their id.
+fn label(store: &Store, id: &StoreId) -> String {
+    let titles : [fn(&Entry) -> Option<String>; 3] = [
+        ::libimagnotes::note::title,
+        ::libimagcounter::counter::title,
+        ::libimagdiary::entry::title,
+    ];
+
+    store.retrieve_copy(id.clone())
+        .ok()
+        .and_then(|entry| titles.iter().filter_map(|title| title(&entry)).next())
+        .unwrap_or_else(|| id.to_string())
+}
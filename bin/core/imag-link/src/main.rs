@@ -36,13 +36,19 @@
 extern crate clap;
 extern crate url;
 #[macro_use] extern crate version;
-#[cfg(test)] extern crate toml;
-#[cfg(test)] extern crate toml_query;
+extern crate toml;
+extern crate toml_query;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 extern crate libimagentrylink;
+extern crate libimagentrylist;
 extern crate libimagrt;
 extern crate libimagstore;
 extern crate libimagerror;
+extern crate libimagnotes;
+extern crate libimagcounter;
+extern crate libimagdiary;
 
 #[cfg(test)]
 #[macro_use]
@@ -53,6 +59,7 @@ extern crate libimagutil;
 
 use std::ops::Deref;
 
+use libimagrt::output::OutputFormat;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagstore::error::StoreError;
@@ -62,14 +69,25 @@ use libimagstore::store::Store;
 use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
 use libimagentrylink::external::ExternalLinker;
 use libimagentrylink::internal::InternalLinker;
+use libimagentrylink::internal::LinkKind;
+use libimagentrylink::internal::add_internal_links_transactional;
+use libimagentrylink::internal::add_internal_links_of_kind_transactional;
+use libimagentrylink::internal::remove_internal_links_transactional;
+use libimagentrylist::serializer::print_items;
 use libimagutil::warn_result::*;
 use libimagutil::warn_exit::warn_exit;
 use libimagutil::info_result::*;
 use clap::ArgMatches;
 use url::Url;
 
+mod consistency;
+mod graph;
+mod query;
 mod ui;
 
+use consistency::handle_check_consistency;
+use graph::handle_graph;
+use query::handle_query;
 use ui::build_ui;
 
 fn main() {
@@ -84,6 +102,9 @@ fn main() {
             match name {
                 "internal" => handle_internal_linking(&rt),
                 "external" => handle_external_linking(&rt),
+                "query"    => handle_query(&rt),
+                "graph"    => handle_graph(&rt),
+                "check-consistency" => handle_check_consistency(&rt),
                 _ => warn_exit("No commandline call", 1)
             }
         });
@@ -114,31 +135,47 @@ fn handle_internal_linking(rt: &Runtime) {
                 .map(|matches| handle_internal_linking_list_call(rt, cmd, matches));
         },
         Some("add") => {
-            let (mut from, to) = get_from_to_entry(&rt, "add");
-            for mut to_entry in to {
-                if let Err(e) = to_entry.add_internal_link(&mut from) {
-                    trace_error_exit(&e, 1);
-                }
-            };
+            let (from, to) = get_from_to_entry(&rt, "add");
+            let kind = cmd
+                .subcommand_matches("add")
+                .unwrap() // safe, we know there is an "add" subcommand
+                .value_of("kind")
+                .map(|kind| kind.parse::<LinkKind>().map_err_trace_exit(1).unwrap()); // exits on error
+
+            match kind {
+                Some(kind) => add_internal_links_of_kind_transactional(rt.store(), from, to, kind),
+                None       => add_internal_links_transactional(rt.store(), from, to),
+            }
+            .map_err_trace_exit(1)
+            .unwrap(); // exits on error
         },
 
         Some("remove") => {
-            let (mut from, to) = get_from_to_entry(&rt, "remove");
-            for mut to_entry in to {
-                if let Err(e) = to_entry.remove_internal_link(&mut from) {
-                    trace_error_exit(&e, 1);
-                }
-            };
+            let (from, to) = get_from_to_entry(&rt, "remove");
+            remove_internal_links_transactional(rt.store(), from, to)
+                .map_err_trace_exit(1)
+                .unwrap(); // exits on error
         },
 
         _ => unreachable!(),
     }
 }
 
+/// An internal link as it is listed
+#[derive(Serialize)]
+struct InternalLinkListing {
+    entry: String,
+    link:  String,
+    kind:  Option<String>,
+}
+
 #[inline]
 fn handle_internal_linking_list_call(rt: &Runtime, cmd: &ArgMatches, list: &ArgMatches) {
     use libimagentrylink::external::is_external_link_storeid;
 
+    let format      = rt.output_format();
+    let mut listing = vec![];
+
     debug!("List...");
     for entry in list.values_of("entries").unwrap() { // clap has our back
         debug!("Listing for '{}'", entry);
@@ -151,16 +188,27 @@ fn handle_internal_linking_list_call(rt: &Runtime, cmd: &ArgMatches, list: &ArgM
                         })
                     })
                     .map(|links| {
-                        let i = links
+                        let links = links
                             .filter_map(|l| {
                                 l.to_str()
                                     .map_warn_err(|e| format!("Failed to convert StoreId to string: {:?}", e))
                                     .ok()
-                            })
-                            .enumerate();
-
-                        for (i, link) in i {
-                            println!("{: <3}: {}", i, link);
+                                    .map(|s| InternalLinkListing {
+                                        entry: String::from(entry),
+                                        link:  s,
+                                        kind:  l.kind().map(|kind| kind.to_string()),
+                                    })
+                            });
+
+                        if format.is_text() {
+                            for (i, link) in links.enumerate() {
+                                match link.kind {
+                                    Some(kind) => println!("{: <3}: {} ({})", i, link.link, kind),
+                                    None       => println!("{: <3}: {}", i, link.link),
+                                }
+                            }
+                        } else {
+                            listing.extend(links);
                         }
                     })
                     .map_err_trace()
@@ -178,6 +226,10 @@ fn handle_internal_linking_list_call(rt: &Runtime, cmd: &ArgMatches, list: &ArgM
             },
         }
     }
+
+    if !format.is_text() {
+        let _ = print_items(format, "links", &listing, |_| String::new()).map_err_trace_exit(1);
+    }
     debug!("Listing ready!");
 }
 
@@ -277,7 +329,7 @@ fn handle_external_linking(rt: &Runtime) {
 
     if scmd.is_present("list") {
         debug!("Listing links in entry!");
-        list_links_for_entry(rt.store(), &mut entry);
+        list_links_for_entry(rt.store(), &mut entry, rt.output_format());
         return;
     }
 
@@ -323,14 +375,20 @@ fn set_links_for_entry(store: &Store, matches: &ArgMatches, entry: &mut FileLock
         .ok();
 }
 
-fn list_links_for_entry(store: &Store, entry: &mut FileLockEntry) {
+fn list_links_for_entry(store: &Store, entry: &mut FileLockEntry, format: OutputFormat) {
     entry.get_external_links(store)
         .and_then(|links| {
-            for (i, link) in links.enumerate() {
-                match link {
-                    Ok(link) => println!("{: <3}: {}", i, link),
-                    Err(e)   => trace_error(&e),
+            let links = links
+                .filter_map(|link| link.map_err_trace().ok())
+                .map(|link| link.to_string())
+                .collect::<Vec<_>>();
+
+            if format.is_text() {
+                for (i, link) in links.iter().enumerate() {
+                    println!("{: <3}: {}", i, link);
                 }
+            } else {
+                let _ = print_items(format, "links", &links, String::clone).map_err_trace_exit(1);
             }
             Ok(())
         })
@@ -0,0 +1,162 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::ArgMatches;
+
+use libimagentrylink::graph::LinkGraph;
+use libimagentrylist::serializer::print_items;
+use libimagrt::output::OutputFormat;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagstore::storeid::StoreId;
+
+/// An entry within a number of hops from another entry
+#[derive(Serialize)]
+struct Neighbor {
+    id:       String,
+    distance: usize,
+}
+
+pub fn handle_query(rt: &Runtime) {
+    let scmd  = rt.cli().subcommand_matches("query").unwrap();
+    let graph = if scmd.is_present("cached") {
+        LinkGraph::load_cached(rt.store(), &LinkGraph::index_path_for_rtp(rt.rtp()))
+    } else {
+        LinkGraph::load(rt.store())
+    }
+    .map_err_trace_exit(1)
+    .unwrap(); // exits on error
+
+    let format = rt.output_format();
+
+    match scmd.subcommand() {
+        ("neighbors", Some(mtc))  => neighbors(&graph, mtc, format),
+        ("incoming", Some(mtc))   => incoming(&graph, mtc, format),
+        ("path", Some(mtc))       => path(&graph, mtc, format),
+        ("components", Some(_))   => components(&graph, format),
+        _ => {
+            warn!("No query given");
+            exit(1)
+        },
+    }
+}
+
+fn neighbors(graph: &LinkGraph, mtc: &ArgMatches, format: OutputFormat) {
+    let id   = get_id(graph, mtc.value_of("entry").unwrap()); // safe by clap
+    let hops = mtc.value_of("hops").map(|hops| {
+        hops.parse::<usize>().unwrap_or_else(|_| {
+            error!("Not a number: '{}'", hops);
+            exit(1)
+        })
+    });
+
+    match hops {
+        None => {
+            let neighbors = graph
+                .neighbors(&id)
+                .into_iter()
+                .filter(|n| in_collection(mtc, n))
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>();
+
+            print_ids(format, "neighbors", &neighbors)
+        },
+        Some(hops) => {
+            let neighbors = graph
+                .within_hops(&id, hops)
+                .into_iter()
+                .filter(|&(n, _)| in_collection(mtc, n))
+                .map(|(n, distance)| Neighbor { id: n.to_string(), distance: distance })
+                .collect::<Vec<_>>();
+
+            let _ = print_items(format, "neighbors", &neighbors, |n| format!("{}: {}", n.distance, n.id))
+                .map_err_trace_exit(1);
+        },
+    }
+}
+
+fn incoming(graph: &LinkGraph, mtc: &ArgMatches, format: OutputFormat) {
+    let id = get_id(graph, mtc.value_of("entry").unwrap()); // safe by clap
+
+    let sources = graph
+        .incoming(&id)
+        .into_iter()
+        .filter(|n| in_collection(mtc, n))
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>();
+
+    print_ids(format, "incoming", &sources)
+}
+
+fn path(graph: &LinkGraph, mtc: &ArgMatches, format: OutputFormat) {
+    let from = get_id(graph, mtc.value_of("from").unwrap()); // safe by clap
+    let to   = get_id(graph, mtc.value_of("to").unwrap()); // safe by clap
+
+    match graph.shortest_path(&from, &to) {
+        Some(path) => {
+            let path = path.into_iter().map(|id| id.to_string()).collect::<Vec<_>>();
+            print_ids(format, "path", &path)
+        },
+        None => {
+            info!("No path from {} to {}", from, to);
+            exit(1)
+        },
+    }
+}
+
+fn components(graph: &LinkGraph, format: OutputFormat) {
+    let components = graph
+        .connected_components()
+        .into_iter()
+        .map(|component| component.into_iter().map(|id| id.to_string()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    if format.is_text() {
+        for (i, component) in components.iter().enumerate() {
+            for id in component {
+                println!("{: <3}: {}", i, id);
+            }
+        }
+    } else {
+        let _ = print_items(format, "components", &components, |_| String::new())
+            .map_err_trace_exit(1);
+    }
+}
+
+fn print_ids(format: OutputFormat, name: &str, ids: &[String]) {
+    let _ = print_items(format, name, ids, String::clone).map_err_trace_exit(1);
+}
+
+fn get_id(graph: &LinkGraph, name: &str) -> StoreId {
+    let id = StoreId::new_baseless(PathBuf::from(name)).map_err_trace_exit(1).unwrap(); // exits on error
+    if !graph.contains(&id) {
+        error!("Entry not found: {}", id);
+        exit(1)
+    }
+    id
+}
+
+fn in_collection(mtc: &ArgMatches, id: &StoreId) -> bool {
+    mtc.value_of("collection")
+        .map(|coll| id.is_in_collection(&coll.split('/').collect::<Vec<&str>>()))
+        .unwrap_or(true)
+}
@@ -41,6 +41,17 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                                      .multiple(true)
                                      .help("Link to this entries")
                                      .value_name("ENTRIES"))
+                                .arg(Arg::with_name("kind")
+                                     .long("kind")
+                                     .short("k")
+                                     .takes_value(true)
+                                     .required(false)
+                                     .multiple(false)
+                                     .possible_values(&["blocks", "blocked-by", "part-of", "has-part",
+                                                        "references", "referenced-by", "follow-up",
+                                                        "followed-up-by"])
+                                     .help("The kind of the links. The linked entries get links of the inverse kind (blocks/blocked-by, part-of/has-part, ...)")
+                                     .value_name("KIND"))
                                 )
 
                     .subcommand(SubCommand::with_name("remove")
@@ -87,6 +98,119 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .required(false)
                          .help("Check the link-consistency in the store (might be time-consuming)"))
                     )
+        .subcommand(SubCommand::with_name("query")
+                    .about("Query the network of internal links")
+                    .version("0.1")
+                    .arg(Arg::with_name("cached")
+                         .long("cached")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Use the link index, so only entries which changed since the last query are read"))
+
+                    .subcommand(SubCommand::with_name("neighbors")
+                                .about("List the entries which are linked with an entry")
+                                .version("0.1")
+                                .arg(Arg::with_name("entry")
+                                     .index(1)
+                                     .takes_value(true)
+                                     .required(true)
+                                     .help("List the neighbors of this entry")
+                                     .value_name("ENTRY"))
+                                .arg(Arg::with_name("hops")
+                                     .long("hops")
+                                     .takes_value(true)
+                                     .required(false)
+                                     .help("Also list entries which are up to this many links away, with their distance")
+                                     .value_name("N"))
+                                .arg(Arg::with_name("collection")
+                                     .long("in")
+                                     .takes_value(true)
+                                     .required(false)
+                                     .help("Only list entries in this collection (for example 'diary' or 'notes')")
+                                     .value_name("COLLECTION"))
+                                )
+
+                    .subcommand(SubCommand::with_name("incoming")
+                                .about("List the entries which link to an entry")
+                                .version("0.1")
+                                .arg(Arg::with_name("entry")
+                                     .index(1)
+                                     .takes_value(true)
+                                     .required(true)
+                                     .help("List the entries linking to this entry")
+                                     .value_name("ENTRY"))
+                                .arg(Arg::with_name("collection")
+                                     .long("in")
+                                     .takes_value(true)
+                                     .required(false)
+                                     .help("Only list entries in this collection (for example 'diary' or 'notes')")
+                                     .value_name("COLLECTION"))
+                                )
+
+                    .subcommand(SubCommand::with_name("path")
+                                .about("Show the shortest path of links between two entries")
+                                .version("0.1")
+                                .arg(Arg::with_name("from")
+                                     .index(1)
+                                     .takes_value(true)
+                                     .required(true)
+                                     .help("Path from this entry")
+                                     .value_name("ENTRY"))
+                                .arg(Arg::with_name("to")
+                                     .index(2)
+                                     .takes_value(true)
+                                     .required(true)
+                                     .help("Path to this entry")
+                                     .value_name("ENTRY"))
+                                )
+
+                    .subcommand(SubCommand::with_name("components")
+                                .about("List the groups of entries which are connected by links")
+                                .version("0.1")
+                                )
+                    )
+        .subcommand(SubCommand::with_name("graph")
+                    .about("Export the network of internal links for visualization")
+                    .version("0.1")
+                    .arg(Arg::with_name("format")
+                         .long("format")
+                         .short("f")
+                         .takes_value(true)
+                         .required(false)
+                         .possible_values(&["dot", "graphml"])
+                         .default_value("dot")
+                         .help("The format to export the graph in")
+                         .value_name("FORMAT"))
+                    .arg(Arg::with_name("collection")
+                         .long("in")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(true)
+                         .number_of_values(1)
+                         .help("Only export entries in this collection (for example 'diary' or 'notes'), can be passed several times")
+                         .value_name("COLLECTION"))
+                    .arg(Arg::with_name("external")
+                         .long("external")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Include external links, as nodes for the URLs"))
+                    .arg(Arg::with_name("cached")
+                         .long("cached")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Use the link index, so only entries which changed since the last query are read"))
+                    )
+
+        .subcommand(SubCommand::with_name("check-consistency")
+                    .about("Check the links in the store and show how inconsistent links would be repaired")
+                    .version("0.1")
+                    .arg(Arg::with_name("fix")
+                         .long("fix")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Repair the links: Add missing back-links and remove links to entries which do not exist"))
+                    )
+
         .subcommand(SubCommand::with_name("external")
                     .about("Add and remove external links")
                     .version("0.1")
@@ -111,7 +235,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .long("remove")
                          .short("r")
                          .takes_value(true)
-                         .required(true)
+                         .required(false)
                          .help("Remove one external link"))
 
                     .arg(Arg::with_name("set")
@@ -0,0 +1,126 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+#[macro_use] extern crate version;
+
+extern crate libimagstore;
+extern crate libimagrt;
+extern crate libimagerror;
+
+use std::path::PathBuf;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+
+mod ui;
+
+use ui::build_ui;
+
+fn main() {
+    let mut rt = generate_runtime_setup("imag-read",
+                                        &version!()[..],
+                                        "Read entries from the store and write them to stdout",
+                                        build_ui);
+
+    // The entries are copied out of the filesystem store before the backend is replaced, as the
+    // filesystem store is not accessible anymore afterwards.
+    let entries = selected_ids(&rt)
+        .into_iter()
+        .map(|id| {
+            debug!("Reading '{}'", id);
+            rt.store().retrieve_copy(id).map_err_trace_exit(1).unwrap() // exits on error
+        })
+        .collect::<Vec<Entry>>();
+
+    rt.store_backend_to_stdout().map_err_trace_exit(1).unwrap(); // exits on error
+
+    for entry in entries {
+        let mut fle = rt
+            .store()
+            .create(entry.get_location().clone())
+            .map_err_trace_exit(1)
+            .unwrap(); // exits on error
+
+        *fle.get_header_mut()  = entry.get_header().clone();
+        *fle.get_content_mut() = entry.get_content().clone();
+    }
+
+    // The entries are written to stdout when the store is dropped
+}
+
+/// Get the ids of the entries to read, either the ones passed via the commandline, the ones from
+/// the passed modules or all entries of the store
+fn selected_ids(rt: &Runtime) -> Vec<StoreId> {
+    let scmd  = rt.cli();
+    let store = rt.store();
+
+    let mut ids = scmd
+        .values_of("id")
+        .map(|ids| {
+            ids.map(|id| {
+                    StoreId::new_baseless(PathBuf::from(id))
+                        .map_err_trace_exit(1)
+                        .unwrap() // exits on error
+                        .with_base(store.path().clone())
+                })
+                .collect::<Vec<StoreId>>()
+        })
+        .unwrap_or_default();
+
+    if let Some(modules) = scmd.values_of("module") {
+        for module in modules {
+            let iter = store.retrieve_for_module(module).map_err_trace_exit(1).unwrap(); // exits on error
+            ids.extend(iter);
+        }
+    }
+
+    if !scmd.is_present("id") && !scmd.is_present("module") {
+        ids = store
+            .entries()
+            .map_err_trace_exit(1)
+            .unwrap() // exits on error
+            .filter(StoreId::is_entry_file)
+            .collect();
+    }
+
+    ids.sort_by(|a, b| a.local().cmp(b.local()));
+    ids.dedup();
+    ids
+}
@@ -145,6 +145,7 @@ fn list(rt: &Runtime) {
         .check_changed(do_check_changed)
         .check_changed_content(do_check_changed_content)
         .check_changed_permiss(do_check_changed_permiss)
+        .output_format(rt.output_format())
         .list(iter.filter_map(Into::into))
         .ok();
 }
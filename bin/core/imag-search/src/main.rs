@@ -0,0 +1,144 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+#[macro_use] extern crate version;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+
+extern crate libimagsearch;
+extern crate libimagentrylist;
+extern crate libimagrt;
+extern crate libimagerror;
+
+use std::process::exit;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagentrylist::serializer::print_items;
+use libimagsearch::config::header_fields;
+use libimagsearch::index::Index;
+use libimagsearch::index::IndexLock;
+use libimagsearch::query::Query;
+use libimagsearch::query::QueryMode;
+
+mod ui;
+
+use ui::build_ui;
+
+/// If the journal of the index has more records than this, the index is compacted after searching
+const MAX_JOURNAL_LEN : usize = 1000;
+
+/// A search hit as it is listed
+#[derive(Serialize)]
+struct HitListing {
+    id:    String,
+    score: f64,
+}
+
+fn main() {
+    let rt = generate_runtime_setup("imag-search",
+                                    &version!()[..],
+                                    "Search the entries in the store",
+                                    build_ui);
+
+    if rt.cli().is_present("rebuild") {
+        rebuild(&rt)
+    } else {
+        search(&rt)
+    }
+}
+
+fn search(rt: &Runtime) {
+    let scmd   = rt.cli();
+    let query  = scmd.values_of("query").unwrap().collect::<Vec<_>>().join(" "); // enforced by clap
+    let mode   = if scmd.is_present("any") { QueryMode::Any } else { QueryMode::All };
+    let query  = Query::parse(&query)
+        .map_err_trace_exit(1)
+        .unwrap() // exits on error
+        .with_mode(mode);
+    let limit  = scmd.value_of("limit").map(|l| match l.parse::<usize>() {
+        Ok(l) => l,
+        Err(_) => {
+            error!("Limit must be a number: '{}'", l);
+            exit(1)
+        },
+    });
+    let scores = scmd.is_present("scores");
+
+    let path  = Index::path_for_rtp(rt.rtp());
+    let index = Index::load(&path).map_err_trace_exit(1).unwrap(); // exits on error
+
+    if index.is_empty() {
+        warn!("The search index is empty. Use 'imag search --rebuild' to build it");
+    }
+
+    let hits = index.search(&query);
+    let hits = hits
+        .iter()
+        .take(limit.unwrap_or(hits.len()))
+        .map(|hit| HitListing { id: hit.id().to_string(), score: hit.score() })
+        .collect::<Vec<_>>();
+
+    let _ = print_items(rt.output_format(), "hits", &hits, |hit| if scores {
+            format!("{:.4} {}", hit.score, hit.id)
+        } else {
+            hit.id.clone()
+        })
+        .map_err_trace_exit(1);
+
+    if index.journal_len() > MAX_JOURNAL_LEN {
+        debug!("Compacting search index, journal has {} records", index.journal_len());
+        let _ = Index::compact(&path).map_err_trace();
+    }
+}
+
+fn rebuild(rt: &Runtime) {
+    let config = rt.store()
+        .config()
+        .and_then(|c| c.get("hooks"))
+        .and_then(|h| h.get("search-index"));
+    let fields = header_fields(config);
+
+    // Changes from other processes are recorded after the index was built, as they wait for the lock
+    let path = Index::path_for_rtp(rt.rtp());
+    let lock = IndexLock::new(&path).map_err_trace_exit(1).unwrap(); // exits on error
+
+    info!("Rebuilding search index");
+    let index = Index::build_from_store(rt.store(), &fields).map_err_trace_exit(1).unwrap();
+    index.save(&path, &lock).map_err_trace_exit(1).unwrap();
+    info!("Indexed {} entries", index.len());
+}
@@ -0,0 +1,60 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use clap::{Arg, App};
+
+pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+    app
+        .arg(Arg::with_name("query")
+             .index(1)
+             .takes_value(true)
+             .multiple(true)
+             .required_unless("rebuild")
+             .help("The query. Words are searched as terms, text in double quotes as phrase")
+             .value_name("QUERY"))
+
+        .arg(Arg::with_name("any")
+             .long("any")
+             .short("a")
+             .takes_value(false)
+             .required(false)
+             .help("Find entries which match any part of the query, not only all of them"))
+
+        .arg(Arg::with_name("limit")
+             .long("limit")
+             .short("l")
+             .takes_value(true)
+             .required(false)
+             .help("Print only this many results")
+             .value_name("N"))
+
+        .arg(Arg::with_name("scores")
+             .long("scores")
+             .short("s")
+             .takes_value(false)
+             .required(false)
+             .help("Print the score of each result"))
+
+        .arg(Arg::with_name("rebuild")
+             .long("rebuild")
+             .takes_value(false)
+             .required(false)
+             .conflicts_with("query")
+             .help("Rebuild the search index from all entries in the store"))
+}
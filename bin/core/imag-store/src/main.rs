@@ -33,15 +33,24 @@
 )]
 
 extern crate clap;
+extern crate chrono;
+extern crate filters;
 #[macro_use] extern crate log;
 extern crate toml;
 #[cfg(test)] extern crate toml_query;
 #[macro_use] extern crate version;
 #[macro_use] extern crate error_chain;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 extern crate libimagrt;
 extern crate libimagstore;
 extern crate libimagerror;
+extern crate libimagentryfilter;
+extern crate libimagentrylink;
+extern crate libimagentryref;
+extern crate libimagentrylist;
+extern crate libimagnotes;
 
 #[cfg(test)]
 #[macro_use]
@@ -57,7 +66,10 @@ mod delete;
 mod dump;
 mod error;
 mod get;
+mod migrate;
+mod mv;
 mod retrieve;
+mod trash;
 mod ui;
 mod update;
 mod verify;
@@ -69,7 +81,10 @@ use create::create;
 use delete::delete;
 use dump::dump;
 use get::get;
+use migrate::migrate;
+use mv::mv;
 use retrieve::retrieve;
+use trash::trash;
 use ui::build_ui;
 use update::update;
 use verify::verify;
@@ -92,6 +107,9 @@ fn main() {
             "update"   => update(&rt),
             "verify"   => verify(&rt),
             "dump"     => dump(&mut rt),
+            "migrate"  => migrate(&rt),
+            "mv"       => mv(&rt),
+            "trash"    => trash(&rt),
             _ => {
                 debug!("Unknown command");
                 // More error handling
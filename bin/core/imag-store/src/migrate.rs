@@ -0,0 +1,100 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::process::exit;
+
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::*;
+use libimagstore::migration::MigrationRegistry;
+use libimagstore::store::Result;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+pub fn migrate(rt: &Runtime) {
+    let scmd     = rt.cli().subcommand_matches("migrate").unwrap();
+    let dry_run  = scmd.is_present("dry-run");
+    let registry = migrations();
+    let store    = rt.store();
+
+    let mut migrated   = 0;
+    let mut up_to_date = 0;
+    let mut failed     = vec![];
+
+    let ids = store.entries().map_err_trace_exit(1).unwrap(); // exits on error
+    for id in ids.filter(StoreId::is_entry_file) {
+        debug!("Working on {:?}", id);
+        match migrate_entry(store, &registry, id.clone(), dry_run) {
+            Ok(true) => {
+                info!("Migrated {}", id);
+                migrated += 1;
+            },
+            Ok(false) => up_to_date += 1,
+            Err(e) => {
+                trace_error(&e);
+                failed.push(id);
+            },
+        }
+    }
+
+    if dry_run {
+        println!("{} entries would be migrated, {} are up to date", migrated, up_to_date);
+    } else {
+        println!("{} entries migrated, {} are up to date", migrated, up_to_date);
+    }
+
+    if !failed.is_empty() {
+        println!("Could not migrate {} entries:", failed.len());
+        for id in failed {
+            println!("    {}", id);
+        }
+        exit(1);
+    }
+}
+
+/// The migrations of the libraries `imag-store` knows about
+///
+/// Libraries which change the layout of their header section expose a `register_migrations()`
+/// function, which has to be added here.
+fn migrations() -> MigrationRegistry {
+    let mut registry = MigrationRegistry::new();
+    let _ = registry
+        .register_all(&[
+            ::libimagnotes::migration::register_migrations,
+        ])
+        .map_err_trace_exit(1);
+    registry
+}
+
+/// Migrate one entry, writing it only if it was altered and this is not a dry run
+fn migrate_entry(store: &Store, registry: &MigrationRegistry, id: StoreId, dry_run: bool)
+    -> Result<bool>
+{
+    let mut transaction = store.transaction();
+    transaction.add(try!(store.retrieve(id)));
+
+    // If migrating fails, the transaction is dropped, so the entry is not written
+    let migrated = try!(registry.migrate_entry(&mut transaction.entries_mut()[0]));
+
+    if migrated && !dry_run {
+        transaction.commit().map(|_| true)
+    } else {
+        transaction.rollback();
+        Ok(migrated)
+    }
+}
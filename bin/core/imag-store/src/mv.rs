@@ -0,0 +1,87 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error_exit;
+use libimagentrylink::internal::store_move::StoreLinkMoveExt;
+use libimagstore::storeid::StoreId;
+
+pub fn mv(rt: &Runtime) {
+    let scmd  = rt.cli().subcommand_matches("mv").unwrap();
+    let store = Some(rt.store().path().clone());
+    let from  = scmd.value_of("from").unwrap(); // safe by clap
+    let from  = StoreId::new(store.clone(), PathBuf::from(from))
+        .unwrap_or_else(|e| trace_error_exit(&e, 1));
+    let to    = scmd.value_of("to").unwrap(); // safe by clap
+    let to    = StoreId::new(store, PathBuf::from(to))
+        .unwrap_or_else(|e| trace_error_exit(&e, 1));
+    debug!("Moving {:?} to {:?}", from, to);
+
+    let _ = rt.store()
+        .move_by_id_with_links(from, to, scmd.is_present("markdown"))
+        .map_err_trace_exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use create::create;
+    use super::mv;
+
+    use std::path::PathBuf;
+
+    use libimagentrylink::internal::InternalLinker;
+
+    make_mock_app! {
+        app "imag-store";
+        modulename mock;
+        version "0.4.0";
+        with help "imag-store mocking app";
+    }
+    use self::mock::generate_test_runtime;
+    use self::mock::reset_test_runtime;
+
+    #[test]
+    fn test_mv_rewrites_links() {
+        let rt = generate_test_runtime(vec!["create", "test_mv_a"]).unwrap();
+        create(&rt);
+        let rt = reset_test_runtime(vec!["create", "test_mv_b"], rt).unwrap();
+        create(&rt);
+
+        {
+            let mut a = rt.store().get(PathBuf::from("test_mv_a")).unwrap().unwrap();
+            let mut b = rt.store().get(PathBuf::from("test_mv_b")).unwrap().unwrap();
+            a.add_internal_link(&mut b).unwrap();
+        }
+
+        let rt = reset_test_runtime(vec!["mv", "test_mv_b", "test_mv_c"], rt).unwrap();
+        mv(&rt);
+
+        assert!(rt.store().get(PathBuf::from("test_mv_b")).unwrap().is_none());
+        assert!(rt.store().get(PathBuf::from("test_mv_c")).unwrap().is_some());
+
+        let a     = rt.store().retrieve_copy(PathBuf::from("test_mv_a")).unwrap();
+        let links = a.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].to_str().unwrap(), "test_mv_c");
+    }
+
+}
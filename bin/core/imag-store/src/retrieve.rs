@@ -26,6 +26,9 @@ use libimagstore::storeid::StoreId;
 use libimagrt::runtime::Runtime;
 use libimagerror::trace::MapErrTrace;
 use libimagutil::debug_result::*;
+use libimagentryfilter::cli::filter_from_matches;
+
+use filters::filter::Filter;
 
 pub fn retrieve(rt: &Runtime) {
     rt.cli()
@@ -48,14 +51,15 @@ pub fn retrieve(rt: &Runtime) {
 }
 
 pub fn print_entry(rt: &Runtime, scmd: &ArgMatches, e: FileLockEntry) {
+    let filter = filter_from_matches(scmd).map_err_trace_exit(1).unwrap(); // exits on error
+    if filter.map(|f| !f.filter(&e)).unwrap_or(false) {
+        info!("Entry does not match the filter");
+        return;
+    }
+
     if do_print_raw(scmd) {
         debug!("Printing raw content...");
         println!("{}", e.to_str());
-    } else if do_filter(scmd) {
-        debug!("Filtering...");
-        warn!("Filtering via header specs is currently now supported.");
-        warn!("Will fail now!");
-        unimplemented!()
     } else {
         debug!("Printing structured...");
         if do_print_header(scmd) {
@@ -95,7 +99,3 @@ fn do_print_raw(m: &ArgMatches) -> bool {
     m.is_present("raw")
 }
 
-fn do_filter(m: &ArgMatches) -> bool {
-    m.subcommand_matches("filter-header").is_some()
-}
-
@@ -0,0 +1,135 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+use std::process::exit;
+
+use chrono::Duration;
+
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::*;
+use libimagentrylist::serializer::print_items;
+use libimagstore::storeid::StoreId;
+use libimagstore::trash::TRASH_COLLECTION;
+use libimagstore::trash::Trash;
+
+pub fn trash(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("trash").unwrap();
+
+    match scmd.subcommand() {
+        ("list", _)            => list(rt),
+        ("restore", Some(mtc)) => restore(rt, mtc.value_of("id").unwrap()), // safe by clap
+        ("purge", Some(mtc))   => {
+            if let Some(id) = mtc.value_of("id") {
+                purge(rt, id)
+            } else if let Some(days) = mtc.value_of("older-than") {
+                let days = days.parse::<i64>().unwrap_or_else(|_| {
+                    error!("Not a number of days: '{}'", days);
+                    exit(1)
+                });
+                purge_older_than(rt, Duration::days(days))
+            } else {
+                // --all
+                purge_older_than(rt, Duration::zero())
+            }
+        },
+        _ => {
+            debug!("Unknown command");
+            exit(1)
+        },
+    }
+}
+
+/// An entry in the trash as it is listed
+#[derive(Serialize)]
+struct TrashListing {
+    id:       String,
+    original: String,
+    module:   Option<String>,
+    deleted:  Option<String>,
+}
+
+fn list(rt: &Runtime) {
+    let entries = Trash::new(rt.store()).entries().map_err_trace_exit(1).unwrap(); // exits on error
+    let format  = rt.output_format();
+
+    if !format.is_text() {
+        let entries = entries
+            .iter()
+            .map(|entry| TrashListing {
+                id:       entry.id().to_string(),
+                original: entry.original().to_string(),
+                module:   entry.module().cloned(),
+                deleted:  entry.deleted().map(|dt| dt.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        let _ = print_items(format, "trash", &entries, |_| String::new()).map_err_trace_exit(1);
+        return;
+    }
+
+    for entry in entries {
+        let deleted = entry
+            .deleted()
+            .map(|dt| dt.to_string())
+            .unwrap_or_else(|| String::from("<unknown>"));
+        let module = entry.module().map(String::as_str).unwrap_or("<unknown>");
+
+        println!("{}\t{}\t{}\t{}", deleted, module, entry.id(), entry.original());
+    }
+}
+
+fn restore(rt: &Runtime, id: &str) {
+    let trash = Trash::new(rt.store());
+    let id    = StoreId::new_baseless(PathBuf::from(id)).map_err_trace_exit(1).unwrap(); // exits on error
+
+    let trash_id = if id.is_in_collection(&[TRASH_COLLECTION]) {
+        id
+    } else {
+        // Restore the entry which was moved to the trash most recently
+        trash.entries()
+            .map_err_trace_exit(1)
+            .unwrap() // exits on error
+            .into_iter()
+            .filter(|e| e.original() == &id)
+            .last()
+            .map(|e| e.id().clone())
+            .unwrap_or_else(|| {
+                error!("No entry '{}' in the trash", id);
+                exit(1)
+            })
+    };
+
+    let restored = trash.restore(trash_id).map_err_trace_exit(1).unwrap(); // exits on error
+    info!("Restored {}", restored);
+}
+
+fn purge(rt: &Runtime, id: &str) {
+    let id = StoreId::new_baseless(PathBuf::from(id)).map_err_trace_exit(1).unwrap(); // exits on error
+    Trash::new(rt.store()).purge(id).map_err_trace_exit(1).unwrap(); // exits on error
+}
+
+fn purge_older_than(rt: &Runtime, age: Duration) {
+    let purged = Trash::new(rt.store())
+        .purge_older_than(age)
+        .map_err_trace_exit(1)
+        .unwrap(); // exits on error
+
+    println!("{} entries purged", purged.len());
+}
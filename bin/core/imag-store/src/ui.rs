@@ -19,6 +19,8 @@
 
 use clap::{Arg, App, ArgGroup, SubCommand};
 
+use libimagentryfilter::cli::filter_argument;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app.subcommand(SubCommand::with_name("create")
                    .about("Create an entry from the store")
@@ -89,22 +91,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .long("raw")
                         .short("r")
                         .help("Print Entries as they are in the store"))
-
-                   .subcommand(SubCommand::with_name("filter-header")
-                               .about("Retrieve Entries by filtering")
-                               .version("0.1")
-                               .arg(Arg::with_name("header-field-where")
-                                    .long("where")
-                                    .short("w")
-                                    .takes_value(true)
-                                    .help("Filter with 'header.field=foo' where the header field 'header.field' equals 'foo'")
-                               )
-                               .arg(Arg::with_name("header-field-grep")
-                                    .long("grep")
-                                    .short("g")
-                                    .takes_value(true)
-                                    .help("Filter with 'header.field=[a-zA-Z0-9]*' where the header field 'header.field' matches '[a-zA-Z0-9]*'"))
-                               )
+                   .arg(filter_argument())
                    )
 
        .subcommand(SubCommand::with_name("get")
@@ -132,23 +119,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .long("raw")
                         .short("r")
                         .help("Print Entries as they are in the store"))
-
-                   .subcommand(SubCommand::with_name("filter-header")
-                               .about("Retrieve Entries by filtering")
-                               .version("0.1")
-                               .arg(Arg::with_name("header-field-where")
-                                    .long("where")
-                                    .short("w")
-                                    .takes_value(true)
-                                    .help("Filter with 'header.field=foo' where the header field 'header.field' equals 'foo'")
-                                    .value_name("header.field=foo")
-                               )
-                               .arg(Arg::with_name("header-field-grep")
-                                    .long("grep")
-                                    .short("g")
-                                    .takes_value(true)
-                                    .help("Filter with 'header.field=[a-zA-Z0-9]*' where the header field 'header.field' matches '[a-zA-Z0-9]*'"))
-                               )
+                   .arg(filter_argument())
                    )
 
        .subcommand(SubCommand::with_name("update")
@@ -186,13 +157,113 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .value_name("PATH"))
                    )
 
+       .subcommand(SubCommand::with_name("mv")
+                   .about("Move an entry and rewrite the links to it")
+                   .version("0.1")
+                   .arg(Arg::with_name("from")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .help("Move the Store Entry with this path")
+                        .value_name("PATH"))
+                   .arg(Arg::with_name("to")
+                        .index(2)
+                        .takes_value(true)
+                        .required(true)
+                        .help("The new path of the Store Entry")
+                        .value_name("PATH"))
+                   .arg(Arg::with_name("markdown")
+                        .long("markdown")
+                        .short("m")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Also rewrite markdown links to the entry in the content of all entries"))
+                   )
+
        .subcommand(SubCommand::with_name("verify")
                    .about("Verify the store")
                    .version("0.1")
+                   .arg(Arg::with_name("json")
+                        .long("json")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Print the report as JSON, same as '--output json'"))
+                   .arg(Arg::with_name("fix")
+                        .long("fix")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Fix missing and invalid version information in headers"))
+                   .arg(Arg::with_name("quarantine")
+                        .long("quarantine")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Move files which cannot be parsed to the quarantine directory of the store"))
+                   .arg(Arg::with_name("module")
+                        .long("module")
+                        .short("M")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(false)
+                        .help("Collection of a module which is not known to imag-store, files in it are not reported as orphaned")
+                        .value_name("MODULE"))
                    )
 
        .subcommand(SubCommand::with_name("dump")
                    .about("Dump the complete store to stdout. Currently does only support JSON")
                    .version("0.1")
                    )
+
+       .subcommand(SubCommand::with_name("migrate")
+                   .about("Migrate the entries of the store to the current version of imag")
+                   .version("0.1")
+                   .arg(Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .short("n")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Only report which entries would be migrated, do not write them"))
+                   )
+
+       .subcommand(SubCommand::with_name("trash")
+                   .about("Manage the trash of the store")
+                   .version("0.1")
+                   .subcommand(SubCommand::with_name("list")
+                               .about("List the entries in the trash")
+                               .version("0.1")
+                               )
+                   .subcommand(SubCommand::with_name("restore")
+                               .about("Move an entry from the trash back to its original location")
+                               .version("0.1")
+                               .arg(Arg::with_name("id")
+                                    .index(1)
+                                    .takes_value(true)
+                                    .required(true)
+                                    .help("The id of the entry in the trash, or its original id to restore the most recently trashed entry with this id")
+                                    .value_name("ID"))
+                               )
+                   .subcommand(SubCommand::with_name("purge")
+                               .about("Delete entries in the trash for good")
+                               .version("0.1")
+                               .arg(Arg::with_name("id")
+                                    .index(1)
+                                    .takes_value(true)
+                                    .required(false)
+                                    .help("The id of the entry in the trash")
+                                    .value_name("ID"))
+                               .arg(Arg::with_name("older-than")
+                                    .long("older-than")
+                                    .takes_value(true)
+                                    .required(false)
+                                    .help("Delete all entries which are in the trash for more than this number of days")
+                                    .value_name("DAYS"))
+                               .arg(Arg::with_name("all")
+                                    .long("all")
+                                    .takes_value(false)
+                                    .required(false)
+                                    .help("Delete all entries in the trash"))
+                               .group(ArgGroup::with_name("purge-what")
+                                      .args(&["id", "older-than", "all"])
+                                      .required(true))
+                               )
+                   )
 }
@@ -17,14 +17,85 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use libimagrt::output::OutputFormat;
 use libimagrt::runtime::Runtime;
-use libimagutil::warn_exit::warn_exit;
+use libimagerror::trace::MapErrTrace;
+use libimagentryref::refstore::RefStore;
+use libimagstore::verify::{Finding, Problem, VerifyOptions};
+
+/// The collections of the modules of imag
+const KNOWN_MODULES : &'static [&'static str] = &[
+    "bookmark",
+    "category",
+    "counter",
+    "diary",
+    "links",
+    "notes",
+    "ref",
+    "timetrack",
+    "todo",
+];
 
 pub fn verify(rt: &Runtime) {
-    if rt.store().verify() {
+    let scmd    = rt.cli().subcommand_matches("verify").unwrap();
+    let modules = KNOWN_MODULES
+        .iter()
+        .map(|m| String::from(*m))
+        .chain(scmd.values_of("module").into_iter().flat_map(|ms| ms.map(String::from)))
+        .collect();
+
+    let opts = VerifyOptions::new()
+        .with_known_modules(modules)
+        .with_fix(scmd.is_present("fix"))
+        .with_quarantine(scmd.is_present("quarantine"));
+
+    let mut report = rt.store().verify(&opts).map_err_trace_exit(1).unwrap(); // exits on error
+
+    let duplicates = rt.store().duplicate_refs().map_err_trace_exit(1).unwrap(); // exits on error
+    for (path, ids) in duplicates {
+        for id in ids.iter() {
+            let others = ids
+                .iter()
+                .filter(|other| *other != id)
+                .map(|other| format!("{}", other.local().display()))
+                .collect();
+
+            let problem = Problem::DuplicateRef {
+                path: format!("{}", path.display()),
+                others: others,
+            };
+            report.findings.push(Finding::new(id.local().clone(), problem));
+        }
+    }
+
+    let format = if scmd.is_present("json") {
+        OutputFormat::Json
+    } else {
+        rt.output_format()
+    };
+
+    match format {
+        OutputFormat::Json => {
+            println!("{}", report.to_json().map_err_trace_exit(1).unwrap()); // exits on error
+        },
+        OutputFormat::Toml => {
+            let toml = ::toml::Value::try_from(&report)
+                .and_then(|value| ::toml::to_string(&value))
+                .map_err_trace_exit(1)
+                .unwrap(); // exits on error
+            print!("{}", toml);
+        },
+        OutputFormat::Text => {
+            for finding in report.findings.iter() {
+                println!("{}", finding);
+            }
+            info!("Checked {} files, found {} problems", report.checked, report.findings.len());
+        },
+    }
+
+    if report.is_ok() {
         info!("Store seems to be fine");
     } else {
-        warn_exit("Store seems to be broken somehow", 1);
+        ::std::process::exit(1);
     }
 }
-
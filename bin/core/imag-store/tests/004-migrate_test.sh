@@ -0,0 +1,62 @@
+#!/usr/bin/env bash
+
+source $(dirname ${BASH_SOURCE[0]})/../../tests/utils.sh
+source $(dirname ${BASH_SOURCE[0]})/utils.sh
+
+old_note() {
+    cat <<EOS
+---
+[imag]
+links = []
+version = "0.3.0"
+---
+buy milk
+EOS
+}
+
+test_migrate_old_note() {
+    mkdir -p ${STORE}/notes
+    old_note > ${STORE}/notes/shopping
+
+    imag-store migrate || { err "migrate failed"; return 1; }
+
+    local result=$(cat_entry notes/shopping)
+    if ! grep -q 'version = "0.4.0"' <<< "$result"; then
+        err "Version was not bumped: '$result'"
+        return 1
+    fi
+
+    if ! grep -q 'name = "shopping"' <<< "$result"; then
+        err "Note name was not added: '$result'"
+        return 1
+    fi
+}
+
+test_migrate_dry_run() {
+    mkdir -p ${STORE}/notes
+    old_note > ${STORE}/notes/shopping
+
+    imag-store migrate --dry-run || { err "migrate failed"; return 1; }
+
+    if [[ "$(cat_entry notes/shopping)" != "$(old_note)" ]]; then
+        err "Entry was changed in a dry run"
+        return 1
+    fi
+}
+
+test_migrate_up_to_date() {
+    imag-store create test-up-to-date || { err "create failed"; return 1; }
+    local before=$(cat_entry test-up-to-date)
+
+    imag-store migrate || { err "migrate failed"; return 1; }
+
+    if [[ "$(cat_entry test-up-to-date)" != "$before" ]]; then
+        err "Up to date entry was changed"
+        return 1
+    fi
+}
+
+invoke_tests                \
+    test_migrate_old_note   \
+    test_migrate_dry_run    \
+    test_migrate_up_to_date
@@ -18,6 +18,7 @@
 //
 
 extern crate clap;
+extern crate filters;
 #[macro_use] extern crate log;
 #[macro_use] extern crate version;
 
@@ -26,6 +27,8 @@ extern crate clap;
 extern crate libimagstore;
 extern crate libimagrt;
 extern crate libimagentrytag;
+extern crate libimagentrylist;
+extern crate libimagentryfilter;
 extern crate libimagerror;
 
 #[cfg(test)]
@@ -43,15 +46,19 @@ extern crate env_logger;
 
 use std::path::PathBuf;
 
+use libimagrt::output::OutputFormat;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagentrytag::tagable::Tagable;
 use libimagentrytag::tag::Tag;
-use libimagerror::trace::{trace_error, trace_error_exit};
+use libimagentryfilter::cli::filter_from_matches;
+use libimagentrylist::serializer::print_items;
+use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
 use libimagstore::storeid::StoreId;
 use libimagutil::warn_exit::warn_exit;
 
 use clap::ArgMatches;
+use filters::filter::Filter;
 
 mod ui;
 
@@ -146,12 +153,22 @@ fn list(id: PathBuf, rt: &Runtime) {
 
     let scmd = rt.cli().subcommand_matches("list").unwrap(); // safe, we checked in main()
 
-    let json_out = scmd.is_present("json");
+    let filter = filter_from_matches(scmd).map_err_trace_exit(1).unwrap(); // exits on error
+    if filter.map(|f| !f.filter(&entry)).unwrap_or(false) {
+        info!("Entry does not match the filter");
+        return;
+    }
+
+    let format = if scmd.is_present("json") {
+        OutputFormat::Json
+    } else {
+        rt.output_format()
+    };
     let line_out = scmd.is_present("linewise");
     let sepp_out = scmd.is_present("sep");
     let mut comm_out = scmd.is_present("commasep");
 
-    if !vec![json_out, line_out, comm_out, sepp_out].iter().any(|v| *v) {
+    if !vec![line_out, comm_out, sepp_out].iter().any(|v| *v) {
         // None of the flags passed, go to default
         comm_out = true;
     }
@@ -162,8 +179,9 @@ fn list(id: PathBuf, rt: &Runtime) {
     }
     let tags = tags.unwrap();
 
-    if json_out {
-        unimplemented!()
+    if !format.is_text() {
+        let _ = print_items(format, "tags", &tags, String::clone).map_err_trace_exit(1);
+        return;
     }
 
     if line_out {
@@ -20,6 +20,7 @@
 use clap::{Arg, App, ArgGroup, SubCommand};
 
 use libimagentrytag::tag::is_tag;
+use libimagentryfilter::cli::filter_argument;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app.arg(Arg::with_name("id")
@@ -55,7 +56,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .short("j")
                         .takes_value(false)
                         .required(false)
-                        .help("List as JSON"))
+                        .help("List as JSON, same as '--output json'"))
                    .arg(Arg::with_name("linewise")
                         .long("linewise")
                         .short("l")
@@ -75,6 +76,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .help("Separated by string")
                         .value_name("SEP"))
+                   .arg(filter_argument())
 
                    .group(ArgGroup::with_name("list-group")
                           .args(&[
@@ -83,7 +85,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                                 "commasep",
                                 "sep",
                           ])
-                          .required(true))
+                          .required(false))
                    )
 
 }
@@ -0,0 +1,96 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+#[macro_use] extern crate version;
+
+extern crate libimagstore;
+extern crate libimagrt;
+extern crate libimagerror;
+
+use std::process::exit;
+
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagstore::storeid::StoreId;
+
+mod ui;
+
+use ui::build_ui;
+
+fn main() {
+    let mut rt = generate_runtime_setup("imag-write",
+                                        &version!()[..],
+                                        "Read entries from stdin and write them to the store",
+                                        build_ui);
+
+    let policy = String::from(rt.cli().value_of("conflict").unwrap()); // has default value
+
+    rt.store_backend_to_stdin().map_err_trace_exit(1).unwrap(); // exits on error
+
+    // As the backend only contains the entries from stdin now, an entry conflicts if its file
+    // exists on the filesystem
+    let conflicts = rt
+        .store()
+        .entries()
+        .map_err_trace_exit(1)
+        .unwrap() // exits on error
+        .filter(|id| id.exists().map_err_trace_exit(1).unwrap()) // exits on error
+        .collect::<Vec<StoreId>>();
+
+    match policy.as_ref() {
+        "overwrite" => for id in conflicts {
+            info!("Overwriting '{}'", id);
+        },
+
+        "skip" => for id in conflicts {
+            info!("Skipping '{}', it exists already", id);
+            rt.store().delete(id).map_err_trace_exit(1).unwrap(); // exits on error
+        },
+
+        "fail" => if !conflicts.is_empty() {
+            for id in conflicts {
+                error!("Entry exists already: '{}'", id);
+            }
+            error!("Not writing anything");
+            exit(1)
+        },
+
+        _ => unreachable!(), // enforced by clap
+    }
+
+    rt.store_backend_to_filesystem().map_err_trace_exit(1).unwrap(); // exits on error
+}
@@ -37,6 +37,7 @@ extern crate clap;
 #[macro_use] extern crate version;
 
 extern crate libimagbookmark;
+extern crate libimagentrylist;
 extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
@@ -47,6 +48,7 @@ use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagbookmark::collection::BookmarkCollection;
 use libimagbookmark::link::Link as BookmarkLink;
+use libimagentrylist::serializer::print_items;
 use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
 use libimagutil::info_result::*;
 use libimagutil::iter::*;
@@ -125,12 +127,23 @@ fn list(rt: &Runtime) {
             match collection.links() {
                 Ok(links) => {
                     debug!("Listing...");
-                    for (i, link) in links.enumerate() {
-                        match link {
-                            Ok(link) => println!("{: >3}: {}", i, link),
-                            Err(e)   => trace_error(&e)
-                        }
-                    };
+                    let format = rt.output_format();
+                    if format.is_text() {
+                        for (i, link) in links.enumerate() {
+                            match link {
+                                Ok(link) => println!("{: >3}: {}", i, link),
+                                Err(e)   => trace_error(&e)
+                            }
+                        };
+                    } else {
+                        let links = links
+                            .filter_map(|link| link.map_err_trace().ok())
+                            .map(|link| link.to_string())
+                            .collect::<Vec<_>>();
+
+                        let _ = print_items(format, "bookmarks", &links, String::clone)
+                            .map_err_trace_exit(1);
+                    }
                     debug!("... ready with listing");
                 },
                 Err(e) => trace_error_exit(&e, 1),
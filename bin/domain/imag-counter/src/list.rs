@@ -17,36 +17,60 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+
 use libimagrt::runtime::Runtime;
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagcounter::counter::Counter;
+use libimagentryfilter::cli::filter_from_matches;
+use libimagentrylist::serializer::print_items;
+
+use filters::filter::Filter;
+
+/// A counter as it is listed
+#[derive(Serialize)]
+struct CounterListing {
+    name:  String,
+    value: i64,
+    unit:  Option<String>,
+}
 
 pub fn list(rt: &Runtime) {
     rt.cli()
         .subcommand_matches("list")
-        .map(|_| {
+        .map(|scmd| {
             debug!("Found 'list' subcommand...");
+            let filter = filter_from_matches(scmd).map_err_trace_exit(1).unwrap(); // exits on error
 
             Counter::all_counters(rt.store()).map(|iterator| {
-                for counter in iterator {
-                    counter.map(|c| {
+                let counters = iterator
+                    .filter_map(|counter| counter.map_err_trace().ok())
+                    .filter(|c| filter.as_ref().map(|f| f.filter(c)).unwrap_or(true))
+                    .filter_map(|c| {
                         let name    = c.name();
                         let value   = c.value();
-                        let unit    = c.unit();
 
                         if name.is_err() {
                             trace_error(&name.unwrap_err());
+                            None
                         } else if value.is_err() {
                             trace_error(&value.unwrap_err());
-                        } else if unit.is_none() {
-                            println!("{} - {}", name.unwrap(), value.unwrap());
+                            None
                         } else {
-                            println!("{} - {} {}", name.unwrap(), value.unwrap(), unit.unwrap());
+                            Some(CounterListing {
+                                name:  name.unwrap(),
+                                value: value.unwrap(),
+                                unit:  c.unit().map(|u| String::from(u.name())),
+                            })
                         }
                     })
-                    .map_err_trace()
-                    .ok();
-                }
+                    .collect::<Vec<_>>();
+
+                print_items(rt.output_format(), "counters", &counters, |c| match c.unit {
+                    None           => format!("{} - {}", c.name, c.value),
+                    Some(ref unit) => format!("{} - {} ({})", c.name, c.value, unit),
+                })
+                .map_err_trace_exit(1)
+                .ok();
             })
             .map_err_trace()
 
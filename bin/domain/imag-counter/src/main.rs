@@ -35,8 +35,13 @@
 #[macro_use] extern crate log;
 #[macro_use] extern crate version;
 extern crate clap;
+extern crate filters;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 extern crate libimagcounter;
+extern crate libimagentrylist;
+extern crate libimagentryfilter;
 extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
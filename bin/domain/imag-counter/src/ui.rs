@@ -19,6 +19,8 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryfilter::cli::filter_argument;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .arg(Arg::with_name("increment")
@@ -121,6 +123,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .help("List counters which equal VALUE")
                         .value_name("VALUE"))
+
+                   .arg(filter_argument())
         )
 
         .subcommand(SubCommand::with_name("interactive")
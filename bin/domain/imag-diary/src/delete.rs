@@ -28,6 +28,7 @@ use libimagtimeui::datetime::DateTime;
 use libimagtimeui::parse::Parse;
 use libimagutil::warn_exit::warn_exit;
 use libimagstore::storeid::IntoStoreId;
+use libimagstore::trash::Trash;
 
 use util::get_diary_name;
 
@@ -64,7 +65,7 @@ pub fn delete(rt: &Runtime) {
         return;
     }
 
-    if let Err(e) = rt.store().delete(to_del_location) {
+    if let Err(e) = Trash::new(rt.store()).trash(to_del_location, "diary") {
         trace_error_exit(&e, 1)
     }
 
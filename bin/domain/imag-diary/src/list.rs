@@ -20,20 +20,31 @@
 use libimagdiary::diary::Diary;
 use libimagdiary::error::DiaryErrorKind as DEK;
 use libimagdiary::error::ResultExt;
-use libimagentrylist::listers::core::CoreLister;
-use libimagentrylist::lister::Lister;
+use libimagentryfilter::cli::filter_from_matches;
+use libimagentrylist::serializer::print_items;
 use libimagrt::runtime::Runtime;
 use libimagstore::store::Entry;
 use libimagutil::warn_exit::warn_exit;
 use libimagerror::trace::MapErrTrace;
 use libimagutil::debug_result::*;
 
+use filters::filter::Filter;
+
 use util::get_diary_name;
 
+/// A diary entry as it is listed
+#[derive(Serialize)]
+struct DiaryEntryListing {
+    id: String,
+}
+
 pub fn list(rt: &Runtime) {
     let diaryname = get_diary_name(rt)
         .unwrap_or_else(|| warn_exit("No diary selected. Use either the configuration file or the commandline option", 1));
 
+    let scmd   = rt.cli().subcommand_matches("list").unwrap(); // safe, we checked in main()
+    let filter = filter_from_matches(scmd).map_err_trace_exit(1).unwrap(); // exits on error
+
     fn entry_to_location_listing_string(e: &Entry) -> String {
         e.get_location().clone()
             .without_base()
@@ -53,10 +64,11 @@ pub fn list(rt: &Runtime) {
                         .map_err_trace() // error tracing here
                         .ok() // so we can ignore errors here
                 })
-                .map(|e| e.into());
+                .filter(|e| filter.as_ref().map(|f| f.filter(e)).unwrap_or(true))
+                .map(|e| DiaryEntryListing { id: entry_to_location_listing_string(&e) })
+                .collect::<Vec<_>>();
 
-            CoreLister::new(&entry_to_location_listing_string)
-                .list(es)
+            print_items(rt.output_format(), "entries", &es, |e| format!("{:?}", e.id))
                 .chain_err(|| DEK::IOError)
         })
         .map_dbg_str("Ok")
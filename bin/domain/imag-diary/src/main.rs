@@ -36,10 +36,14 @@
 #[macro_use] extern crate version;
 extern crate clap;
 extern crate chrono;
+extern crate filters;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 extern crate libimagdiary;
 extern crate libimagentryedit;
 extern crate libimagentrylist;
+extern crate libimagentryfilter;
 extern crate libimagerror;
 extern crate libimaginteraction;
 extern crate libimagrt;
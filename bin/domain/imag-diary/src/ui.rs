@@ -19,6 +19,8 @@
 
 use clap::{Arg, ArgGroup, App, SubCommand};
 
+use libimagentryfilter::cli::filter_argument;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
        .arg(Arg::with_name("diaryname")
@@ -82,7 +84,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
 
         .subcommand(SubCommand::with_name("list")
                    .about("List diary entries")
-                   .version("0.1"))
+                   .version("0.1")
+                   .arg(filter_argument()))
 
         .subcommand(SubCommand::with_name("delete")
                    .about("Delete a diary entry")
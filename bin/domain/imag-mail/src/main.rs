@@ -20,13 +20,17 @@
 extern crate clap;
 #[macro_use] extern crate log;
 #[macro_use] extern crate version;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 extern crate libimagrt;
 extern crate libimagmail;
 extern crate libimagerror;
 extern crate libimagutil;
+extern crate libimagentrylist;
 
-use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
+use libimagerror::trace::{MapErrTrace, trace_error_exit};
+use libimagentrylist::serializer::print_items;
 use libimagmail::mail::Mail;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
@@ -64,6 +68,15 @@ fn import_mail(rt: &Runtime) {
         .map_info_str("Ok");
 }
 
+/// A mail as it is listed
+#[derive(Serialize)]
+struct MailListing {
+    id:      Option<String>,
+    from:    Option<String>,
+    to:      Option<String>,
+    subject: Option<String>,
+}
+
 fn list(rt: &Runtime) {
     use libimagmail::error::MailErrorKind as MEK;
     use libimagmail::error::ResultExt;
@@ -81,55 +94,27 @@ fn list(rt: &Runtime) {
         Err(e)   => trace_error_exit(&e, 1),
     };
 
-    fn list_mail(m: Mail) {
-        let id = match m.get_message_id() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no id>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        let from = match m.get_from() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no from>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        let to = match m.get_to() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no to>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        let subject = match m.get_subject() {
-            Ok(Some(f)) => f,
-            Ok(None) => "<no subject>".to_owned(),
-            Err(e) => {
-                trace_error(&e);
-                "<error>".to_owned()
-            },
-        };
-
-        println!("Mail: {id}\n\tFrom: {from}\n\tTo: {to}\n\t{subj}\n",
-                 from = from,
-                 id   = id,
-                 subj = subject,
-                 to   = to
-        );
-    }
-
-    // TODO: Implement lister type in libimagmail for this
-    for mail in iter {
-        list_mail(mail)
-    }
+    let mails = iter
+        .map(|m| MailListing {
+            id:      m.get_message_id().map_err_trace().unwrap_or(None),
+            from:    m.get_from().map_err_trace().unwrap_or(None),
+            to:      m.get_to().map_err_trace().unwrap_or(None),
+            subject: m.get_subject().map_err_trace().unwrap_or(None),
+        })
+        .collect::<Vec<_>>();
+
+    let _ = print_items(rt.output_format(), "mails", &mails, |m| {
+        fn or<'a>(field: &'a Option<String>, default: &'a str) -> &'a str {
+            field.as_ref().map(String::as_str).unwrap_or(default)
+        }
+
+        format!("Mail: {id}\n\tFrom: {from}\n\tTo: {to}\n\t{subj}\n",
+                from = or(&m.from, "<no from>"),
+                id   = or(&m.id, "<no id>"),
+                subj = or(&m.subject, "<no subject>"),
+                to   = or(&m.to, "<no to>"))
+    })
+    .map_err_trace_exit(1);
 }
 
 fn mail_store(rt: &Runtime) {
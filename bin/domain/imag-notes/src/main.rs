@@ -21,18 +21,26 @@ extern crate clap;
 #[macro_use] extern crate log;
 #[macro_use] extern crate version;
 extern crate itertools;
+extern crate filters;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 extern crate libimagnotes;
 extern crate libimagrt;
 extern crate libimagentryedit;
+extern crate libimagentrylist;
+extern crate libimagentryfilter;
 extern crate libimagerror;
 extern crate libimagutil;
 
 use std::process::exit;
 
 use itertools::Itertools;
+use filters::filter::Filter;
 
 use libimagentryedit::edit::Edit;
+use libimagentryfilter::cli::filter_from_matches;
+use libimagentrylist::serializer::print_items;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagnotes::note::Note;
@@ -107,13 +115,24 @@ fn edit_entry(rt: &Runtime, name: String) -> bool {
     note.edit_content(rt).map_err_trace().map_warn_err_str("Editing failed").is_ok()
 }
 
+/// A note as it is listed
+#[derive(Serialize)]
+struct NoteListing {
+    name: String,
+    id:   String,
+}
+
 fn list(rt: &Runtime) {
     use std::cmp::Ordering;
 
+    let scmd   = rt.cli().subcommand_matches("list").unwrap(); // safe, we checked in main()
+    let filter = filter_from_matches(scmd).map_err_trace_exit(1).unwrap(); // exits on error
+
     Note::all_notes(rt.store())
         .map_err_trace_exit(1)
         .map(|iter| {
             let notes = iter.filter_map(|note| note.map_err_trace().ok())
+                .filter(|note| filter.as_ref().map(|f| f.filter(note)).unwrap_or(true))
                 .sorted_by(|note_a, note_b| {
                     if let (Ok(a), Ok(b)) = (note_a.get_name(), note_b.get_name()) {
                         return a.cmp(&b)
@@ -122,12 +141,19 @@ fn list(rt: &Runtime) {
                     }
                 });
 
-            for note in notes.iter() {
-                note.get_name()
-                    .map(|name| println!("{}", name))
-                    .map_err_trace()
-                    .ok();
-            }
+            let notes = notes
+                .iter()
+                .filter_map(|note| {
+                    note.get_name()
+                        .map(|name| NoteListing { name: name, id: note.get_location().to_string() })
+                        .map_err_trace()
+                        .ok()
+                })
+                .collect::<Vec<_>>();
+
+            print_items(rt.output_format(), "notes", &notes, |note| note.name.clone())
+                .map_err_trace_exit(1)
+                .ok();
         })
         .ok();
 }
@@ -19,6 +19,8 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryfilter::cli::filter_argument;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("create")
@@ -64,6 +66,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
 
         .subcommand(SubCommand::with_name("list")
                    .about("List Notes")
-                   .version("0.1"))
+                   .version("0.1")
+                   .arg(filter_argument()))
 
 }
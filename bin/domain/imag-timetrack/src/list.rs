@@ -19,17 +19,87 @@
 
 use std::str::FromStr;
 
+use chrono::NaiveDateTime;
 use filters::filter::Filter;
+use serde::{Serialize, Serializer};
 
 use libimagerror::trace::trace_error;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::iter::TraceIterator;
 use libimagstore::store::FileLockEntry;
+use libimagentrylist::serializer::print_items;
+use libimagtimetrack::error::Result;
 use libimagtimetrack::timetrackingstore::TimeTrackStore;
 use libimagtimetrack::timetracking::TimeTracking;
 
 use libimagrt::runtime::Runtime;
 
+/// A timetracking as it is listed
+#[derive(Serialize)]
+struct TimetrackingListing {
+    tag:   String,
+
+    #[serde(serialize_with = "serialize_datetime")]
+    start: Option<NaiveDateTime>,
+
+    #[serde(serialize_with = "serialize_datetime")]
+    end:   Option<NaiveDateTime>,
+}
+
+impl TimetrackingListing {
+
+    /// The line which is printed for the timetracking in the text format
+    fn text(&self) -> String {
+        match (self.start, self.end) {
+            (None, _)          => format!("{} has no start time.", self.tag),
+            (Some(s), None)    => format!("{} | {} - ...", self.tag, s),
+            (Some(s), Some(e)) => format!("{} | {} - {}", self.tag, s, e),
+        }
+    }
+
+}
+
+fn serialize_datetime<S>(dt: &Option<NaiveDateTime>, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    dt.map(|dt| dt.format("%Y-%m-%dT%H:%M:%S").to_string()).serialize(serializer)
+}
+
+/// Print the timetrackings in the output format of the runtime, returns the exit code
+pub fn print_timetrackings<'a, I>(rt: &Runtime, timetrackings: I) -> i32
+    where I: Iterator<Item = FileLockEntry<'a>>
+{
+    let listing = timetrackings.fold(Ok(vec![]), |acc: Result<Vec<TimetrackingListing>>, e| {
+        acc.and_then(|mut listing| {
+            debug!("Processing {:?}", e.get_location());
+
+            let tag   = try!(e.get_timetrack_tag());
+            debug!(" -> tag = {:?}", tag);
+
+            let start = try!(e.get_start_datetime());
+            debug!(" -> start = {:?}", start);
+
+            let end   = try!(e.get_end_datetime());
+            debug!(" -> end = {:?}", end);
+
+            listing.push(TimetrackingListing {
+                tag:   tag.to_string(),
+                start: start,
+                end:   end,
+            });
+            Ok(listing)
+        })
+    });
+
+    match listing.map_err_trace() {
+        Ok(listing) => print_items(rt.output_format(), "timetrackings", &listing, TimetrackingListing::text)
+            .map(|_| 0)
+            .map_err_trace()
+            .unwrap_or(1),
+        Err(_) => 1,
+    }
+}
+
 pub fn list(rt: &Runtime) -> i32 {
     let (_, cmd) = rt.cli().subcommand();
     let cmd = cmd.unwrap(); // checked in main()
@@ -84,34 +154,7 @@ pub fn list(rt: &Runtime) -> i32 {
 
     rt.store()
         .get_timetrackings()
-        .and_then(|iter| {
-            iter.trace_unwrap()
-                .filter(|e| filter.filter(e))
-                .fold(Ok(()), |acc, e| {
-                    acc.and_then(|_| {
-                        debug!("Processing {:?}", e.get_location());
-
-                        let tag   = try!(e.get_timetrack_tag());
-                        debug!(" -> tag = {:?}", tag);
-
-                        let start = try!(e.get_start_datetime());
-                        debug!(" -> start = {:?}", start);
-
-                        let end   = try!(e.get_end_datetime());
-                        debug!(" -> end = {:?}", end);
-
-                        match (start, end) {
-                            (None, _)          => println!("{} has no start time.", tag),
-                            (Some(s), None)    => println!("{} | {} - ...", tag, s),
-                            (Some(s), Some(e)) => println!("{} | {} - {}", tag, s, e),
-                        }
-
-                        Ok(())
-                    })
-                })
-        })
-        .map(|_| 0)
         .map_err_trace()
+        .map(|iter| print_timetrackings(rt, iter.trace_unwrap().filter(|e| filter.filter(e))))
         .unwrap_or(1)
 }
-
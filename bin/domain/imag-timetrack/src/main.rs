@@ -27,11 +27,16 @@ extern crate clap;
 extern crate chrono;
 extern crate filters;
 extern crate itertools;
+extern crate serde;
+
+#[macro_use]
+extern crate serde_derive;
 
 extern crate libimagerror;
 extern crate libimagstore;
 extern crate libimagrt;
 extern crate libimagtimetrack;
+extern crate libimagentrylist;
 
 mod cont;
 mod day;
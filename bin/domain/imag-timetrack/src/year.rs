@@ -27,12 +27,13 @@ use libimagerror::trace::MapErrTrace;
 use libimagerror::iter::TraceIterator;
 use libimagstore::store::FileLockEntry;
 use libimagtimetrack::timetrackingstore::TimeTrackStore;
-use libimagtimetrack::timetracking::TimeTracking;
 use libimagtimetrack::tag::TimeTrackingTag;
 use libimagtimetrack::iter::filter::*;
 
 use libimagrt::runtime::Runtime;
 
+use list::print_timetrackings;
+
 pub fn year(rt: &Runtime) -> i32 {
     let cmd = rt.cli().subcommand().1.unwrap(); // checked in main
 
@@ -87,34 +88,7 @@ pub fn year(rt: &Runtime) -> i32 {
 
     rt.store()
         .get_timetrackings()
-        .and_then(|iter| {
-            iter.trace_unwrap()
-                .filter(|e| filter.filter(e))
-                .fold(Ok(()), |acc, e| {
-                    acc.and_then(|_| {
-                        debug!("Processing {:?}", e.get_location());
-
-                        let tag   = try!(e.get_timetrack_tag());
-                        debug!(" -> tag = {:?}", tag);
-
-                        let start = try!(e.get_start_datetime());
-                        debug!(" -> start = {:?}", start);
-
-                        let end   = try!(e.get_end_datetime());
-                        debug!(" -> end = {:?}", end);
-
-                        match (start, end) {
-                            (None, _)          => println!("{} has no start time.", tag),
-                            (Some(s), None)    => println!("{} | {} - ...", tag, s),
-                            (Some(s), Some(e)) => println!("{} | {} - {}", tag, s, e),
-                        }
-
-                        Ok(())
-                    })
-                })
-        })
-        .map(|_| 0)
         .map_err_trace()
+        .map(|iter| print_timetrackings(rt, iter.trace_unwrap().filter(|e| filter.filter(e))))
         .unwrap_or(1)
 }
-
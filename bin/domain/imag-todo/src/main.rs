@@ -27,6 +27,7 @@ extern crate toml_query;
 extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagtodo;
+extern crate libimagentrylist;
 
 use std::process::{Command, Stdio};
 use std::io::stdin;
@@ -36,6 +37,7 @@ use toml::Value;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagtodo::taskstore::TaskStore;
+use libimagentrylist::serializer::print_items;
 use libimagerror::trace::{MapErrTrace, trace_error, trace_error_exit};
 
 mod ui;
@@ -127,7 +129,14 @@ fn list(rt: &Runtime) {
             })
             .collect();
 
-            // compose a `task` call with them, ...
+            // print the uuids if the output is for programs, ...
+            let format = rt.output_format();
+            if !format.is_text() {
+                let _ = print_items(format, "tasks", &uuids, String::clone).map_err_trace_exit(1);
+                return;
+            }
+
+            // ... or compose a `task` call with them, ...
             let outstring = if verbose { // ... if verbose
                 let output = Command::new("task")
                     .stdin(Stdio::null())
@@ -17,12 +17,18 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fmt::Error as FmtError;
 use std::path::PathBuf;
 use std::ops::Deref;
 
 use toml::Value;
+use toml::value::Table;
 use clap::App;
 
+use libimagutil::schema::{ConfigSchema, ValueType};
+
 error_chain! {
     types {
         ConfigError, ConfigErrorKind, ResultExt, Result;
@@ -58,16 +64,77 @@ error_chain! {
 use self::ConfigErrorKind as CEK;
 use self::ConfigError as CE;
 
+/// The system wide configuration file
+pub const SYSTEM_CONFIG_FILE : &'static str = "/etc/imag/imagrc.toml";
+
+/// The directory next to the configuration file which contains configuration fragments
+pub const FRAGMENT_DIR : &'static str = "imagrc.d";
+
+/// The prefix of environment variables which set configuration values
+pub const ENV_PREFIX : &'static str = "IMAG_CFG_";
+
+/// Where a configuration value comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    /// A configuration file
+    File(PathBuf),
+
+    /// An `IMAG_CFG_*` environment variable
+    Env(String),
+
+    /// `--override-config` on the commandline
+    CommandLine,
+}
+
+impl Display for ConfigOrigin {
+
+    fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        match *self {
+            ConfigOrigin::File(ref path) => write!(fmt, "{}", path.display()),
+            ConfigOrigin::Env(ref var)   => write!(fmt, "environment variable {}", var),
+            ConfigOrigin::CommandLine    => write!(fmt, "commandline"),
+        }
+    }
+
+}
+
 /// `Configuration` object
 ///
 /// Holds all config variables which are globally available plus the configuration object from the
 /// config parser, which can be accessed.
+///
+/// The configuration is merged from several layers, where later layers override values of
+/// earlier ones:
+///
+/// 1. The system configuration file (`/etc/imag/imagrc.toml`)
+/// 1. The user configuration file (`imagrc.toml`, see `Configuration::new()`)
+/// 1. The fragments in `imagrc.d/*.toml` next to the user configuration file, in the order of
+///    their file names
+/// 1. The configuration file of the module next to the user configuration file, for example
+///    `imag-diary.toml`
+/// 1. `IMAG_CFG_*` environment variables, see `env_config_key()`
+/// 1. `--override-config` on the commandline
+///
+/// Tables are merged, all other values (including arrays) are replaced.
 #[derive(Debug)]
 pub struct Configuration {
 
     /// The plain configuration object for direct access if necessary
     config: Value,
 
+    /// The origin of each value, by the path of the value
+    origins: BTreeMap<String, ConfigOrigin>,
+
+    /// The origins of the layers, in the order they were merged
+    layers: Vec<ConfigOrigin>,
+
+    /// The user configuration file, if any
+    user_config_file: Option<PathBuf>,
+
+    /// The directory of the user configuration file, where the fragments and the module
+    /// configuration files are searched
+    config_dir: Option<PathBuf>,
+
     /// The verbosity the program should run with
     verbosity: bool,
 
@@ -80,43 +147,143 @@ pub struct Configuration {
 
 impl Configuration {
 
-    /// Get a new configuration object.
+    /// Get a new configuration object for the module `appname`.
     ///
-    /// The passed runtimepath is used for searching the configuration file, whereas several file
-    /// names are tested. If that does not work, the home directory and the XDG basedir are tested
-    /// with all variants.
+    /// The passed runtimepath is used for searching the user configuration file, whereas several
+    /// file names are tested. If that does not work, the home directory and the XDG basedir are
+    /// tested with all variants. The other layers of the configuration (see `Configuration`) are
+    /// loaded as well.
     ///
-    /// If that doesn't work either, an error is returned.
-    pub fn new(config_searchpath: &PathBuf) -> Result<Configuration> {
-        fetch_config(&config_searchpath).map(|cfg| {
-            let verbosity   = get_verbosity(&cfg);
-            let editor      = get_editor(&cfg);
-            let editor_opts = get_editor_opts(&cfg);
-
-            debug!("Building configuration");
-            debug!("  - verbosity  : {:?}", verbosity);
-            debug!("  - editor     : {:?}", editor);
-            debug!("  - editor-opts: {}", editor_opts);
-
-            Configuration {
-                config: cfg,
-                verbosity: verbosity,
-                editor: editor,
-                editor_opts: editor_opts,
+    /// If there is no configuration at all, an error is returned.
+    pub fn new(config_searchpath: &PathBuf, appname: &str) -> Result<Configuration> {
+        use std::env;
+
+        let mut layers = vec![];
+
+        let system = PathBuf::from(SYSTEM_CONFIG_FILE);
+        if let Some(value) = read_config_file(&system) {
+            layers.push((ConfigOrigin::File(system), value));
+        }
+
+        let user = fetch_config(config_searchpath);
+        let config_dir = match user {
+            Some((ref path, _)) => path.parent().map(PathBuf::from),
+            None if config_searchpath.is_dir() => Some(config_searchpath.clone()),
+            None => None,
+        };
+        let user_config_file = user.as_ref().map(|&(ref path, _)| path.clone());
+
+        if let Some((path, value)) = user {
+            layers.push((ConfigOrigin::File(path), value));
+        }
+
+        if let Some(ref dir) = config_dir {
+            for path in fragment_files(dir) {
+                if let Some(value) = read_config_file(&path) {
+                    layers.push((ConfigOrigin::File(path), value));
+                }
             }
-        })
+
+            let module = dir.join(format!("{}.toml", appname));
+            if let Some(value) = read_config_file(&module) {
+                layers.push((ConfigOrigin::File(module), value));
+            }
+        }
+
+        let mut vars : Vec<(String, String)> = env::vars()
+            .filter(|&(ref var, _)| var.starts_with(ENV_PREFIX))
+            .collect();
+        vars.sort();
+
+        for (var, value) in vars {
+            match env_config_key(&var) {
+                Some(key) => layers.push((ConfigOrigin::Env(var), env_layer(&key, &value))),
+                None      => warn!("Ignoring environment variable '{}'", var),
+            }
+        }
+
+        if layers.is_empty() {
+            return Err(CE::from_kind(ConfigErrorKind::NoConfigFileFound));
+        }
+
+        let mut config = Configuration::from_layers(layers);
+        config.user_config_file = user_config_file;
+        config.config_dir       = config_dir;
+        Ok(config)
+    }
+
+    /// Get a new configuration object by merging the `layers`, in order
+    pub fn from_layers(layers: Vec<(ConfigOrigin, Value)>) -> Configuration {
+        let mut config  = Table::new();
+        let mut origins = BTreeMap::new();
+        let mut merged  = vec![];
+
+        for (origin, layer) in layers {
+            debug!("Merging configuration from {}", origin);
+            match layer {
+                Value::Table(layer) => merge_table(&mut config, layer, "", &origin, &mut origins),
+                _ => warn!("Ignoring configuration from {}, it is not a table", origin),
+            }
+            merged.push(origin);
+        }
+
+        let mut config = Configuration::with_value(Value::Table(config));
+        config.origins = origins;
+        config.layers  = merged;
+        config
     }
 
     /// Get a new configuration object built from the given toml value.
     pub fn with_value(value: Value) -> Configuration {
-        Configuration{
-            verbosity: get_verbosity(&value),
-            editor: get_editor(&value),
-            editor_opts: get_editor_opts(&value),
+        let verbosity   = get_verbosity(&value);
+        let editor      = get_editor(&value);
+        let editor_opts = get_editor_opts(&value);
+
+        debug!("Building configuration");
+        debug!("  - verbosity  : {:?}", verbosity);
+        debug!("  - editor     : {:?}", editor);
+        debug!("  - editor-opts: {}", editor_opts);
+
+        Configuration {
             config: value,
+            origins: BTreeMap::new(),
+            layers: vec![],
+            user_config_file: None,
+            config_dir: None,
+            verbosity: verbosity,
+            editor: editor,
+            editor_opts: editor_opts,
         }
     }
 
+    /// Get the origin of the value at `key` (a path like `store.implicit-create`)
+    ///
+    /// Only values which are not tables have an origin, as tables can be merged from several
+    /// layers.
+    pub fn origin(&self, key: &str) -> Option<&ConfigOrigin> {
+        self.origins.get(key)
+    }
+
+    /// Get the origins of all values which are not tables, by their path
+    pub fn origins(&self) -> &BTreeMap<String, ConfigOrigin> {
+        &self.origins
+    }
+
+    /// Get the origins of the layers the configuration was merged from, in order
+    pub fn layers(&self) -> &[ConfigOrigin] {
+        &self.layers
+    }
+
+    /// Get the user configuration file, if one was found
+    pub fn user_config_file(&self) -> Option<&PathBuf> {
+        self.user_config_file.as_ref()
+    }
+
+    /// Get the directory where the fragments and module configuration files are searched
+    pub fn config_dir(&self) -> Option<&PathBuf> {
+        self.config_dir.as_ref()
+    }
+
     /// Get the Editor setting from the configuration
     pub fn editor(&self) -> Option<&String> {
         self.editor.as_ref()
@@ -145,11 +312,11 @@ impl Configuration {
     /// If `v` is empty, this is considered to be a successful `override_config()` call.
     pub fn override_config(&mut self, v: Vec<String>) -> Result<()> {
         use libimagutil::key_value_split::*;
-        use libimagutil::iter::*;
 
         use toml_query::read::TomlValueReadExt;
+        use toml_query::set::TomlValueSetExt;
 
-        v.into_iter()
+        let overrides = v.into_iter()
             .map(|s| { debug!("Trying to process '{}'", s); s })
             .filter_map(|s| match s.into_kv() {
                 Some(kv) => Some(kv.into()),
@@ -157,24 +324,140 @@ impl Configuration {
                     warn!("Could split at '=' - will be ignore override");
                     None
                 }
-            })
-            .map(|(k, v)| self
-                 .config
-                 .read(&k[..])
-                 .chain_err(|| CEK::TOMLParserError)
-                 .map(|toml| match toml {
-                    Some(value) => match into_value(value, v) {
-                        Some(v) => {
-                            info!("Successfully overridden: {} = {}", k, v);
-                            Ok(v)
-                        },
-                        None => Err(CE::from_kind(CEK::ConfigOverrideTypeNotMatching)),
-                    },
-                    None => Err(CE::from_kind(CEK::ConfigOverrideKeyNotAvailable)),
-                })
-            )
-            .fold_result(|i| i)
-            .chain_err(|| CEK::ConfigOverrideError)
+            });
+
+        for (k, v) in overrides {
+            let k : String = k;
+            let value = match try!(self.config.read(&k[..]).chain_err(|| CEK::ConfigOverrideError)) {
+                Some(value) => try!(into_value(value, v)
+                    .ok_or_else(|| CE::from_kind(CEK::ConfigOverrideTypeNotMatching))
+                    .chain_err(|| CEK::ConfigOverrideError)),
+                None => return Err(CE::from_kind(CEK::ConfigOverrideKeyNotAvailable))
+                    .chain_err(|| CEK::ConfigOverrideError),
+            };
+
+            let _ = try!(self.config.set(&k[..], value.clone()).chain_err(|| CEK::ConfigOverrideError));
+            info!("Successfully overridden: {} = {}", k, value);
+            self.origins.insert(k, ConfigOrigin::CommandLine);
+        }
+
+        self.verbosity   = get_verbosity(&self.config);
+        self.editor      = get_editor(&self.config);
+        self.editor_opts = get_editor_opts(&self.config);
+        Ok(())
+    }
+}
+
+/// Get the configuration key for the environment variable `var`
+///
+/// The `IMAG_CFG_` prefix is removed, `__` separates the parts of the key, the parts are
+/// lowercased and `_` is replaced by `-`. For example, `IMAG_CFG_STORE__IMPLICIT_CREATE` is the
+/// variable for `store.implicit-create`.
+///
+/// Returns None if `var` is not such a variable.
+pub fn env_config_key(var: &str) -> Option<String> {
+    if !var.starts_with(ENV_PREFIX) {
+        return None;
+    }
+
+    let parts : Vec<String> = var[ENV_PREFIX.len()..]
+        .split("__")
+        .map(|part| part.to_lowercase().replace("_", "-"))
+        .collect();
+
+    if parts.iter().any(|part| part.is_empty()) {
+        None
+    } else {
+        Some(parts.join("."))
+    }
+}
+
+/// Parse a configuration value as given on the commandline or in an environment variable
+///
+/// The value is parsed as TOML value (for example `true`, `5000` or `[ "a", "b" ]`). If that
+/// fails, it is used as string.
+pub fn parse_config_value(s: &str) -> Value {
+    match ::toml::de::from_str::<Value>(&format!("value = {}", s)) {
+        Ok(Value::Table(mut t)) => t.remove("value").unwrap_or_else(|| Value::String(String::from(s))),
+        _                       => Value::String(String::from(s)),
+    }
+}
+
+/// Build the configuration layer which sets `key` to the value `value`
+pub fn env_layer(key: &str, value: &str) -> Value {
+    key.split('.')
+        .rev()
+        .fold(parse_config_value(value), |value, part| {
+            let mut table = Table::new();
+            table.insert(String::from(part), value);
+            Value::Table(table)
+        })
+}
+
+/// The keys read by the runtime, including the logging configuration
+pub fn config_schema() -> ConfigSchema {
+    ConfigSchema::new()
+        .key("verbose",                             ValueType::Boolean)
+        .key("editor",                              ValueType::String)
+        .key("editor-opts",                         ValueType::String)
+
+        .key("imag.logging.level",                  ValueType::String)
+        .key("imag.logging.destinations",           ValueType::Array)
+        .key("imag.logging.format.trace",           ValueType::String)
+        .key("imag.logging.format.debug",           ValueType::String)
+        .key("imag.logging.format.info",            ValueType::String)
+        .key("imag.logging.format.warn",            ValueType::String)
+        .key("imag.logging.format.error",           ValueType::String)
+        .key("imag.logging.modules.*.destinations", ValueType::Array)
+        .key("imag.logging.modules.*.level",        ValueType::String)
+        .key("imag.logging.modules.*.enabled",      ValueType::Boolean)
+}
+
+/// Merge `layer` into `base`, recording the origin of each value which is set
+///
+/// `prefix` is the path of the tables in the configuration.
+fn merge_table(base: &mut Table,
+               layer: Table,
+               prefix: &str,
+               origin: &ConfigOrigin,
+               origins: &mut BTreeMap<String, ConfigOrigin>)
+{
+    for (k, v) in layer {
+        let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+
+        let both_tables = match (base.get(&k), &v) {
+            (Some(&Value::Table(_)), &Value::Table(_)) => true,
+            _                                          => false,
+        };
+
+        if both_tables {
+            if let (Some(&mut Value::Table(ref mut base)), Value::Table(layer)) = (base.get_mut(&k), v) {
+                merge_table(base, layer, &key, origin, origins);
+            }
+        } else {
+            let below = format!("{}.", key);
+            let old : Vec<String> = origins
+                .keys()
+                .filter(|o| **o == key || o.starts_with(&below))
+                .cloned()
+                .collect();
+            for o in old {
+                origins.remove(&o);
+            }
+
+            record_origins(&v, &key, origin, origins);
+            base.insert(k, v);
+        }
+    }
+}
+
+/// Record `origin` for `value` at `key` and all values in it, if it is a table
+fn record_origins(value: &Value, key: &str, origin: &ConfigOrigin, origins: &mut BTreeMap<String, ConfigOrigin>) {
+    match *value {
+        Value::Table(ref t) if !t.is_empty() => for (k, v) in t.iter() {
+            record_origins(v, &format!("{}.{}", key, k), origin, origins);
+        },
+        _ => { origins.insert(String::from(key), origin.clone()); },
     }
 }
 
@@ -237,18 +520,13 @@ fn get_editor_opts(v: &Value) -> String {
 /// Helper to fetch the config file
 ///
 /// Tests several variants for the config file path and uses the first one which works.
-fn fetch_config(searchpath: &PathBuf) -> Result<Value> {
+fn fetch_config(searchpath: &PathBuf) -> Option<(PathBuf, Value)> {
     use std::env;
-    use std::fs::File;
-    use std::io::Read;
-    use std::io::Write;
-    use std::io::stderr;
 
     use xdg_basedir;
     use itertools::Itertools;
 
     use libimagutil::variants::generate_variants as gen_vars;
-    use libimagerror::trace::trace_error;
 
     let variants = vec!["config", "config.toml", "imagrc", "imagrc.toml"];
     let modifier = |base: &PathBuf, v: &'static str| {
@@ -270,37 +548,72 @@ fn fetch_config(searchpath: &PathBuf) -> Result<Value> {
         .flatten()
         .filter(|path| path.exists() && path.is_file())
         .filter_map(|path| {
-            let content = {
-                let f = File::open(path);
-                if f.is_err() {
-                    let _ = write!(stderr(), "Error opening file: {:?}", f);
-                    return None
-                }
-                let mut f = f.unwrap();
+            read_config_file(path).map(|value| (path.clone(), value))
+        })
+        .nth(0)
+}
 
-                let mut s = String::new();
-                f.read_to_string(&mut s).ok();
-                s
-            };
+/// Get the configuration fragments in `imagrc.d` in `config_dir`, sorted by file name
+fn fragment_files(config_dir: &PathBuf) -> Vec<PathBuf> {
+    use std::fs::read_dir;
 
-            match ::toml::de::from_str::<::toml::Value>(&content[..]) {
-                Ok(res) => Some(res),
-                Err(e) => {
-                    let line_col = e
-                        .line_col()
-                        .map(|(line, col)| {
-                            format!("Line {}, Column {}", line, col)
-                        })
-                        .unwrap_or_else(|| String::from("Line unknown, Column unknown"));
-
-                    let _ = write!(stderr(), "Config file parser error at {}", line_col);
-                    trace_error(&e);
-                    None
-                }
-            }
+    let mut files : Vec<PathBuf> = read_dir(config_dir.join(FRAGMENT_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().map(|ext| ext == "toml").unwrap_or(false))
+                .collect()
         })
-        .nth(0)
-        .ok_or(CE::from_kind(ConfigErrorKind::NoConfigFileFound))
+        .unwrap_or_default();
+
+    files.sort();
+    files
+}
+
+/// Read and parse the configuration file at `path`
+///
+/// Returns None if the file does not exist. If it cannot be read or parsed, the error is printed
+/// and None is returned.
+fn read_config_file(path: &PathBuf) -> Option<Value> {
+    use std::fs::File;
+    use std::io::Read;
+    use std::io::Write;
+    use std::io::stderr;
+
+    use libimagerror::trace::trace_error;
+
+    if !(path.exists() && path.is_file()) {
+        return None;
+    }
+
+    let content = {
+        let f = File::open(path);
+        if f.is_err() {
+            let _ = write!(stderr(), "Error opening file: {:?}", f);
+            return None
+        }
+        let mut f = f.unwrap();
+
+        let mut s = String::new();
+        f.read_to_string(&mut s).ok();
+        s
+    };
+
+    match ::toml::de::from_str::<::toml::Value>(&content[..]) {
+        Ok(res) => Some(res),
+        Err(e) => {
+            let line_col = e
+                .line_col()
+                .map(|(line, col)| {
+                    format!("Line {}, Column {}", line, col)
+                })
+                .unwrap_or_else(|| String::from("Line unknown, Column unknown"));
+
+            let _ = write!(stderr(), "Config file parser error in {:?} at {}", path, line_col);
+            trace_error(&e);
+            None
+        }
+    }
 }
 
 pub trait InternalConfiguration {
@@ -314,3 +627,95 @@ pub trait InternalConfiguration {
 }
 
 impl<'a> InternalConfiguration for App<'a, 'a> {}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use toml::Value;
+    use toml::de::from_str;
+    use toml_query::read::TomlValueReadExt;
+
+    use super::*;
+
+    fn file(name: &str) -> ConfigOrigin {
+        ConfigOrigin::File(PathBuf::from(name))
+    }
+
+    #[test]
+    fn test_layers_are_merged_in_order() {
+        let system : Value = from_str(r#"
+            editor = "vi"
+            [store]
+            implicit-create = false
+            lock-timeout = 5000
+        "#).unwrap();
+        let user : Value = from_str(r#"
+            [store]
+            implicit-create = true
+            [store.hooks.git-update]
+            aspect = "vcs"
+        "#).unwrap();
+        let module : Value = from_str(r#"
+            editor = "emacs"
+            [store]
+            hooks = []
+        "#).unwrap();
+
+        // In the order of `Configuration::new()`: the environment overrides the module file
+        let config = Configuration::from_layers(vec![
+            (file("system"), system),
+            (file("user"), user),
+            (file("module"), module),
+            (ConfigOrigin::Env(String::from("IMAG_CFG_EDITOR")), env_layer("editor", "nano")),
+        ]);
+
+        assert_eq!(config.editor(), Some(&String::from("nano")));
+        assert_eq!(config.read("store.implicit-create").unwrap(), Some(&Value::Boolean(true)));
+        assert_eq!(config.read("store.lock-timeout").unwrap(), Some(&Value::Integer(5000)));
+        assert_eq!(config.read("store.hooks").unwrap(), Some(&Value::Array(vec![])));
+
+        assert_eq!(config.origin("editor"), Some(&ConfigOrigin::Env(String::from("IMAG_CFG_EDITOR"))));
+        assert_eq!(config.origin("store.implicit-create"), Some(&file("user")));
+        assert_eq!(config.origin("store.lock-timeout"), Some(&file("system")));
+        assert_eq!(config.origin("store.hooks"), Some(&file("module")));
+        assert_eq!(config.origin("store.hooks.git-update.aspect"), None);
+        assert_eq!(config.origin("store"), None);
+    }
+
+    #[test]
+    fn test_env_config_key() {
+        assert_eq!(env_config_key("IMAG_CFG_EDITOR"), Some(String::from("editor")));
+        assert_eq!(env_config_key("IMAG_CFG_STORE__IMPLICIT_CREATE"),
+                   Some(String::from("store.implicit-create")));
+        assert_eq!(env_config_key("IMAG_CFG_STORE____X"), None);
+        assert_eq!(env_config_key("HOME"), None);
+    }
+
+    #[test]
+    fn test_parse_config_value() {
+        assert_eq!(parse_config_value("true"), Value::Boolean(true));
+        assert_eq!(parse_config_value("42"), Value::Integer(42));
+        assert_eq!(parse_config_value("\"42\""), Value::String(String::from("42")));
+        assert_eq!(parse_config_value("vim -p"), Value::String(String::from("vim -p")));
+        assert_eq!(parse_config_value("[1, 2]"),
+                   Value::Array(vec![Value::Integer(1), Value::Integer(2)]));
+    }
+
+    #[test]
+    fn test_override_config() {
+        let value : Value = from_str(r#"
+            [store]
+            implicit-create = false
+        "#).unwrap();
+        let mut config = Configuration::from_layers(vec![(file("user"), value)]);
+
+        config.override_config(vec![String::from("store.implicit-create=true")]).unwrap();
+        assert_eq!(config.read("store.implicit-create").unwrap(), Some(&Value::Boolean(true)));
+        assert_eq!(config.origin("store.implicit-create"), Some(&ConfigOrigin::CommandLine));
+
+        assert!(config.override_config(vec![String::from("store.implicit-create=x")]).is_err());
+        assert!(config.override_config(vec![String::from("store.missing=1")]).is_err());
+    }
+
+}
@@ -48,6 +48,11 @@ error_chain! {
             display("Error while reading the configuration")
         }
 
+        HookRegisterError {
+            description("Error while registering hooks")
+            display("Error while registering hooks")
+        }
+
         ConfigTypeError {
             description("Error while reading the configuration: Type Error")
             display("Error while reading the configuration: Type Error")
@@ -42,6 +42,8 @@ extern crate itertools;
 extern crate env_logger;
 extern crate ansi_term;
 extern crate handlebars;
+extern crate chrono;
+#[macro_use] extern crate serde_json;
 
 extern crate clap;
 extern crate toml;
@@ -49,12 +51,14 @@ extern crate toml_query;
 #[macro_use] extern crate is_match;
 
 extern crate libimagstore;
+extern crate libimagstorestdhook;
 extern crate libimagutil;
 extern crate libimagerror;
 
 pub mod error;
 pub mod configuration;
 pub mod logger;
+pub mod output;
 pub mod runtime;
 pub mod setup;
 pub mod spec;
@@ -20,6 +20,7 @@
 use std::io::Write;
 use std::io::stderr;
 use std::collections::BTreeMap;
+use std::fs::File;
 
 use configuration::Configuration;
 use error::RuntimeErrorKind as EK;
@@ -32,18 +33,52 @@ use log::{Log, LogLevel, LogRecord, LogMetadata};
 use toml::Value;
 use toml_query::read::TomlValueReadExt;
 use handlebars::Handlebars;
+use chrono::Local;
 
 type ModuleName = String;
 type Result<T> = ::std::result::Result<T, RE>;
 
-enum LogDestination {
+/// The format a destination is written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LogFormat {
+    /// The handlebars format from `imag.logging.format`
+    Text,
+
+    /// One JSON object per line
+    Json,
+
+    /// RFC 5424 syslog messages
+    Syslog,
+}
+
+enum LogTarget {
     Stderr,
-    File(::std::fs::File),
+    File(File),
+}
+
+struct LogDestination {
+    target: LogTarget,
+    format: LogFormat,
+}
+
+impl LogDestination {
+
+    fn write(&self, line: &str) {
+        let line = format!("{}\n", line);
+        let _ = match self.target {
+            LogTarget::Stderr       => stderr().write_all(line.as_bytes()),
+            LogTarget::File(ref f)  => { let mut f : &File = f; f.write_all(line.as_bytes()) },
+        };
+    }
+
 }
 
 impl Default for LogDestination {
     fn default() -> LogDestination {
-        LogDestination::Stderr
+        LogDestination {
+            target: LogTarget::Stderr,
+            format: LogFormat::Text,
+        }
     }
 }
 
@@ -51,15 +86,71 @@ struct ModuleSettings {
     enabled:        bool,
     level:          Option<LogLevel>,
 
-    #[allow(unused)]
+    /// Destinations which are used for this module instead of the global ones
     destinations:   Option<Vec<LogDestination>>,
 }
 
+/// The data of a log record which is written to the structured destinations
+struct LogLine<'a> {
+    level       : LogLevel,
+    module_path : &'a str,
+    file        : &'a str,
+    line        : u32,
+    timestamp   : String,
+    message     : String,
+}
+
+impl<'a> LogLine<'a> {
+
+    fn from_record(record: &'a LogRecord) -> LogLine<'a> {
+        LogLine {
+            level       : record.level(),
+            module_path : record.location().module_path(),
+            file        : record.location().file(),
+            line        : record.location().line(),
+            timestamp   : Local::now().format("%Y-%m-%dT%H:%M:%S%.6f%:z").to_string(),
+            message     : format!("{}", record.args()),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let obj = json!({
+            "level":       format!("{}", self.level),
+            "module_path": self.module_path,
+            "file":        self.file,
+            "line":        self.line,
+            "timestamp":   self.timestamp,
+            "message":     self.message,
+        });
+
+        obj.to_string()
+    }
+
+    /// Format the line as RFC 5424 message with the "user" facility. The hostname is left out
+    /// ("-"), the module path is prepended to the message.
+    fn to_syslog(&self, appname: &str, pid: u32) -> String {
+        let severity = match self.level {
+            LogLevel::Error => 3,
+            LogLevel::Warn  => 4,
+            LogLevel::Info  => 6,
+            LogLevel::Debug => 7,
+            LogLevel::Trace => 7,
+        };
+
+        format!("<{}>1 {} - {} {} - - {}: {}",
+                8 + severity, // facility "user"
+                self.timestamp,
+                appname,
+                pid,
+                self.module_path,
+                self.message)
+    }
+
+}
+
 /// Logger implementation for `log` crate.
 pub struct ImagLogger {
     global_loglevel     : LogLevel,
-
-    #[allow(unused)]
     global_destinations : Vec<LogDestination>,
     // global_format_trace : ,
     // global_format_debug : ,
@@ -69,6 +160,9 @@ pub struct ImagLogger {
     module_settings     : BTreeMap<ModuleName, ModuleSettings>,
 
     handlebars: Handlebars,
+
+    /// Name of the program, used in syslog messages
+    appname: String,
 }
 
 impl ImagLogger {
@@ -122,6 +216,7 @@ impl ImagLogger {
             global_destinations : try!(aggregate_global_destinations(matches, config)),
             module_settings     : try!(aggregate_module_settings(matches, config)),
             handlebars          : handlebars,
+            appname             : appname(),
         })
     }
 
@@ -150,6 +245,55 @@ impl Log for ImagLogger {
             return;
         }
 
+        let (set, destinations) = match self.module_setting(record.target()) {
+            Some(module_setting) => {
+                let set = module_setting.enabled &&
+                    module_setting.level.unwrap_or(self.global_loglevel) >= record.level();
+                let destinations = module_setting
+                    .destinations
+                    .as_ref()
+                    .unwrap_or(&self.global_destinations);
+
+                (set, destinations)
+            },
+            None => (self.global_loglevel >= record.level(), &self.global_destinations),
+        };
+
+        if !set {
+            return;
+        }
+
+        let line = LogLine::from_record(record);
+
+        for destination in destinations {
+            let logtext = match destination.format {
+                LogFormat::Text   => self.render_text(record),
+                LogFormat::Json   => line.to_json(),
+                LogFormat::Syslog => line.to_syslog(&self.appname, ::std::process::id()),
+            };
+
+            destination.write(&logtext);
+        }
+    }
+}
+
+impl ImagLogger {
+
+    /// Get the settings for the module the target belongs to
+    ///
+    /// The settings of the most specific module are used, so settings for "libimagstore" apply
+    /// to "libimagstore::store" unless there are settings for "libimagstore::store".
+    fn module_setting(&self, target: &str) -> Option<&ModuleSettings> {
+        self.module_settings
+            .iter()
+            .rev() // longer names of the same module come later
+            .find(|&(name, _)| {
+                target == name || (target.starts_with(name.as_str()) && target[name.len()..].starts_with("::"))
+            })
+            .map(|(_, settings)| settings)
+    }
+
+    fn render_text(&self, record: &LogRecord) -> String {
         let mut data = BTreeMap::new();
 
         {
@@ -161,28 +305,24 @@ impl Log for ImagLogger {
             data.insert("message",      format!("{}", record.args()));
         }
 
-        let logtext = self
-            .handlebars
+        self.handlebars
             .render(&format!("{}", record.level()), &data)
-            .unwrap_or_else(|e| format!("Failed rendering logging data: {:?}\n", e));
+            .unwrap_or_else(|e| format!("Failed rendering logging data: {:?}\n", e))
+    }
 
-        self.module_settings
-            .get(record.target())
-            .map(|module_setting| {
-                let set = module_setting.enabled &&
-                    module_setting.level.unwrap_or(self.global_loglevel) >= record.level();
+}
 
-                if set {
-                    let _ = write!(stderr(), "{}\n", logtext);
-                }
-            })
-            .unwrap_or_else(|| {
-                if self.global_loglevel >= record.level() {
-                    // Yes, we log
-                    let _ = write!(stderr(), "{}\n", logtext);
-                }
-            });
-    }
+/// The name of the running program, "imag" if it cannot be found
+fn appname() -> String {
+    ::std::env::args()
+        .next()
+        .and_then(|arg0| {
+            ::std::path::Path::new(&arg0)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(String::from)
+        })
+        .unwrap_or_else(|| String::from("imag"))
 }
 
 fn match_log_level_str(s: &str) -> Result<LogLevel> {
@@ -222,20 +362,37 @@ fn aggregate_global_loglevel(matches: &ArgMatches, config: Option<&Configuration
     }
 }
 
+/// Translate a destination specification
+///
+/// A destination is "-" for stderr or the path of a file, optionally prefixed with "json:" or
+/// "syslog:" to select the format the destination is written in.
 fn translate_destination(raw: &str) -> Result<LogDestination> {
     use std::fs::OpenOptions;
 
-    match raw {
-        "-" => Ok(LogDestination::Stderr),
+    let (format, raw) = if raw.starts_with("json:") {
+        (LogFormat::Json, &raw["json:".len()..])
+    } else if raw.starts_with("syslog:") {
+        (LogFormat::Syslog, &raw["syslog:".len()..])
+    } else {
+        (LogFormat::Text, raw)
+    };
+
+    let target = match raw {
+        "-" => LogTarget::Stderr,
         other => {
-            OpenOptions::new()
+            try!(OpenOptions::new()
                 .append(true)
                 .create(true)
                 .open(other)
-                .map(LogDestination::File)
-                .chain_err(|| EK::IOLogFileOpenError)
+                .map(LogTarget::File)
+                .chain_err(|| EK::IOLogFileOpenError))
         }
-    }
+    };
+
+    Ok(LogDestination {
+        target: target,
+        format: format,
+    })
 }
 
 
@@ -563,3 +720,85 @@ mod template_helpers {
 
 }
 
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{ImagLogger, ModuleSettings, LogLine, LogFormat, LogTarget, translate_destination};
+
+    use handlebars::Handlebars;
+    use log::LogLevel;
+    use serde_json::Value;
+
+    fn line() -> LogLine<'static> {
+        LogLine {
+            level       : LogLevel::Warn,
+            module_path : "libimagrt::logger",
+            file        : "lib/core/libimagrt/src/logger.rs",
+            line        : 42,
+            timestamp   : String::from("2017-08-01T12:00:00.000000+02:00"),
+            message     : String::from("a \"quoted\" message"),
+        }
+    }
+
+    #[test]
+    fn test_json_line() {
+        let json : Value = ::serde_json::from_str(&line().to_json()).unwrap();
+
+        assert_eq!(json["level"],       Value::String(String::from("WARN")));
+        assert_eq!(json["module_path"], Value::String(String::from("libimagrt::logger")));
+        assert_eq!(json["file"],        Value::String(String::from("lib/core/libimagrt/src/logger.rs")));
+        assert_eq!(json["line"],        json!(42));
+        assert_eq!(json["timestamp"],   Value::String(String::from("2017-08-01T12:00:00.000000+02:00")));
+        assert_eq!(json["message"],     Value::String(String::from("a \"quoted\" message")));
+    }
+
+    #[test]
+    fn test_syslog_line() {
+        assert_eq!(line().to_syslog("imag-diary", 1234),
+                   "<12>1 2017-08-01T12:00:00.000000+02:00 - imag-diary 1234 - - libimagrt::logger: a \"quoted\" message");
+    }
+
+    #[test]
+    fn test_translate_destination() {
+        let dest = translate_destination("-").unwrap();
+        assert_eq!(dest.format, LogFormat::Text);
+        assert!(is_match!(dest.target, LogTarget::Stderr));
+
+        let dest = translate_destination("json:-").unwrap();
+        assert_eq!(dest.format, LogFormat::Json);
+        assert!(is_match!(dest.target, LogTarget::Stderr));
+
+        let dest = translate_destination("syslog:-").unwrap();
+        assert_eq!(dest.format, LogFormat::Syslog);
+        assert!(is_match!(dest.target, LogTarget::Stderr));
+    }
+
+    #[test]
+    fn test_module_setting() {
+        let mut module_settings = BTreeMap::new();
+        for &(name, level) in &[("libimagstore", LogLevel::Info), ("libimagstore::store", LogLevel::Trace)] {
+            let settings = ModuleSettings { enabled: true, level: Some(level), destinations: None };
+            module_settings.insert(String::from(name), settings);
+        }
+
+        let logger = ImagLogger {
+            global_loglevel     : LogLevel::Warn,
+            global_destinations : vec![],
+            module_settings     : module_settings,
+            handlebars          : Handlebars::new(),
+            appname             : String::from("imag"),
+        };
+
+        let level = |target| logger.module_setting(target).and_then(|s| s.level);
+
+        assert_eq!(level("libimagstore"),                Some(LogLevel::Info));
+        assert_eq!(level("libimagstore::storeid"),       Some(LogLevel::Info));
+        assert_eq!(level("libimagstore::store"),         Some(LogLevel::Trace));
+        assert_eq!(level("libimagstore::store::inner"),  Some(LogLevel::Trace));
+        assert_eq!(level("libimagstorestdhook::vcs"),    None);
+        assert_eq!(level("libimagrt"),                   None);
+    }
+
+}
@@ -0,0 +1,95 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+/// The format in which binaries print the results of listing and query commands
+///
+/// The format is selected with the `--output` commandline argument, see
+/// `Runtime::output_format()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The human readable output of the binary
+    Text,
+
+    /// A JSON document
+    Json,
+
+    /// A TOML document
+    Toml,
+}
+
+impl OutputFormat {
+
+    /// Get the format for a name as passed to `--output`
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "toml" => Some(OutputFormat::Toml),
+            _      => None,
+        }
+    }
+
+    /// Get the names of all formats
+    pub fn names() -> &'static [&'static str] {
+        &["text", "json", "toml"]
+    }
+
+    /// Whether the output is meant to be read by humans rather than by programs
+    pub fn is_text(&self) -> bool {
+        *self == OutputFormat::Text
+    }
+
+}
+
+impl Default for OutputFormat {
+    fn default() -> OutputFormat {
+        OutputFormat::Text
+    }
+}
+
+impl Display for OutputFormat {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        write!(fmt, "{}", match *self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Toml => "toml",
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::OutputFormat;
+
+    #[test]
+    fn test_names() {
+        for name in OutputFormat::names() {
+            let format = OutputFormat::from_name(name).unwrap();
+            assert_eq!(*name, format.to_string());
+        }
+
+        assert_eq!(None, OutputFormat::from_name("yaml"));
+    }
+
+}
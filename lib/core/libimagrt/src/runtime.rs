@@ -17,8 +17,11 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::io::{Read, Stdin, Write};
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::env;
 use std::process::exit;
 
@@ -32,9 +35,11 @@ use error::RuntimeError;
 use error::RuntimeErrorKind;
 use error::ResultExt;
 use logger::ImagLogger;
+use output::OutputFormat;
 
 use libimagerror::trace::*;
 use libimagstore::store::Store;
+use libimagstore::file_abstraction::FileAbstraction;
 use libimagstore::file_abstraction::InMemoryFileAbstraction;
 use spec::CliSpec;
 
@@ -72,7 +77,7 @@ impl<'a> Runtime<'a> {
 
         debug!("Config path = {:?}", configpath);
 
-        let config = match Configuration::new(&configpath) {
+        let config = match Configuration::new(&configpath, cli_app.name()) {
             Err(e) => if !is_match!(e.kind(), &ConfigErrorKind::NoConfigFileFound) {
                 return Err(e).chain_err(|| RuntimeErrorKind::Instantiate);
             } else {
@@ -152,8 +157,15 @@ impl<'a> Runtime<'a> {
             Store::new_with_backend(storepath,
                                     store_config,
                                     Box::new(InMemoryFileAbstraction::new()))
+                .chain_err(|| RuntimeErrorKind::Instantiate)
         } else {
             Store::new(storepath, store_config)
+                .chain_err(|| RuntimeErrorKind::Instantiate)
+                .and_then(|store| {
+                    register_std_hooks(&store, cli_app.name(), &rtp)
+                        .chain_err(|| RuntimeErrorKind::Instantiate)
+                        .map(|_| store)
+                })
         };
 
         store_result.map(|store| {
@@ -164,7 +176,6 @@ impl<'a> Runtime<'a> {
                 store: store,
             }
         })
-        .chain_err(|| RuntimeErrorKind::Instantiate)
     }
 
     ///
@@ -248,9 +259,25 @@ impl<'a> Runtime<'a> {
                 .value_name("SHELL")
                 .possible_values(&["bash", "fish", "zsh"]))
 
+            .arg(Arg::with_name(Runtime::arg_stdio_format_name())
+                .long(Runtime::arg_stdio_format_name())
+                .help("The format of the store if it is read from stdin or written to stdout")
+                .required(false)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["json", "toml", "yaml"]))
+
+            .arg(Arg::with_name(Runtime::arg_output_format_name())
+                .long(Runtime::arg_output_format_name())
+                .help("The format in which listing and query commands print their results")
+                .required(false)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(OutputFormat::names()))
+
             .arg(Arg::with_name(Runtime::arg_logdest_name())
                 .long(Runtime::arg_logdest_name())
-                .help("Override the logging destinations from the configuration: values can be seperated by ',', a value of '-' marks the stderr output, everything else is expected to be a path")
+                .help("Override the logging destinations from the configuration: values can be seperated by ',', a value of '-' marks the stderr output, everything else is expected to be a path. Prefix a value with 'json:' or 'syslog:' to write JSON lines or syslog messages")
                 .required(false)
                 .takes_value(true)
                 .value_name("LOGDESTS"))
@@ -387,6 +414,16 @@ impl<'a> Runtime<'a> {
         self
     }
 
+    /// Get the argument name for the format of the store on stdin/stdout
+    pub fn arg_stdio_format_name() -> &'static str {
+        "stdio-format"
+    }
+
+    /// Get the argument name for the output format
+    pub fn arg_output_format_name() -> &'static str {
+        "output"
+    }
+
     /// Get the argument name for the logging destination
     pub fn arg_logdest_name() -> &'static str {
         "logging-destinations"
@@ -467,48 +504,90 @@ impl<'a> Runtime<'a> {
         &self.store
     }
 
-    /// Change the store backend to stdout
+    /// Get the format in which listing and query commands print their results
+    ///
+    /// The format is selected with the `--output` argument and defaults to
+    /// `OutputFormat::Text`. Use the `libimagentrylist::serializer` module to print the results.
+    pub fn output_format(&self) -> OutputFormat {
+        self.cli()
+            .value_of(Runtime::arg_output_format_name())
+            .and_then(OutputFormat::from_name)
+            .unwrap_or_default()
+    }
+
+    /// Change the store backend to stdin/stdout
     ///
     /// For the documentation on purpose and cavecats, have a look at the documentation of the
     /// `Store::reset_backend()` function.
     ///
+    /// The format of the store on stdin/stdout is selected with the `--stdio-format` argument and
+    /// defaults to JSON.
+    ///
+    /// The hooks which were registered for the filesystem store (like the git or search index
+    /// hooks) are unregistered, as they must not act on entries which are not in the filesystem.
+    ///
     pub fn store_backend_to_stdio(&mut self) -> Result<(), RuntimeError> {
-        use libimagstore::file_abstraction::stdio::*;
-        use libimagstore::file_abstraction::stdio::mapper::json::JsonMapper;
-        use std::rc::Rc;
-        use std::cell::RefCell;
+        let mut input = ::std::io::stdin();
+        let output    = Arc::new(Mutex::new(::std::io::stdout()));
+        let base      = self.store.path().clone();
+
+        stdio_backend(self.stdio_format(), Some(&mut input), output, base)
+            .and_then(|backend| self.reset_stdio_backend(backend))
+    }
 
+    /// Change the store backend to stdin
+    ///
+    /// Works like `Runtime::store_backend_to_stdio()`, but the store is not written to stdout
+    /// afterwards. Use this to read a store from stdin and persist it with
+    /// `Runtime::store_backend_to_filesystem()`.
+    ///
+    pub fn store_backend_to_stdin(&mut self) -> Result<(), RuntimeError> {
         let mut input = ::std::io::stdin();
-        let output    = ::std::io::stdout();
-        let output    = Rc::new(RefCell::new(output));
-        let mapper    = JsonMapper::new();
+        let output    = Arc::new(Mutex::new(::std::io::sink()));
+        let base      = self.store.path().clone();
 
-        StdIoFileAbstraction::new(&mut input, output, mapper)
-            .chain_err(|| RuntimeErrorKind::Instantiate)
-            .and_then(|backend| {
-                self.store
-                    .reset_backend(Box::new(backend))
-                    .chain_err(|| RuntimeErrorKind::Instantiate)
-            })
+        stdio_backend(self.stdio_format(), Some(&mut input), output, base)
+            .and_then(|backend| self.reset_stdio_backend(backend))
     }
 
+    /// Change the store backend to stdout
+    ///
+    /// Works like `Runtime::store_backend_to_stdio()`, but without reading a store from stdin.
+    ///
     pub fn store_backend_to_stdout(&mut self) -> Result<(), RuntimeError> {
-        use libimagstore::file_abstraction::stdio::mapper::json::JsonMapper;
-        use libimagstore::file_abstraction::stdio::out::StdoutFileAbstraction;
-        use std::rc::Rc;
-        use std::cell::RefCell;
+        let output = Arc::new(Mutex::new(::std::io::stdout()));
+        let base   = self.store.path().clone();
+
+        stdio_backend::<Stdin, _>(self.stdio_format(), None, output, base)
+            .and_then(|backend| self.reset_stdio_backend(backend))
+    }
 
-        let output    = ::std::io::stdout();
-        let output    = Rc::new(RefCell::new(output));
-        let mapper    = JsonMapper::new();
+    /// Get the format of the store on stdin/stdout
+    fn stdio_format(&self) -> &str {
+        self.cli().value_of(Runtime::arg_stdio_format_name()).unwrap_or("json")
+    }
 
-        StdoutFileAbstraction::new(output, mapper)
+    fn reset_stdio_backend(&mut self, backend: Box<FileAbstraction>) -> Result<(), RuntimeError> {
+        self.store
+            .reset_backend(backend)
+            .and_then(|_| self.store.unregister_hooks())
+            .chain_err(|| RuntimeErrorKind::Instantiate)
+    }
+
+    /// Change the store backend to the filesystem
+    ///
+    /// All entries of the current backend are written to the filesystem, overwriting existing
+    /// files. The hooks are not executed for these writes.
+    ///
+    /// For the documentation on purpose and cavecats, have a look at the documentation of the
+    /// `Store::reset_backend()` function.
+    ///
+    pub fn store_backend_to_filesystem(&mut self) -> Result<(), RuntimeError> {
+        use libimagstore::file_abstraction::FSFileAbstraction;
+
+        self.store
+            .reset_backend(Box::new(FSFileAbstraction::new()))
             .chain_err(|| RuntimeErrorKind::Instantiate)
-            .and_then(|backend| {
-                self.store
-                    .reset_backend(Box::new(backend))
-                    .chain_err(|| RuntimeErrorKind::Instantiate)
-            })
     }
 
     /// Get a editor command object which can be called to open the $EDITOR
@@ -557,3 +636,102 @@ fn get_override_specs(matches: &ArgMatches) -> Vec<String> {
         .unwrap_or(vec![])
 }
 
+/// Build a backend for the store on stdin/stdout in the format `format`
+///
+/// If there is no `input`, the backend starts with an empty store.
+fn stdio_backend<R, W>(format: &str, input: Option<&mut R>, output: Arc<Mutex<W>>, base: PathBuf)
+    -> Result<Box<FileAbstraction>, RuntimeError>
+    where R: Read,
+          W: Write + Send + 'static
+{
+    use libimagstore::error::StoreError;
+    use libimagstore::file_abstraction::stdio::StdIoFileAbstraction;
+    use libimagstore::file_abstraction::stdio::out::StdoutFileAbstraction;
+    use libimagstore::file_abstraction::stdio::mapper::Mapper;
+    use libimagstore::file_abstraction::stdio::mapper::json::JsonMapper;
+    use libimagstore::file_abstraction::stdio::mapper::toml::TomlMapper;
+    use libimagstore::file_abstraction::stdio::mapper::yaml::YamlMapper;
+
+    fn build<R, W, M>(input: Option<&mut R>, output: Arc<Mutex<W>>, mapper: M, base: PathBuf)
+        -> Result<Box<FileAbstraction>, StoreError>
+        where R: Read,
+              W: Write + Send + 'static,
+              M: Mapper + Send + Sync + 'static
+    {
+        match input {
+            Some(input) => StdIoFileAbstraction::new(input, output, mapper, base)
+                .map(|backend| Box::new(backend) as Box<FileAbstraction>),
+            None => StdoutFileAbstraction::new(output, mapper, base)
+                .map(|backend| Box::new(backend) as Box<FileAbstraction>),
+        }
+    }
+
+    debug!("Using '{}' as format for the store on stdin/stdout", format);
+    match format {
+        "json" => build(input, output, JsonMapper::new(), base),
+        "toml" => build(input, output, TomlMapper::new(), base),
+        "yaml" => build(input, output, YamlMapper::new(), base),
+        _      => return Err(RuntimeError::from_kind(RuntimeErrorKind::Instantiate)),
+    }
+    .chain_err(|| RuntimeErrorKind::Instantiate)
+}
+
+/// Register the hooks from `libimagstorestdhook` which are enabled in the configuration
+///
+/// A hook is enabled by setting `aspect` in its configuration (`[store.hooks.<hookname>]`) to the
+/// name of the aspect it should be registered for.
+fn register_std_hooks(store: &Store, appname: &str, rtp: &PathBuf) -> Result<(), RuntimeError> {
+    use toml::Value;
+
+    use libimagstore::hook::position::HookPosition as HP;
+    use libimagstorestdhook::search::SearchIndexHook;
+    use libimagstorestdhook::vcs::git::GitDeleteHook;
+    use libimagstorestdhook::vcs::git::GitMoveHook;
+    use libimagstorestdhook::vcs::git::GitUpdateHook;
+
+    let aspect_for = |hookname: &str| -> Result<Option<String>, RuntimeError> {
+        let cfg = store
+            .config()
+            .and_then(|c| c.get("hooks"))
+            .and_then(|h| h.get(hookname))
+            .and_then(|h| h.get("aspect"));
+
+        match cfg {
+            Some(&Value::String(ref s)) => Ok(Some(s.clone())),
+            Some(_) => Err(RuntimeError::from_kind(RuntimeErrorKind::ConfigTypeError)),
+            None => Ok(None),
+        }
+    };
+
+    let path    = store.path().clone();
+    let appname = String::from(appname);
+
+    if let Some(aspect) = try!(aspect_for("git-update")) {
+        let hook = GitUpdateHook::new(path.clone(), appname.clone());
+        try!(store.register_hook(HP::PostUpdate, &aspect, Box::new(hook))
+             .chain_err(|| RuntimeErrorKind::HookRegisterError));
+    }
+
+    if let Some(aspect) = try!(aspect_for("git-delete")) {
+        let hook = GitDeleteHook::new(path.clone(), appname.clone());
+        try!(store.register_hook(HP::PostDelete, &aspect, Box::new(hook))
+             .chain_err(|| RuntimeErrorKind::HookRegisterError));
+    }
+
+    if let Some(aspect) = try!(aspect_for("git-move")) {
+        let (pre, post) = GitMoveHook::new_pair(path, appname);
+        try!(store.register_hook(HP::PreMove, &aspect, Box::new(pre))
+             .chain_err(|| RuntimeErrorKind::HookRegisterError));
+        try!(store.register_hook(HP::PostMove, &aspect, Box::new(post))
+             .chain_err(|| RuntimeErrorKind::HookRegisterError));
+    }
+
+    if let Some(aspect) = try!(aspect_for("search-index")) {
+        for (position, hook) in SearchIndexHook::new_set(rtp) {
+            try!(store.register_hook(position, &aspect, Box::new(hook))
+                 .chain_err(|| RuntimeErrorKind::HookRegisterError));
+        }
+    }
+
+    Ok(())
+}
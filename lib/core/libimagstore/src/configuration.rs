@@ -17,10 +17,19 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::BTreeMap;
+use std::time::Duration;
+
 use toml::Value;
 
+use libimagutil::schema::{ConfigSchema, ValueType};
+
 use store::Result;
 use error::StoreError as SE;
+use file_abstraction::LockWait;
+use hook::position::HookPosition;
+use idtemplate::IdTemplate;
+use idtemplate::TEMPLATE_MODULES;
 
 /// Check whether the configuration is valid for the store
 pub fn config_is_valid(config: &Option<Value>) -> Result<()> {
@@ -65,6 +74,151 @@ pub fn config_implicit_store_create_allowed(config: Option<&Value>) -> bool {
     }).unwrap_or(false)
 }
 
+/// The time the store waits for an entry which is locked by another process, if not configured
+pub const DEFAULT_LOCK_TIMEOUT_MS : u64 = 5000;
+
+/// Get how the store waits for entries which are locked by another process
+///
+/// If the key "lock-wait" maps to `false`, the store does not wait at all. Otherwise it waits for
+/// the number of milliseconds the key "lock-timeout" maps to, or `DEFAULT_LOCK_TIMEOUT_MS`.
+pub fn config_lock_wait(config: Option<&Value>) -> LockWait {
+    let table = match config {
+        Some(&Value::Table(ref t)) => t,
+        _ => return LockWait::Timeout(Duration::from_millis(DEFAULT_LOCK_TIMEOUT_MS)),
+    };
+
+    match table.get("lock-wait") {
+        Some(&Value::Boolean(false)) => return LockWait::NoWait,
+        Some(&Value::Boolean(true)) | None => { },
+        Some(_) => warn!("Key 'lock-wait' does not contain a Boolean value"),
+    }
+
+    let timeout = match table.get("lock-timeout") {
+        Some(&Value::Integer(i)) if i >= 0 => i as u64,
+        Some(_) => {
+            warn!("Key 'lock-timeout' does not contain a positive Integer value");
+            DEFAULT_LOCK_TIMEOUT_MS
+        },
+        None => DEFAULT_LOCK_TIMEOUT_MS,
+    };
+
+    LockWait::Timeout(Duration::from_millis(timeout))
+}
+
+/// Get the names of the aspects which are configured for the hook position `pos`
+///
+/// Reads the `<position>-hook-aspects` array from the store configuration. Non-string values in
+/// the array are ignored with a warning.
+pub fn get_aspect_names_for_position(pos: &HookPosition, config: Option<&Value>) -> Vec<String> {
+    let key = pos.config_key();
+
+    match config {
+        Some(&Value::Table(ref t)) => match t.get(key) {
+            Some(&Value::Array(ref a)) => a
+                .iter()
+                .filter_map(|v| match *v {
+                    Value::String(ref s) => Some(s.clone()),
+                    _ => {
+                        warn!("Non-String in '{}' - ignoring", key);
+                        None
+                    },
+                })
+                .collect(),
+            Some(_) => {
+                warn!("Key '{}' does not contain an Array", key);
+                vec![]
+            },
+            None => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+/// Get the configuration for the aspect `name` from `[store.aspects.<name>]`
+pub fn get_config_for_aspect(name: &str, config: Option<&Value>) -> Option<Value> {
+    get_subsection_config("aspects", name, config)
+}
+
+/// Get the configuration for the hook `name` from `[store.hooks.<name>]`
+pub fn get_config_for_hook(name: &str, config: Option<&Value>) -> Option<Value> {
+    get_subsection_config("hooks", name, config)
+}
+
+/// Get the id templates of the modules from `[store.id-templates]`
+///
+/// Templates for modules which do not support them (see `idtemplate::TEMPLATE_MODULES`) are
+/// ignored with a warning. Fails if one of the templates is not a string or is not a valid template
+/// for its module.
+pub fn get_id_templates(config: Option<&Value>) -> Result<BTreeMap<String, IdTemplate>> {
+    use error::StoreErrorKind as SEK;
+
+    let templates = match config {
+        Some(&Value::Table(ref t)) => match t.get("id-templates") {
+            Some(&Value::Table(ref templates)) => templates,
+            Some(_) => {
+                warn!("Key 'id-templates' in store configuration is not a Table");
+                return Err(SE::from_kind(SEK::ConfigTypeError));
+            },
+            None => return Ok(BTreeMap::new()),
+        },
+        _ => return Ok(BTreeMap::new()),
+    };
+
+    let mut map = BTreeMap::new();
+    for (module, template) in templates.iter() {
+        if !TEMPLATE_MODULES.contains(&module.as_ref()) {
+            warn!("Ignoring id template for '{}', the module does not support id templates", module);
+            continue;
+        }
+
+        let template = match *template {
+            Value::String(ref template) => try!(IdTemplate::parse(module, template)),
+            _ => {
+                warn!("Id template for '{}' is not a String", module);
+                return Err(SE::from_kind(SEK::ConfigTypeError));
+            },
+        };
+
+        map.insert(module.clone(), template);
+    }
+
+    Ok(map)
+}
+
+/// The keys of the store configuration (`[store]`)
+///
+/// The configuration of the hooks and aspects is described by the libraries which provide them.
+pub fn config_schema() -> ConfigSchema {
+    let schema = ConfigSchema::new()
+        .key("store.implicit-create", ValueType::Boolean)
+        .key("store.lock-wait",       ValueType::Boolean)
+        .key("store.lock-timeout",    ValueType::Integer)
+        .key("store.aspects.*",       ValueType::Table)
+        .key("store.hooks.*",         ValueType::Table);
+
+    let schema = TEMPLATE_MODULES.iter().fold(schema, |schema, module| {
+        schema.key(&format!("store.id-templates.{}", module), ValueType::String)
+    });
+
+    HookPosition::all().iter().fold(schema, |schema, position| {
+        schema.key(&format!("store.{}", position.config_key()), ValueType::Array)
+    })
+}
+
+fn get_subsection_config(section: &str, name: &str, config: Option<&Value>) -> Option<Value> {
+    match config {
+        Some(&Value::Table(ref t)) => match t.get(section) {
+            Some(&Value::Table(ref sec)) => sec.get(name).cloned(),
+            Some(_) => {
+                warn!("Key '{}' in store configuration is not a Table", section);
+                None
+            },
+            None => None,
+        },
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use toml::de::from_str as toml_from_str;
@@ -99,5 +253,90 @@ mod tests {
         assert!(config_implicit_store_create_allowed(Some(config).as_ref()));
     }
 
-}
+    #[test]
+    fn test_lock_wait_default() {
+        use std::time::Duration;
+        use file_abstraction::LockWait;
+
+        let default = LockWait::Timeout(Duration::from_millis(DEFAULT_LOCK_TIMEOUT_MS));
+        let config  = toml_from_str("").unwrap();
+
+        assert_eq!(config_lock_wait(None), default);
+        assert_eq!(config_lock_wait(Some(&config)), default);
+    }
+
+    #[test]
+    fn test_lock_wait_configured() {
+        use std::time::Duration;
+        use file_abstraction::LockWait;
+
+        let config = toml_from_str(r#"
+            lock-wait = false
+            lock-timeout = 100
+        "#).unwrap();
+        assert_eq!(config_lock_wait(Some(&config)), LockWait::NoWait);
+
+        let config = toml_from_str(r#"
+            lock-wait = true
+            lock-timeout = 100
+        "#).unwrap();
+        assert_eq!(config_lock_wait(Some(&config)), LockWait::Timeout(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_aspect_names_for_position() {
+        use hook::position::HookPosition;
+
+        let config = toml_from_str(r#"
+            pre-create-hook-aspects = [ "debug", "vcs" ]
+            post-create-hook-aspects = [ ]
+        "#).unwrap();
+
+        let pre  = get_aspect_names_for_position(&HookPosition::PreCreate, Some(&config));
+        let post = get_aspect_names_for_position(&HookPosition::PostCreate, Some(&config));
+        let del  = get_aspect_names_for_position(&HookPosition::PreDelete, Some(&config));
+
+        assert_eq!(pre, vec![String::from("debug"), String::from("vcs")]);
+        assert!(post.is_empty());
+        assert!(del.is_empty());
+    }
 
+    #[test]
+    fn test_id_templates() {
+        let config = toml_from_str(r#"
+            [id-templates]
+            notes = "notes/{slug(name)}"
+        "#).unwrap();
+
+        let templates = get_id_templates(Some(&config)).unwrap();
+        assert_eq!(templates.get("notes").map(|t| t.as_str()), Some("notes/{slug(name)}"));
+        assert!(get_id_templates(None).unwrap().is_empty());
+
+        let config = toml_from_str(r#"
+            [id-templates]
+            notes = "diary/{name}"
+        "#).unwrap();
+
+        assert!(get_id_templates(Some(&config)).is_err());
+
+        let config = toml_from_str(r#"
+            [id-templates]
+            diary = "diary/{name}/{yyyy}/{mm}/{dd}"
+        "#).unwrap();
+
+        assert!(get_id_templates(Some(&config)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_config_for_hook() {
+        let config = toml_from_str(r#"
+            [hooks.foo]
+            bar = 1
+        "#).unwrap();
+
+        assert!(get_config_for_hook("foo", Some(&config)).is_some());
+        assert!(get_config_for_hook("bar", Some(&config)).is_none());
+        assert!(get_config_for_aspect("foo", Some(&config)).is_none());
+    }
+
+}
@@ -0,0 +1,161 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Conflicts between entries and their versions in the backend
+//!
+//! When an entry file is changed in the backend (for example by a text editor or a `git pull`)
+//! while the entry is borrowed, `Store::update()` refuses to overwrite the change and fails with
+//! `StoreErrorKind::EntryModifiedInBackend`.
+//!
+//! `Store::get_conflict()` then returns a `Conflict`, which contains the version of the entry as
+//! it was retrieved ("base"), the borrowed version ("ours") and the version in the backend
+//! ("theirs"). After merging the changes into the borrowed entry, `Store::resolve_conflict()`
+//! marks the conflict as resolved, so the next update overwrites the version in the backend.
+
+use std::fmt::Write;
+
+use diff;
+use toml::Value;
+
+use store::Entry;
+
+/// A conflict between a borrowed entry and the version of the entry in the backend
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    base: Option<Entry>,
+    ours: Entry,
+    theirs: Option<Entry>,
+}
+
+impl Conflict {
+
+    pub fn new(base: Option<Entry>, ours: Entry, theirs: Option<Entry>) -> Conflict {
+        Conflict {
+            base: base,
+            ours: ours,
+            theirs: theirs,
+        }
+    }
+
+    /// The entry as it was when it was retrieved, `None` if it did not exist in the backend then
+    pub fn base(&self) -> Option<&Entry> {
+        self.base.as_ref()
+    }
+
+    /// The borrowed entry
+    pub fn ours(&self) -> &Entry {
+        &self.ours
+    }
+
+    /// The entry as it is in the backend now, `None` if it was removed from the backend
+    pub fn theirs(&self) -> Option<&Entry> {
+        self.theirs.as_ref()
+    }
+
+    /// Check whether the header was changed on both sides
+    pub fn header_conflicts(&self) -> bool {
+        let base = self.base.as_ref().map(|e| header_str(e.get_header()));
+        let ours = Some(header_str(self.ours.get_header()));
+        let theirs = self.theirs.as_ref().map(|e| header_str(e.get_header()));
+
+        base != ours && base != theirs && ours != theirs
+    }
+
+    /// Check whether the content was changed on both sides
+    pub fn content_conflicts(&self) -> bool {
+        let base = self.base.as_ref().map(|e| e.get_content());
+        let ours = Some(self.ours.get_content());
+        let theirs = self.theirs.as_ref().map(|e| e.get_content());
+
+        base != ours && base != theirs && ours != theirs
+    }
+
+    /// Render a three-way diff of header and content
+    ///
+    /// For both the header and the content, the changes from the base version to our version and
+    /// the changes from the base version to their version are listed, in unified-diff style
+    /// (`-` for removed lines, `+` for added lines, ` ` for unchanged lines).
+    pub fn diff(&self) -> String {
+        let base_header = self.base.as_ref().map(|e| header_str(e.get_header())).unwrap_or_default();
+        let base_content = self.base.as_ref().map(|e| e.get_content().clone()).unwrap_or_default();
+        let ours_header = header_str(self.ours.get_header());
+        let theirs_header = self.theirs.as_ref().map(|e| header_str(e.get_header())).unwrap_or_default();
+        let theirs_content = self.theirs.as_ref().map(|e| e.get_content().clone()).unwrap_or_default();
+
+        let mut out = String::new();
+        diff_section(&mut out, "header", "ours", &base_header, &ours_header);
+        diff_section(&mut out, "header", "theirs", &base_header, &theirs_header);
+        diff_section(&mut out, "content", "ours", &base_content, self.ours.get_content());
+        diff_section(&mut out, "content", "theirs", &base_content, &theirs_content);
+        out
+    }
+
+}
+
+fn header_str(header: &Value) -> String {
+    ::toml::ser::to_string(header).unwrap_or_default()
+}
+
+fn diff_section(out: &mut String, part: &str, side: &str, base: &str, other: &str) {
+    let _ = writeln!(out, "--- {} (base)", part);
+    let _ = writeln!(out, "+++ {} ({})", part, side);
+    for line in diff::lines(base, other) {
+        let _ = match line {
+            diff::Result::Left(l)    => writeln!(out, "-{}", l),
+            diff::Result::Both(l, _) => writeln!(out, " {}", l),
+            diff::Result::Right(r)   => writeln!(out, "+{}", r),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Conflict;
+    use store::Entry;
+    use storeid::StoreId;
+
+    fn entry(content: &str) -> Entry {
+        let mut entry = Entry::new(StoreId::new_baseless("test".into()).unwrap());
+        *entry.get_content_mut() = String::from(content);
+        entry
+    }
+
+    #[test]
+    fn test_content_conflict() {
+        let conflict = Conflict::new(Some(entry("a\nb\n")), entry("a\nc\n"), Some(entry("a\nd\n")));
+        assert!(conflict.content_conflicts());
+        assert!(!conflict.header_conflicts());
+    }
+
+    #[test]
+    fn test_no_conflict_if_only_one_side_changed() {
+        let conflict = Conflict::new(Some(entry("a\n")), entry("a\n"), Some(entry("b\n")));
+        assert!(!conflict.content_conflicts());
+    }
+
+    #[test]
+    fn test_diff_shows_both_sides() {
+        let conflict = Conflict::new(Some(entry("a\nb\n")), entry("a\nc\n"), Some(entry("a\nd\n")));
+        let diff = conflict.diff();
+
+        assert!(diff.contains("+++ content (ours)\n a\n-b\n+c\n"));
+        assert!(diff.contains("+++ content (theirs)\n a\n-b\n+d\n"));
+    }
+
+}
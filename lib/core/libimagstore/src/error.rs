@@ -49,6 +49,41 @@ error_chain! {
             display("Incompatible store versions detected")
         }
 
+        InvalidMigration(name: String) {
+            description("Invalid migration")
+            display("Invalid migration: '{}'", name)
+        }
+
+        MigrationError(name: String) {
+            description("Migration failed")
+            display("Migration '{}' failed", name)
+        }
+
+        EntryVersionNewer(version: String) {
+            description("Entry was written by a newer version of imag")
+            display("Entry was written by a newer version of imag: {}", version)
+        }
+
+        IdTemplateInvalid(template: String) {
+            description("Id template cannot be parsed")
+            display("Id template cannot be parsed: '{}'", template)
+        }
+
+        IdTemplateOutsideModule(template: String, module: String) {
+            description("Id template does not start with the collection of its module")
+            display("Id template '{}' does not start with '{}/'", template, module)
+        }
+
+        IdTemplateUnknownVariable(var: String) {
+            description("Unknown variable in id template")
+            display("Unknown variable in id template: '{}'", var)
+        }
+
+        IdTemplateInvalidId(id: String) {
+            description("Id template resulted in an invalid id")
+            display("Id template resulted in an invalid id: '{}'", id)
+        }
+
         CreateStoreDirDenied    {
             description("Creating store directory implicitely denied")
             display("Creating store directory implicitely denied")
@@ -114,6 +149,16 @@ error_chain! {
             display("File could not be copied")
         }
 
+        FileNotLocked           {
+            description("File could not be locked or unlocked")
+            display("File could not be locked or unlocked")
+        }
+
+        EntryLockedByOtherProcess {
+            description("Entry is locked by another process")
+            display("Entry is locked by another process")
+        }
+
         DirNotCreated           {
             description("Directory/Directories could not be created")
             display("Directory/Directories could not be created")
@@ -144,6 +189,21 @@ error_chain! {
             display("Entry is already borrowed")
         }
 
+        EntryModifiedInBackend  {
+            description("Entry was modified in the backend since it was retrieved")
+            display("Entry was modified in the backend since it was retrieved")
+        }
+
+        EntryInTrash            {
+            description("Entry is in the trash")
+            display("Entry is in the trash")
+        }
+
+        EntryNotInTrash         {
+            description("Entry is not in the trash")
+            display("Entry is not in the trash")
+        }
+
         EntryAlreadyExists      {
             description("Entry already exists")
             display("Entry already exists")
@@ -269,6 +329,43 @@ error_chain! {
             display("Error when calling move_by_id()")
         }
 
+        TransactionCommitError     {
+            description("Error when committing a transaction")
+            display("Error when committing a transaction")
+        }
+
+        TransactionRollbackError   {
+            description("Error when rolling back a transaction, the store might be inconsistent")
+            display("Error when rolling back a transaction, the store might be inconsistent")
+        }
+
+        // Hook-related errors
+
+        HookRegisterError          {
+            description("Error while registering a hook")
+            display("Error while registering a hook")
+        }
+
+        AspectNameNotFoundError    {
+            description("Aspect name not found")
+            display("Aspect name not found")
+        }
+
+        HookExecutionError         {
+            description("Hook execution error")
+            display("Hook execution error")
+        }
+
+        PreHookExecuteError        {
+            description("Pre-Hook execution error")
+            display("Pre-Hook execution error")
+        }
+
+        PostHookExecuteError       {
+            description("Post-Hook execution error")
+            display("Post-Hook execution error")
+        }
+
         // Parser-related errors
 
         TOMLParserErrors    {
@@ -18,14 +18,20 @@
 //
 
 use std::fs::{File, OpenOptions, create_dir_all, remove_file, copy, rename};
-use std::io::{Seek, SeekFrom, Read};
+use std::io::{ErrorKind, Seek, SeekFrom, Read};
 use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use fs2::{FileExt, lock_contended_error};
 
 use error::{StoreError as SE, StoreErrorKind as SEK};
 use error::ResultExt;
 
 use super::FileAbstraction;
 use super::FileAbstractionInstance;
+use super::FileStamp;
+use super::LockWait;
 use super::Drain;
 use store::Entry;
 use storeid::StoreId;
@@ -34,6 +40,10 @@ use file_abstraction::iter::PathIterator;
 #[derive(Debug)]
 pub enum FSFileAbstractionInstance {
     Absent(PathBuf),
+
+    /// A file which did not exist when it was locked. It is locked as soon as it is created.
+    AbsentLocked(PathBuf, LockWait),
+
     File(File, PathBuf)
 }
 
@@ -44,6 +54,12 @@ impl FileAbstractionInstance for FSFileAbstractionInstance {
      */
     fn get_file_content(&mut self, id: StoreId) -> Result<Entry, SE> {
         debug!("Getting lazy file: {:?}", self);
+        if let Some((path, wait)) = self.pending_lock() {
+            // The file might have been created in the meantime, it has to be locked before reading
+            *self = FSFileAbstractionInstance::Absent(path);
+            try!(self.lock(&wait));
+        }
+
         let (file, path) = match *self {
             FSFileAbstractionInstance::File(ref mut f, _) => return {
                 // We seek to the beginning of the file since we expect each
@@ -59,6 +75,7 @@ impl FileAbstractionInstance for FSFileAbstractionInstance {
             },
             FSFileAbstractionInstance::Absent(ref p) =>
                 (try!(open_file(p).chain_err(|| SEK::FileNotFound)), p.clone()),
+            FSFileAbstractionInstance::AbsentLocked(..) => return Err(SE::from_kind(SEK::FileNotFound)),
         };
         *self = FSFileAbstractionInstance::File(file, path);
         if let FSFileAbstractionInstance::File(ref mut f, _) = *self {
@@ -76,30 +93,200 @@ impl FileAbstractionInstance for FSFileAbstractionInstance {
      * Write the content of this file
      */
     fn write_file_content(&mut self, buf: &Entry) -> Result<(), SE> {
-        use std::io::Write;
-
         let buf = buf.to_str().into_bytes();
 
-        let (file, path) = match *self {
-            FSFileAbstractionInstance::File(ref mut f, _) => return {
-                // We seek to the beginning of the file since we expect each
-                // access to the file to be in a different context
-                try!(f.seek(SeekFrom::Start(0))
-                    .chain_err(|| SEK::FileNotCreated));
+        if let Some((path, wait)) = self.pending_lock() {
+            // Lock the file right after creating it, before anything is written to it
+            try!(create_file(&path).chain_err(|| SEK::FileNotCreated));
+            *self = FSFileAbstractionInstance::Absent(path.clone());
+
+            let written_by_other = self.lock(&wait).and_then(|_| match *self {
+                FSFileAbstractionInstance::File(ref f, _) => f
+                    .metadata()
+                    .map(|m| m.len() != 0)
+                    .chain_err(|| SEK::FileNotCreated),
+                _ => Err(SE::from_kind(SEK::FileNotCreated)),
+            });
 
-                try!(f.set_len(buf.len() as u64).chain_err(|| SEK::FileNotWritten));
+            match written_by_other {
+                Ok(false) => { },
+                Ok(true) => {
+                    // Another process created the entry since it was found absent
+                    debug!("{:?} was created by another process", path);
+                    try!(self.unlock());
+                    *self = FSFileAbstractionInstance::AbsentLocked(path, wait);
+                    return Err(SE::from_kind(SEK::EntryModifiedInBackend));
+                },
+                Err(e) => {
+                    *self = FSFileAbstractionInstance::AbsentLocked(path, wait);
+                    return Err(e);
+                },
+            }
+        }
 
-                f.write_all(&buf).chain_err(|| SEK::FileNotWritten)
-            },
+        let (file, path) = match *self {
+            FSFileAbstractionInstance::File(ref mut f, _) => return write_to(f, &buf),
             FSFileAbstractionInstance::Absent(ref p) =>
                 (try!(create_file(p).chain_err(|| SEK::FileNotCreated)), p.clone()),
+            FSFileAbstractionInstance::AbsentLocked(..) => unreachable!(),
         };
         *self = FSFileAbstractionInstance::File(file, path);
         if let FSFileAbstractionInstance::File(ref mut f, _) = *self {
-            return f.write_all(&buf).chain_err(|| SEK::FileNotWritten);
+            // The file might have existed already, so it is truncated like an opened file
+            return write_to(f, &buf);
         }
         unreachable!();
     }
+
+    /**
+     * Lock the file with an advisory lock (flock()), waiting for other processes as configured
+     *
+     * Files which do not exist yet cannot be locked, so they are locked as soon as they are
+     * created by `write_file_content()`.
+     *
+     * If the file was replaced by another process since it was opened, the new file is opened and
+     * locked instead.
+     */
+    fn lock(&mut self, wait: &LockWait) -> Result<(), SE> {
+        let start = Instant::now();
+
+        loop {
+            let path = self.path().clone();
+
+            let replaced = match *self {
+                FSFileAbstractionInstance::File(ref f, _) => !is_current_file(f, &path),
+                _                                         => false,
+            };
+
+            if replaced {
+                debug!("{:?} was replaced since it was opened, reopening", path);
+                *self = FSFileAbstractionInstance::Absent(path.clone());
+            }
+
+            if !is_match!(*self, FSFileAbstractionInstance::File(..)) {
+                match open_file(&path) {
+                    Ok(file) => *self = FSFileAbstractionInstance::File(file, path.clone()),
+                    Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                        debug!("{:?} does not exist yet, locking it when it is created", path);
+                        *self = FSFileAbstractionInstance::AbsentLocked(path.clone(), *wait);
+                        return Ok(());
+                    },
+                    Err(e) => return Err(e).chain_err(|| SEK::FileNotLocked),
+                }
+            }
+
+            let res = match *self {
+                FSFileAbstractionInstance::File(ref f, _) => f
+                    .try_lock_exclusive()
+                    .map(|_| is_current_file(f, &path)),
+                _ => unreachable!(),
+            };
+
+            match res {
+                Ok(true) => {
+                    debug!("Locked {:?}", path);
+                    return Ok(());
+                },
+                Ok(false) => {
+                    // Replaced between opening and locking, reopen in the next iteration
+                    try!(self.unlock());
+                },
+                Err(ref e) if e.kind() == lock_contended_error().kind() => {
+                    let timeout = match *wait {
+                        LockWait::NoWait           => Duration::from_millis(0),
+                        LockWait::Timeout(timeout) => timeout,
+                    };
+
+                    if start.elapsed() >= timeout {
+                        debug!("{:?} is locked by another process", path);
+                        return Err(SE::from_kind(SEK::EntryLockedByOtherProcess));
+                    }
+
+                    sleep(Duration::from_millis(LOCK_RETRY_INTERVAL_MS));
+                },
+                Err(e) => return Err(e).chain_err(|| SEK::FileNotLocked),
+            }
+        }
+    }
+
+    /**
+     * The stamp of a file is its modification time
+     */
+    fn stamp(&mut self) -> Result<Option<FileStamp>, SE> {
+        match ::std::fs::metadata(self.path()) {
+            Ok(meta) => {
+                let modified = try!(meta.modified().chain_err(|| SEK::IoError))
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or(Duration::from_secs(0));
+                let version  = modified.as_secs() * 1_000_000_000 + modified.subsec_nanos() as u64;
+                Ok(Some(FileStamp::new(meta.len(), version)))
+            },
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).chain_err(|| SEK::IoError),
+        }
+    }
+
+    fn unlock(&mut self) -> Result<(), SE> {
+        let path = match *self {
+            FSFileAbstractionInstance::File(ref f, _)         => return f.unlock().chain_err(|| SEK::FileNotLocked),
+            FSFileAbstractionInstance::Absent(_)              => return Ok(()),
+            FSFileAbstractionInstance::AbsentLocked(ref p, _) => p.clone(),
+        };
+
+        *self = FSFileAbstractionInstance::Absent(path);
+        Ok(())
+    }
+}
+
+impl FSFileAbstractionInstance {
+
+    fn path(&self) -> &PathBuf {
+        match *self {
+            FSFileAbstractionInstance::File(_, ref p)         => p,
+            FSFileAbstractionInstance::Absent(ref p)          => p,
+            FSFileAbstractionInstance::AbsentLocked(ref p, _) => p,
+        }
+    }
+
+    /// The path and the wait configuration of a file which has to be locked when it is created
+    fn pending_lock(&self) -> Option<(PathBuf, LockWait)> {
+        match *self {
+            FSFileAbstractionInstance::AbsentLocked(ref p, wait) => Some((p.clone(), wait)),
+            _ => None,
+        }
+    }
+
+}
+
+/// Replace the contents of `file` with `buf`
+fn write_to(file: &mut File, buf: &[u8]) -> Result<(), SE> {
+    use std::io::Write;
+
+    // We seek to the beginning of the file since we expect each
+    // access to the file to be in a different context
+    try!(file.seek(SeekFrom::Start(0)).chain_err(|| SEK::FileNotCreated));
+    try!(file.set_len(buf.len() as u64).chain_err(|| SEK::FileNotWritten));
+    file.write_all(buf).chain_err(|| SEK::FileNotWritten)
+}
+
+/// How long to sleep between two attempts to lock a file which is locked by another process
+const LOCK_RETRY_INTERVAL_MS : u64 = 10;
+
+/// Check whether `file` is (still) the file at `path`
+#[cfg(unix)]
+fn is_current_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), ::std::fs::metadata(path)) {
+        (Ok(opened), Ok(current)) => opened.dev() == current.dev() && opened.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Check whether `file` is (still) the file at `path`
+#[cfg(not(unix))]
+fn is_current_file(_: &File, path: &Path) -> bool {
+    path.exists()
 }
 
 /// `FSFileAbstraction` state type
@@ -133,6 +320,10 @@ impl FileAbstraction for FSFileAbstraction {
         create_dir_all(path).chain_err(|| SEK::DirNotCreated)
     }
 
+    fn exists(&self, path: &PathBuf) -> Result<bool, SE> {
+        Ok(path.exists())
+    }
+
     fn new_instance(&self, p: PathBuf) -> Box<FileAbstractionInstance> {
         Box::new(FSFileAbstractionInstance::Absent(p))
     }
@@ -184,3 +375,137 @@ fn create_file<A: AsRef<Path>>(p: A) -> ::std::io::Result<File> {
     OpenOptions::new().write(true).read(true).create(true).open(p)
 }
 
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use tempdir::TempDir;
+
+    use super::FSFileAbstractionInstance;
+    use error::StoreErrorKind as SEK;
+    use file_abstraction::FileAbstractionInstance;
+    use file_abstraction::LockWait;
+    use store::Entry;
+    use storeid::StoreId;
+
+    fn write_entry(path: &PathBuf, content: &str) {
+        let id        = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        let mut entry = Entry::new(id);
+        *entry.get_content_mut() = String::from(content);
+        FSFileAbstractionInstance::Absent(path.clone()).write_file_content(&entry).unwrap();
+    }
+
+    #[test]
+    fn test_overwrite_with_shorter_entry() {
+        let dir  = TempDir::new("imag-store-write").unwrap();
+        let path = dir.path().join("test");
+        write_entry(&path, "a very long content which is longer than the new one");
+        write_entry(&path, "short");
+
+        let id    = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        let entry = FSFileAbstractionInstance::Absent(path.clone()).get_file_content(id).unwrap();
+        assert_eq!(entry.get_content(), "short");
+    }
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir  = TempDir::new("imag-store-lock").unwrap();
+        let path = dir.path().join("test");
+        write_entry(&path, "");
+
+        let mut first  = FSFileAbstractionInstance::Absent(path.clone());
+        let mut second = FSFileAbstractionInstance::Absent(path.clone());
+
+        assert!(first.lock(&LockWait::NoWait).is_ok());
+
+        let res = second.lock(&LockWait::NoWait);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err().kind(), &SEK::EntryLockedByOtherProcess));
+
+        assert!(first.unlock().is_ok());
+        assert!(second.lock(&LockWait::NoWait).is_ok());
+    }
+
+    #[test]
+    fn test_lock_waits_for_timeout() {
+        let dir  = TempDir::new("imag-store-lock").unwrap();
+        let path = dir.path().join("test");
+        write_entry(&path, "");
+
+        let mut first  = FSFileAbstractionInstance::Absent(path.clone());
+        let mut second = FSFileAbstractionInstance::Absent(path.clone());
+        assert!(first.lock(&LockWait::NoWait).is_ok());
+
+        let timeout = Duration::from_millis(50);
+        let start   = Instant::now();
+        assert!(second.lock(&LockWait::Timeout(timeout)).is_err());
+        assert!(start.elapsed() >= timeout);
+    }
+
+    #[test]
+    fn test_lock_absent_file() {
+        let dir      = TempDir::new("imag-store-lock").unwrap();
+        let mut file = FSFileAbstractionInstance::Absent(dir.path().join("test"));
+        assert!(file.lock(&LockWait::NoWait).is_ok());
+        assert!(file.unlock().is_ok());
+    }
+
+    #[test]
+    fn test_lock_absent_file_when_created() {
+        let dir  = TempDir::new("imag-store-lock").unwrap();
+        let path = dir.path().join("test");
+        let id   = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+
+        let mut first  = FSFileAbstractionInstance::Absent(path.clone());
+        let mut second = FSFileAbstractionInstance::Absent(path.clone());
+        assert!(first.lock(&LockWait::NoWait).is_ok());
+        assert!(second.lock(&LockWait::NoWait).is_ok());
+
+        // The first instance creates the file and holds the lock on it
+        let mut entry = Entry::new(id.clone());
+        *entry.get_content_mut() = String::from("first");
+        assert!(first.write_file_content(&entry).is_ok());
+
+        *entry.get_content_mut() = String::from("second");
+        let res = second.write_file_content(&entry);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err().kind(), &SEK::EntryLockedByOtherProcess));
+
+        // After the lock is released, the second instance does not clobber the created file
+        assert!(first.unlock().is_ok());
+        let res = second.write_file_content(&entry);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err().kind(), &SEK::EntryModifiedInBackend));
+
+        let read = FSFileAbstractionInstance::Absent(path.clone()).get_file_content(id).unwrap();
+        assert_eq!(read.get_content(), "first");
+    }
+
+    #[test]
+    fn test_lock_reopens_replaced_file() {
+        use std::fs::rename;
+
+        let dir  = TempDir::new("imag-store-lock").unwrap();
+        let path = dir.path().join("test");
+        let new  = dir.path().join("new");
+        write_entry(&path, "old");
+
+        let mut first  = FSFileAbstractionInstance::Absent(path.clone());
+        let mut second = FSFileAbstractionInstance::Absent(path.clone());
+        assert!(first.lock(&LockWait::NoWait).is_ok());
+        assert!(second.get_file_content(StoreId::new_baseless(PathBuf::from("test")).unwrap()).is_ok());
+
+        // Replace the file, as committing a transaction does
+        write_entry(&new, "new");
+        rename(&new, &path).unwrap();
+
+        // The lock on the old file does not block the new file, which is read after locking
+        assert!(second.lock(&LockWait::NoWait).is_ok());
+        let entry = second.get_file_content(StoreId::new_baseless(PathBuf::from("test")).unwrap());
+        assert_eq!(entry.unwrap().get_content(), "new");
+    }
+
+}
@@ -29,6 +29,7 @@ use std::ops::Deref;
 
 use super::FileAbstraction;
 use super::FileAbstractionInstance;
+use super::FileStamp;
 use super::Drain;
 use store::Entry;
 use storeid::StoreId;
@@ -61,7 +62,7 @@ impl FileAbstractionInstance for InMemoryFileAbstractionInstance {
     /**
      * Get the mutable file behind a InMemoryFileAbstraction object
      */
-    fn get_file_content(&mut self, _: StoreId) -> Result<Entry, SE> {
+    fn get_file_content(&mut self, id: StoreId) -> Result<Entry, SE> {
         debug!("Getting lazy file: {:?}", self);
 
         self.fs_abstraction
@@ -70,7 +71,15 @@ impl FileAbstractionInstance for InMemoryFileAbstractionInstance {
             .and_then(|mut mtx| {
                 mtx.get_mut()
                     .get(&self.absent_path)
-                    .cloned()
+                    .map(|stored| {
+                        // The stored entry might have been put into the backend with another
+                        // location (for example a baseless one by a Mapper), so we rebuild it for
+                        // the requested id
+                        let mut entry = Entry::new(id);
+                        *entry.get_header_mut()  = stored.get_header().clone();
+                        *entry.get_content_mut() = stored.get_content().clone();
+                        entry
+                    })
                     .ok_or(SE::from_kind(SEK::FileNotFound))
             })
     }
@@ -85,6 +94,26 @@ impl FileAbstractionInstance for InMemoryFileAbstractionInstance {
             },
         };
     }
+
+    /// The stamp of an in-memory file is the hash of its contents
+    fn stamp(&mut self) -> Result<Option<FileStamp>, SE> {
+        use std::hash::{Hash, Hasher};
+        use std::collections::hash_map::DefaultHasher;
+
+        self.fs_abstraction
+            .lock()
+            .map_err(|_| SE::from_kind(SEK::LockError))
+            .map(|mut mtx| {
+                mtx.get_mut()
+                    .get(&self.absent_path)
+                    .map(|stored| {
+                        let s          = stored.to_str();
+                        let mut hasher = DefaultHasher::new();
+                        s.hash(&mut hasher);
+                        FileStamp::new(s.len() as u64, hasher.finish())
+                    })
+            })
+    }
 }
 
 #[derive(Debug)]
@@ -142,7 +171,7 @@ impl FileAbstraction for InMemoryFileAbstraction {
         let mut mtx = self.backend().lock().expect("Locking Mutex failed");
         let backend = mtx.get_mut();
 
-        let a = try!(backend.get(from).cloned().ok_or(SE::from_kind(SEK::FileNotFound)));
+        let a = try!(backend.remove(from).ok_or(SE::from_kind(SEK::FileNotFound)));
         backend.insert(to.clone(), a);
         debug!("Renaming: {:?} -> {:?} worked", from, to);
         Ok(())
@@ -152,6 +181,13 @@ impl FileAbstraction for InMemoryFileAbstraction {
         Ok(())
     }
 
+    fn exists(&self, path: &PathBuf) -> Result<bool, SE> {
+        self.backend()
+            .lock()
+            .map_err(|_| SE::from_kind(SEK::LockError))
+            .map(|mtx| mtx.borrow().contains_key(path))
+    }
+
     fn new_instance(&self, p: PathBuf) -> Box<FileAbstractionInstance> {
         Box::new(InMemoryFileAbstractionInstance::new(self.backend().clone(), p))
     }
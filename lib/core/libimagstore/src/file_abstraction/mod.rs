@@ -20,6 +20,7 @@
 use std::path::PathBuf;
 use std::fmt::Debug;
 use std::collections::HashMap;
+use std::time::Duration;
 
 use error::StoreError as SE;
 use store::Entry;
@@ -37,11 +38,14 @@ pub use self::inmemory::InMemoryFileAbstractionInstance;
 use self::iter::PathIterator;
 
 /// An abstraction trait over filesystem actions
-pub trait FileAbstraction : Debug {
+///
+/// Backends have to be `Send + Sync`, so a `Store` can be shared between threads.
+pub trait FileAbstraction : Debug + Send + Sync {
     fn remove_file(&self, path: &PathBuf) -> Result<(), SE>;
     fn copy(&self, from: &PathBuf, to: &PathBuf) -> Result<(), SE>;
     fn rename(&self, from: &PathBuf, to: &PathBuf) -> Result<(), SE>;
     fn create_dir_all(&self, _: &PathBuf) -> Result<(), SE>;
+    fn exists(&self, &PathBuf) -> Result<bool, SE>;
 
     fn new_instance(&self, p: PathBuf) -> Box<FileAbstractionInstance>;
 
@@ -52,7 +56,7 @@ pub trait FileAbstraction : Debug {
 }
 
 /// An abstraction trait over actions on files
-pub trait FileAbstractionInstance : Debug {
+pub trait FileAbstractionInstance : Debug + Send + Sync {
 
     /// Get the contents of the FileAbstractionInstance, as Entry object.
     ///
@@ -60,6 +64,69 @@ pub trait FileAbstractionInstance : Debug {
     /// Entry type itself must be constructed with the id.
     fn get_file_content(&mut self, id: StoreId) -> Result<Entry, SE>;
     fn write_file_content(&mut self, buf: &Entry) -> Result<(), SE>;
+
+    /// Get the stamp of the file in the backend, `None` if the file does not exist.
+    ///
+    /// The stamp is taken from the file in the backend, not from the file this instance opened, so
+    /// files which were replaced (as text editors do when saving) get a new stamp as well.
+    fn stamp(&mut self) -> Result<Option<FileStamp>, SE>;
+
+    /// Lock the file, so no other process can borrow the entry until `unlock()` is called.
+    ///
+    /// `wait` defines what happens if the file is locked by another process already.
+    ///
+    /// Backends which cannot be shared between processes do not have to lock anything.
+    fn lock(&mut self, _wait: &LockWait) -> Result<(), SE> {
+        Ok(())
+    }
+
+    /// Release the lock taken with `lock()`
+    fn unlock(&mut self) -> Result<(), SE> {
+        Ok(())
+    }
+}
+
+/// The state of a file in a backend
+///
+/// The stamp of a file changes whenever the file is written, so comparing stamps is a cheap way to
+/// find out whether a file was modified, without reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    len: u64,
+    version: u64,
+}
+
+impl FileStamp {
+
+    /// Create a stamp for a file of length `len`
+    ///
+    /// `version` has to change whenever the file is written, for example the modification time.
+    pub fn new(len: u64, version: u64) -> FileStamp {
+        FileStamp {
+            len: len,
+            version: version,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+}
+
+/// What to do when locking a file which is locked by another process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockWait {
+
+    /// Fail immediately
+    NoWait,
+
+    /// Wait for the other process to release the lock, but fail after the timeout
+    Timeout(Duration),
 }
 
 pub struct Drain(HashMap<PathBuf, Entry>);
@@ -25,7 +25,6 @@ use serde_json;
 use toml;
 
 use error::StoreErrorKind as SEK;
-use error::StoreError as SE;
 use error::ResultExt;
 use super::Mapper;
 use store::Result;
@@ -69,7 +68,9 @@ impl JsonMapper {
 }
 
 impl Mapper for JsonMapper {
-    fn read_to_fs<R: Read>(&self, r: &mut R, hm: &mut HashMap<PathBuf, Entry>)   -> Result<()> {
+    fn read_to_fs<R: Read>(&self, r: &mut R, base: &PathBuf, hm: &mut HashMap<PathBuf, Entry>)
+        -> Result<()>
+    {
         let mut document = {
             debug!("Reading Document");
             let mut s = String::new();
@@ -81,25 +82,11 @@ impl Mapper for JsonMapper {
             doc
         };
 
-        let _ = try!(::semver::Version::parse(&document.version)
-            .chain_err(|| SEK::VersionError)
-            .and_then(|doc_vers| {
-                // safe because cargo does not compile if crate version is not valid
-                let crate_version = ::semver::Version::parse(version!()).unwrap();
-
-                debug!("Document version vs. own version: {doc_vers} > {crate_vers}",
-                       doc_vers = doc_vers,
-                       crate_vers = crate_version);
-
-                if doc_vers > crate_version {
-                    Err(SE::from_kind(SEK::VersionError))
-                } else {
-                    Ok(())
-                }
-            }));
+        let _ = try!(super::check_version(&document.version));
 
         for (key, val) in document.store.drain() {
             debug!("(key, value) ({:?}, {:?})", key, val);
+            let key = super::local_path(base, key);
             let res = val
                 .to_string()
                 .and_then(|vals| {
@@ -169,7 +156,7 @@ mod test {
         let mapper   = JsonMapper::new();
         let mut hm   = HashMap::new();
 
-        let io_res  = mapper.read_to_fs(&mut json, &mut hm);
+        let io_res  = mapper.read_to_fs(&mut json, &PathBuf::from("/"), &mut hm);
         assert!(io_res.is_ok());
         assert!(hm.is_empty());
     }
@@ -194,7 +181,7 @@ mod test {
         let mapper   = JsonMapper::new();
         let mut hm   = HashMap::new();
 
-        let io_res  = mapper.read_to_fs(&mut json, &mut hm);
+        let io_res  = mapper.read_to_fs(&mut json, &PathBuf::from("/"), &mut hm);
         assert!(io_res.is_ok());
 
         assert_eq!(1, hm.len()); // we should have exactly one entry
@@ -20,13 +20,73 @@
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::path::PathBuf;
+
+use toml::Value;
+
+use error::StoreErrorKind as SEK;
+use error::StoreError as SE;
+use error::ResultExt;
 use store::Result;
 use store::Entry;
+use storeid::StoreId;
 
 pub trait Mapper {
-    fn read_to_fs<R: Read>(&self, &mut R, &mut HashMap<PathBuf, Entry>)   -> Result<()>;
+    /// Read the entries of a document for a store at `base` into the map
+    ///
+    /// The keys of the map are the pathes relative to `base`.
+    fn read_to_fs<R: Read>(&self, &mut R, &PathBuf, &mut HashMap<PathBuf, Entry>) -> Result<()>;
     fn fs_to_write<W: Write>(&self, &mut HashMap<PathBuf, Entry>, &mut W) -> Result<()>;
 }
 
 pub mod json;
+pub mod toml;
+pub mod yaml;
+
+/// Check whether a document with the version `version` can be read by this version of the store
+///
+/// Documents written by a newer version of imag cannot be read.
+fn check_version(version: &str) -> Result<()> {
+    ::semver::Version::parse(version)
+        .chain_err(|| SEK::VersionError)
+        .and_then(|doc_vers| {
+            // safe because cargo does not compile if crate version is not valid
+            let crate_version = ::semver::Version::parse(version!()).unwrap();
+
+            debug!("Document version vs. own version: {doc_vers} > {crate_vers}",
+                   doc_vers = doc_vers,
+                   crate_vers = crate_version);
+
+            if doc_vers > crate_version {
+                Err(SE::from_kind(SEK::VersionError))
+            } else {
+                Ok(())
+            }
+        })
+}
+
+/// Make the path `id` of an entry in a document relative to the store at `base`
+///
+/// Documents written by older versions of imag contain absolute pathes.
+fn local_path(base: &PathBuf, id: PathBuf) -> PathBuf {
+    id.strip_prefix(base).map(PathBuf::from).unwrap_or(id)
+}
+
+/// Build an Entry from the parts of an entry in a document
+fn entry_from_parts(id: PathBuf, header: Value, content: String) -> Result<Entry> {
+    StoreId::new_baseless(id)
+        .map(Entry::new)
+        .and_then(|mut entry| {
+            *entry.get_header_mut()  = header;
+            *entry.get_content_mut() = content;
+            entry.verify().map(|_| entry)
+        })
+}
+
+/// Get the entries of the store sorted by their path, so the documents are written in a stable
+/// order
+fn sorted_entries(hm: &mut HashMap<PathBuf, Entry>) -> Vec<(PathBuf, Entry)> {
+    let mut entries = hm.drain().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
 
@@ -0,0 +1,230 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! A Mapper for a TOML document with an array of tables
+//!
+//! ```toml
+//! version = "0.4.0"
+//!
+//! [[entries]]
+//! id = "example"
+//! content = "hi there!"
+//!
+//! [entries.header.imag]
+//! version = "0.4.0"
+//! ```
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use toml;
+use toml::Value;
+
+use error::StoreErrorKind as SEK;
+use error::ResultExt;
+use super::Mapper;
+use store::Result;
+use store::Entry;
+
+// The order of the fields matters for serialization, as TOML requires the tables to come after the
+// plain values
+#[derive(Debug, Deserialize, Serialize)]
+struct BackendEntry {
+    id: PathBuf,
+    content: String,
+    header: Value,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Document {
+    version: String,
+
+    #[serde(default)]
+    entries: Vec<BackendEntry>,
+}
+
+pub struct TomlMapper;
+
+impl TomlMapper {
+
+    pub fn new() -> TomlMapper {
+        TomlMapper
+    }
+
+}
+
+impl Mapper for TomlMapper {
+    fn read_to_fs<R: Read>(&self, r: &mut R, base: &PathBuf, hm: &mut HashMap<PathBuf, Entry>)
+        -> Result<()>
+    {
+        let document = {
+            debug!("Reading Document");
+            let mut s = String::new();
+            try!(r.read_to_string(&mut s).chain_err(|| SEK::IoError));
+            debug!("Document = {:?}", s);
+            debug!("Parsing Document");
+            let doc : Document = try!(toml::de::from_str(&s).chain_err(|| SEK::IoError));
+            debug!("Document = {:?}", doc);
+            doc
+        };
+
+        let _ = try!(super::check_version(&document.version));
+
+        for backend_entry in document.entries {
+            let id    = super::local_path(base, backend_entry.id);
+            let entry = try!(super::entry_from_parts(id.clone(),
+                                                     backend_entry.header,
+                                                     backend_entry.content));
+            hm.insert(id, entry);
+        }
+
+        Ok(())
+    }
+
+    fn fs_to_write<W: Write>(&self, hm: &mut HashMap<PathBuf, Entry>, out: &mut W) -> Result<()> {
+        let entries = super::sorted_entries(hm)
+            .into_iter()
+            .map(|(id, entry)| {
+                BackendEntry {
+                    id:      id,
+                    content: entry.get_content().clone(),
+                    header:  entry.get_header().clone(),
+                }
+            })
+            .collect();
+
+        let doc = Document {
+            version: String::from(version!()),
+            entries: entries,
+        };
+
+        toml::ser::to_string_pretty(&doc)
+            .chain_err(|| SEK::IoError)
+            .and_then(|toml| out.write_all(toml.as_bytes()).chain_err(|| SEK::IoError))
+            .and_then(|_| out.flush().chain_err(|| SEK::IoError))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use storeid::StoreId;
+
+    #[test]
+    fn test_empty_toml_to_fs() {
+        let toml = r#"version = "0.4.0""#;
+        let mut toml = Cursor::new(String::from(toml).into_bytes());
+        let mapper   = TomlMapper::new();
+        let mut hm   = HashMap::new();
+
+        assert!(mapper.read_to_fs(&mut toml, &PathBuf::from("/"), &mut hm).is_ok());
+        assert!(hm.is_empty());
+    }
+
+    #[test]
+    fn test_toml_to_fs() {
+        let toml = r#"
+        version = "0.4.0"
+
+        [[entries]]
+        id = "example"
+        content = "test"
+
+        [entries.header.imag]
+        version = "0.4.0"
+
+        [[entries]]
+        id = "other/example"
+        content = """
+multiple
+lines
+"""
+
+        [entries.header.foo]
+        bar = 1
+
+        [entries.header.imag]
+        version = "0.4.0"
+        "#;
+        let mut toml = Cursor::new(String::from(toml).into_bytes());
+        let mapper   = TomlMapper::new();
+        let mut hm   = HashMap::new();
+
+        let io_res = mapper.read_to_fs(&mut toml, &PathBuf::from("/"), &mut hm);
+        assert!(io_res.is_ok(), format!("Not ok: {:?}", io_res));
+        assert_eq!(2, hm.len());
+
+        let other = hm.get(&PathBuf::from("other/example")).unwrap();
+        assert_eq!("multiple\nlines\n", other.get_content());
+        assert_eq!(Some(&Value::Integer(1)),
+                   other.get_header().get("foo").and_then(|foo| foo.get("bar")));
+    }
+
+    #[test]
+    fn test_toml_without_imag_header_fails() {
+        let toml = r#"
+        version = "0.4.0"
+
+        [[entries]]
+        id = "example"
+        content = "test"
+
+        [entries.header.foo]
+        bar = 1
+        "#;
+        let mut toml = Cursor::new(String::from(toml).into_bytes());
+        let mapper   = TomlMapper::new();
+        let mut hm   = HashMap::new();
+
+        assert!(mapper.read_to_fs(&mut toml, &PathBuf::from("/"), &mut hm).is_err());
+    }
+
+    #[test]
+    fn test_fs_to_toml_to_fs() {
+        let mapper                    = TomlMapper::new();
+        let mut out : Cursor<Vec<u8>> = Cursor::new(vec![]);
+
+        let mut hm = {
+            let mut hm = HashMap::new();
+            for id in &["a", "b"] {
+                let id        = PathBuf::from(id);
+                let mut entry = Entry::new(StoreId::new_baseless(id.clone()).unwrap());
+                *entry.get_content_mut() = String::from("hi there!\n---\n");
+                hm.insert(id, entry);
+            }
+            hm
+        };
+
+        assert!(mapper.fs_to_write(&mut hm, &mut out).is_ok());
+
+        let mut input = Cursor::new(out.into_inner());
+        let mut read  = HashMap::new();
+        assert!(mapper.read_to_fs(&mut input, &PathBuf::from("/"), &mut read).is_ok());
+
+        assert_eq!(2, read.len());
+        for id in &["a", "b"] {
+            let entry = read.get(&PathBuf::from(id)).unwrap();
+            assert_eq!("hi there!\n---\n", entry.get_content());
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! A Mapper for a stream of YAML documents
+//!
+//! Each entry is one document in the stream:
+//!
+//! ```yaml
+//! ---
+//! version: 0.4.0
+//! id: example
+//! header:
+//!   imag:
+//!     version: 0.4.0
+//! content: hi there!
+//! ```
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use serde_yaml;
+use toml::Value;
+
+use error::StoreErrorKind as SEK;
+use error::ResultExt;
+use super::Mapper;
+use store::Result;
+use store::Entry;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Document {
+    version: String,
+    id: PathBuf,
+    header: Value,
+    content: String,
+}
+
+pub struct YamlMapper;
+
+impl YamlMapper {
+
+    pub fn new() -> YamlMapper {
+        YamlMapper
+    }
+
+}
+
+/// Split a stream into its documents
+///
+/// Documents are seperated by a line containing only `---` (which may also start the stream) or
+/// ended by a line containing only `...`.
+fn split_documents(s: &str) -> Vec<String> {
+    let mut documents = vec![];
+    let mut current   = String::new();
+
+    for line in s.lines() {
+        match line.trim_right() {
+            "---" | "..." => {
+                if !current.trim().is_empty() {
+                    documents.push(current.clone());
+                }
+                current.clear();
+            },
+            _ => {
+                current.push_str(line);
+                current.push('\n');
+            },
+        }
+    }
+
+    if !current.trim().is_empty() {
+        documents.push(current);
+    }
+
+    documents
+}
+
+impl Mapper for YamlMapper {
+    fn read_to_fs<R: Read>(&self, r: &mut R, base: &PathBuf, hm: &mut HashMap<PathBuf, Entry>)
+        -> Result<()>
+    {
+        let mut s = String::new();
+        try!(r.read_to_string(&mut s).chain_err(|| SEK::IoError));
+        debug!("Document stream = {:?}", s);
+
+        for document in split_documents(&s) {
+            debug!("Parsing Document");
+            let doc : Document = try!(serde_yaml::from_str(&document).chain_err(|| SEK::IoError));
+            debug!("Document = {:?}", doc);
+
+            let _     = try!(super::check_version(&doc.version));
+            let id    = super::local_path(base, doc.id);
+            let entry = try!(super::entry_from_parts(id.clone(), doc.header, doc.content));
+            hm.insert(id, entry);
+        }
+
+        Ok(())
+    }
+
+    fn fs_to_write<W: Write>(&self, hm: &mut HashMap<PathBuf, Entry>, out: &mut W) -> Result<()> {
+        for (id, entry) in super::sorted_entries(hm) {
+            let doc = Document {
+                version: String::from(version!()),
+                id:      id,
+                header:  entry.get_header().clone(),
+                content: entry.get_content().clone(),
+            };
+
+            let yaml = try!(serde_yaml::to_string(&doc).chain_err(|| SEK::IoError));
+            try!(out.write_all(yaml.as_bytes()).chain_err(|| SEK::IoError));
+            try!(out.write_all(b"\n").chain_err(|| SEK::IoError));
+        }
+
+        out.flush().chain_err(|| SEK::IoError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use storeid::StoreId;
+
+    #[test]
+    fn test_empty_yaml_to_fs() {
+        let mut yaml = Cursor::new(String::new().into_bytes());
+        let mapper   = YamlMapper::new();
+        let mut hm   = HashMap::new();
+
+        assert!(mapper.read_to_fs(&mut yaml, &PathBuf::from("/"), &mut hm).is_ok());
+        assert!(hm.is_empty());
+    }
+
+    #[test]
+    fn test_yaml_to_fs() {
+        let yaml = r#"---
+version: 0.4.0
+id: example
+header:
+  imag:
+    version: 0.4.0
+content: test
+---
+version: 0.4.0
+id: other/example
+header:
+  imag:
+    version: 0.4.0
+  foo:
+    bar: 1
+content: |
+  multiple
+  lines
+"#;
+        let mut yaml = Cursor::new(String::from(yaml).into_bytes());
+        let mapper   = YamlMapper::new();
+        let mut hm   = HashMap::new();
+
+        let io_res = mapper.read_to_fs(&mut yaml, &PathBuf::from("/"), &mut hm);
+        assert!(io_res.is_ok(), format!("Not ok: {:?}", io_res));
+        assert_eq!(2, hm.len());
+
+        let other = hm.get(&PathBuf::from("other/example")).unwrap();
+        assert_eq!("multiple\nlines\n", other.get_content());
+        assert_eq!(Some(&Value::Integer(1)),
+                   other.get_header().get("foo").and_then(|foo| foo.get("bar")));
+    }
+
+    #[test]
+    fn test_yaml_with_newer_version_fails() {
+        let yaml = r#"---
+version: 100.0.0
+id: example
+header:
+  imag:
+    version: 0.4.0
+content: test
+"#;
+        let mut yaml = Cursor::new(String::from(yaml).into_bytes());
+        let mapper   = YamlMapper::new();
+        let mut hm   = HashMap::new();
+
+        assert!(mapper.read_to_fs(&mut yaml, &PathBuf::from("/"), &mut hm).is_err());
+    }
+
+    #[test]
+    fn test_yaml_with_absolute_id_to_fs() {
+        let yaml = r#"---
+version: 0.4.0
+id: /tmp/store/example
+header:
+  imag:
+    version: 0.4.0
+content: test
+"#;
+        let mut yaml = Cursor::new(String::from(yaml).into_bytes());
+        let mapper   = YamlMapper::new();
+        let mut hm   = HashMap::new();
+
+        let io_res = mapper.read_to_fs(&mut yaml, &PathBuf::from("/tmp/store"), &mut hm);
+        assert!(io_res.is_ok(), format!("Not ok: {:?}", io_res));
+        assert!(hm.get(&PathBuf::from("example")).is_some());
+    }
+
+    #[test]
+    fn test_fs_to_yaml_to_fs() {
+        let mapper                    = YamlMapper::new();
+        let mut out : Cursor<Vec<u8>> = Cursor::new(vec![]);
+
+        let mut hm = {
+            let mut hm = HashMap::new();
+            for id in &["a", "b"] {
+                let id        = PathBuf::from(id);
+                let mut entry = Entry::new(StoreId::new_baseless(id.clone()).unwrap());
+                *entry.get_content_mut() = String::from("hi there!\n---\n");
+                hm.insert(id, entry);
+            }
+            hm
+        };
+
+        assert!(mapper.fs_to_write(&mut hm, &mut out).is_ok());
+
+        let mut input = Cursor::new(out.into_inner());
+        let mut read  = HashMap::new();
+        assert!(mapper.read_to_fs(&mut input, &PathBuf::from("/"), &mut read).is_ok());
+
+        assert_eq!(2, read.len());
+        for id in &["a", "b"] {
+            let entry = read.get(&PathBuf::from(id)).unwrap();
+            assert_eq!("hi there!\n---\n", entry.get_content());
+        }
+    }
+}
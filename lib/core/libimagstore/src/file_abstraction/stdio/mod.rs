@@ -17,7 +17,6 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{Read, Write};
@@ -53,14 +52,27 @@ impl<W, M> StdIoFileAbstraction<W, M>
           W: Write
 {
 
-    pub fn new<R: Read>(in_stream: &mut R, out_stream: Rc<RefCell<W>>, mapper: M) -> Result<StdIoFileAbstraction<W, M>, SE> {
-        StdoutFileAbstraction::new(out_stream, mapper)
+    /// Create a new StdIoFileAbstraction for a store at `base`, reading the store from `in_stream`
+    ///
+    /// The pathes in the read document are relative to `base`.
+    pub fn new<R: Read>(in_stream: &mut R, out_stream: Arc<Mutex<W>>, mapper: M, base: PathBuf)
+        -> Result<StdIoFileAbstraction<W, M>, SE>
+    {
+        StdoutFileAbstraction::new(out_stream, mapper, base)
             .and_then(|out| {
+                let mut local = HashMap::new();
+                let _ = try!(out.mapper().read_to_fs(in_stream, out.base(), &mut local));
+
                 let _ = try!(out
                              .backend()
                              .lock()
                              .map_err(|_| SE::from_kind(SEK::LockError))
-                             .map(|mut mtx| out.mapper().read_to_fs(in_stream, mtx.get_mut())));
+                             .map(|mut mtx| {
+                                 let backend = mtx.get_mut();
+                                 for (path, entry) in local.drain() {
+                                     backend.insert(out.base().join(path), entry);
+                                 }
+                             }));
 
                 Ok(StdIoFileAbstraction(out))
             })
@@ -93,7 +105,10 @@ impl<W, M> Deref for StdIoFileAbstraction<W, M>
 }
 
 // basically #[derive(FileAbstraction)]
-impl<W: Write, M: Mapper> FileAbstraction for StdIoFileAbstraction<W, M> {
+impl<W, M> FileAbstraction for StdIoFileAbstraction<W, M>
+    where M: Mapper + Send + Sync,
+          W: Write + Send
+{
 
     fn remove_file(&self, path: &PathBuf) -> Result<(), SE> {
         self.0.remove_file(path)
@@ -111,6 +126,10 @@ impl<W: Write, M: Mapper> FileAbstraction for StdIoFileAbstraction<W, M> {
         self.0.create_dir_all(pb)
     }
 
+    fn exists(&self, pb: &PathBuf) -> Result<bool, SE> {
+        self.0.exists(pb)
+    }
+
     fn new_instance(&self, p: PathBuf) -> Box<FileAbstractionInstance> {
         self.0.new_instance(p)
     }
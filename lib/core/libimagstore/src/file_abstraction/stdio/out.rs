@@ -19,7 +19,6 @@
 
 //! A StdIoFileAbstraction which does not read from stdin.
 
-use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -50,7 +49,8 @@ type Backend = Arc<Mutex<RefCell<HashMap<PathBuf, Entry>>>>;
 pub struct StdoutFileAbstraction<W: Write, M: Mapper> {
     mapper: M,
     mem: InMemoryFileAbstraction,
-    out: Rc<RefCell<W>>,
+    out: Arc<Mutex<W>>,
+    base: PathBuf,
 }
 
 impl<W, M> StdoutFileAbstraction<W, M>
@@ -58,11 +58,18 @@ impl<W, M> StdoutFileAbstraction<W, M>
           W: Write
 {
 
-    pub fn new(out_stream: Rc<RefCell<W>>, mapper: M) -> Result<StdoutFileAbstraction<W, M>, SE> {
+    /// Create a new StdoutFileAbstraction for a store at `base`
+    ///
+    /// The pathes in the written document are relative to `base`, so the document can be read
+    /// into a store at another location.
+    pub fn new(out_stream: Arc<Mutex<W>>, mapper: M, base: PathBuf)
+        -> Result<StdoutFileAbstraction<W, M>, SE>
+    {
         Ok(StdoutFileAbstraction {
             mapper: mapper,
             mem:    InMemoryFileAbstraction::new(),
             out:    out_stream,
+            base:   base,
         })
     }
 
@@ -82,6 +89,11 @@ impl<W, M> StdoutFileAbstraction<W, M>
         &self.mapper
     }
 
+    /// The path of the store the pathes in the backend are relative to
+    pub fn base(&self) -> &PathBuf {
+        &self.base
+    }
+
 }
 
 impl<W, M> Debug for StdoutFileAbstraction<W, M>
@@ -103,7 +115,20 @@ impl<W, M> Drop for StdoutFileAbstraction<W, M>
         let fill_res = match self.mem.backend().lock() {
             Err(_) => Err(SE::from_kind(SEK::LockError)),
             Ok(mut mtx) => {
-                self.mapper.fs_to_write(mtx.get_mut(), self.out.borrow_mut().deref_mut())
+                let base = &self.base;
+                let mut local = mtx
+                    .get_mut()
+                    .drain()
+                    .map(|(path, entry)| {
+                        let path = path.strip_prefix(base).map(PathBuf::from).unwrap_or(path);
+                        (path, entry)
+                    })
+                    .collect();
+
+                match self.out.lock() {
+                    Err(_) => Err(SE::from_kind(SEK::LockError)),
+                    Ok(mut out) => self.mapper.fs_to_write(&mut local, out.deref_mut()),
+                }
             },
         };
 
@@ -114,7 +139,10 @@ impl<W, M> Drop for StdoutFileAbstraction<W, M>
     }
 }
 
-impl<W: Write, M: Mapper> FileAbstraction for StdoutFileAbstraction<W, M> {
+impl<W, M> FileAbstraction for StdoutFileAbstraction<W, M>
+    where M: Mapper + Send + Sync,
+          W: Write + Send
+{
 
     fn remove_file(&self, path: &PathBuf) -> Result<(), SE> {
         self.mem.remove_file(path)
@@ -132,6 +160,10 @@ impl<W: Write, M: Mapper> FileAbstraction for StdoutFileAbstraction<W, M> {
         self.mem.create_dir_all(pb)
     }
 
+    fn exists(&self, pb: &PathBuf) -> Result<bool, SE> {
+        self.mem.exists(pb)
+    }
+
     fn new_instance(&self, p: PathBuf) -> Box<FileAbstractionInstance> {
         self.mem.new_instance(p)
     }
@@ -0,0 +1,53 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::fmt::Debug;
+
+use hook::error::Result as HookResult;
+use store::FileLockEntry;
+use storeid::StoreId;
+
+/// Each hook provides an accessor, which defines what data the hook gets access to
+pub trait HookDataAccessorProvider {
+    fn accessor(&self) -> HookDataAccessor;
+}
+
+/// The type of access a hook wants to have
+pub enum HookDataAccessor<'a> {
+    /// Access to the StoreId only
+    StoreIdAccess(&'a StoreIdAccessor),
+
+    /// Mutable access to the entry, the hook can alter the entry before it is written
+    MutableAccess(&'a MutableHookDataAccessor),
+
+    /// Read-only access to the entry
+    NonMutableAccess(&'a NonMutableHookDataAccessor),
+}
+
+pub trait StoreIdAccessor : Debug {
+    fn access(&self, &StoreId) -> HookResult<()>;
+}
+
+pub trait MutableHookDataAccessor : Debug {
+    fn access_mut(&self, &mut FileLockEntry) -> HookResult<()>;
+}
+
+pub trait NonMutableHookDataAccessor : Debug {
+    fn access(&self, &FileLockEntry) -> HookResult<()>;
+}
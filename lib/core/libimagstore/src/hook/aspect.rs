@@ -0,0 +1,124 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::sync::Arc;
+
+use toml::Value;
+
+use libimagutil::iter::FoldResult;
+
+use hook::Hook;
+use hook::accessor::{StoreIdAccessor, MutableHookDataAccessor, NonMutableHookDataAccessor};
+use hook::accessor::HookDataAccessor as HDA;
+use hook::error::HookError as HE;
+use hook::error::HookErrorKind as HEK;
+use hook::error::Result as HookResult;
+use hook::error::ResultExt;
+use store::FileLockEntry;
+use storeid::StoreId;
+
+/// An aspect is a named collection of hooks which are executed in the order they were registered
+///
+/// Which aspects exist at which position is defined via the configuration file.
+///
+/// Aspects are cheap to clone, as the hooks are shared between the clones. The store clones the
+/// aspects before executing them, so hooks can call store operations themselves.
+#[derive(Debug, Clone)]
+pub struct Aspect {
+    cfg: Option<Value>,
+    name: String,
+    hooks: Vec<Arc<Hook>>,
+}
+
+impl Aspect {
+
+    pub fn new(name: String, cfg: Option<Value>) -> Aspect {
+        Aspect {
+            cfg: cfg,
+            name: name,
+            hooks: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Get the configuration of the aspect (`[store.aspects.<name>]`), if any
+    pub fn config(&self) -> Option<&Value> {
+        self.cfg.as_ref()
+    }
+
+    pub fn register_hook(&mut self, h: Box<Hook>) {
+        self.hooks.push(Arc::from(h));
+    }
+
+    /// Remove all hooks from the aspect, keeping its configuration
+    pub fn clear_hooks(&mut self) {
+        self.hooks.clear();
+    }
+
+}
+
+impl StoreIdAccessor for Aspect {
+
+    fn access(&self, id: &StoreId) -> HookResult<()> {
+        self.hooks.iter().fold_result(|hook| {
+            debug!("[Aspect][{}] Executing hook '{}'", self.name, hook.name());
+            match hook.accessor() {
+                HDA::StoreIdAccess(accessor) => accessor.access(id),
+                _ => Err(HE::from_kind(HEK::AccessTypeViolation)),
+            }
+            .chain_err(|| HEK::HookExecutionError)
+        })
+    }
+
+}
+
+impl MutableHookDataAccessor for Aspect {
+
+    fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+        self.hooks.iter().fold_result(|hook| {
+            debug!("[Aspect][{}] Executing hook '{}'", self.name, hook.name());
+            match hook.accessor() {
+                HDA::StoreIdAccess(accessor)    => accessor.access(fle.get_location()),
+                HDA::MutableAccess(accessor)    => accessor.access_mut(fle),
+                HDA::NonMutableAccess(accessor) => accessor.access(fle),
+            }
+            .chain_err(|| HEK::HookExecutionError)
+        })
+    }
+
+}
+
+impl NonMutableHookDataAccessor for Aspect {
+
+    fn access(&self, fle: &FileLockEntry) -> HookResult<()> {
+        self.hooks.iter().fold_result(|hook| {
+            debug!("[Aspect][{}] Executing hook '{}'", self.name, hook.name());
+            match hook.accessor() {
+                HDA::StoreIdAccess(accessor)    => accessor.access(fle.get_location()),
+                HDA::NonMutableAccess(accessor) => accessor.access(fle),
+                HDA::MutableAccess(_)           => Err(HE::from_kind(HEK::AccessTypeViolation)),
+            }
+            .chain_err(|| HEK::HookExecutionError)
+        })
+    }
+
+}
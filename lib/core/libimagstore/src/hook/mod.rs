@@ -0,0 +1,72 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Hooks for the store
+//!
+//! Hooks are pieces of code which are run by the store around its core operations (`create()`,
+//! `retrieve()`, `update()`, `delete()` and `move_by_id()`). Hooks are not registered directly at
+//! a position, but inside an `Aspect`. The aspects which exist for each position are defined in
+//! the `[store]` section of the configuration file, for example:
+//!
+//! ```toml
+//! [store]
+//! pre-create-hook-aspects  = [ "debug" ]
+//! post-update-hook-aspects = [ "debug", "vcs" ]
+//!
+//! [store.aspects.debug]
+//!
+//! [store.hooks.my-debug-hook]
+//! verbose = true
+//! ```
+//!
+//! A hook which is registered for an aspect which is not configured for a position cannot be
+//! registered.
+//!
+//! Hooks which are executed _before_ an operation can veto the operation by returning an error.
+//! Hooks which are executed _after_ an operation cannot undo it, but their errors are reported to
+//! the caller of the store operation.
+
+use std::fmt::Debug;
+
+use toml::Value;
+
+pub mod accessor;
+pub mod aspect;
+pub mod error;
+pub mod position;
+
+use self::accessor::HookDataAccessorProvider;
+
+/// A hook which can be registered in the `Store`
+///
+/// Hooks have to be `Send + Sync`, so a `Store` can be shared between threads.
+pub trait Hook : HookDataAccessorProvider + Debug + Send + Sync {
+
+    /// The name of the hook
+    ///
+    /// The name is used to look up the configuration of the hook in `[store.hooks.<name>]`.
+    fn name(&self) -> &'static str;
+
+    /// Set the configuration of the hook
+    ///
+    /// Called by the store when the hook gets registered, with the `[store.hooks.<name>]` section
+    /// of the configuration (or an empty table if there is none).
+    fn set_config(&mut self, cfg: &Value);
+
+}
@@ -0,0 +1,97 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::fmt::{Display, Formatter};
+use std::fmt::Error as FmtError;
+
+/// The positions in the store where hooks can be executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookPosition {
+    StoreUnload,
+
+    PreCreate,
+    PostCreate,
+    PreRetrieve,
+    PostRetrieve,
+    PreUpdate,
+    PostUpdate,
+    PreDelete,
+    PostDelete,
+    PreMove,
+    PostMove,
+}
+
+impl HookPosition {
+
+    /// Get all hook positions
+    pub fn all() -> Vec<HookPosition> {
+        vec![
+            HookPosition::StoreUnload,
+            HookPosition::PreCreate,
+            HookPosition::PostCreate,
+            HookPosition::PreRetrieve,
+            HookPosition::PostRetrieve,
+            HookPosition::PreUpdate,
+            HookPosition::PostUpdate,
+            HookPosition::PreDelete,
+            HookPosition::PostDelete,
+            HookPosition::PreMove,
+            HookPosition::PostMove,
+        ]
+    }
+
+    /// Get the name of the configuration key which lists the aspects for this position
+    pub fn config_key(&self) -> &'static str {
+        match *self {
+            HookPosition::StoreUnload  => "store-unload-hook-aspects",
+            HookPosition::PreCreate    => "pre-create-hook-aspects",
+            HookPosition::PostCreate   => "post-create-hook-aspects",
+            HookPosition::PreRetrieve  => "pre-retrieve-hook-aspects",
+            HookPosition::PostRetrieve => "post-retrieve-hook-aspects",
+            HookPosition::PreUpdate    => "pre-update-hook-aspects",
+            HookPosition::PostUpdate   => "post-update-hook-aspects",
+            HookPosition::PreDelete    => "pre-delete-hook-aspects",
+            HookPosition::PostDelete   => "post-delete-hook-aspects",
+            HookPosition::PreMove      => "pre-move-hook-aspects",
+            HookPosition::PostMove     => "post-move-hook-aspects",
+        }
+    }
+
+}
+
+impl Display for HookPosition {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        let s = match *self {
+            HookPosition::StoreUnload  => "store-unload",
+            HookPosition::PreCreate    => "pre-create",
+            HookPosition::PostCreate   => "post-create",
+            HookPosition::PreRetrieve  => "pre-retrieve",
+            HookPosition::PostRetrieve => "post-retrieve",
+            HookPosition::PreUpdate    => "pre-update",
+            HookPosition::PostUpdate   => "post-update",
+            HookPosition::PreDelete    => "pre-delete",
+            HookPosition::PostDelete   => "post-delete",
+            HookPosition::PreMove      => "pre-move",
+            HookPosition::PostMove     => "post-move",
+        };
+        write!(fmt, "{}", s)
+    }
+
+}
@@ -0,0 +1,360 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Templates for the ids of new entries
+//!
+//! Modules which opt into templates build the ids of their new entries from a template which is
+//! configured per module in the store configuration:
+//!
+//! ```toml
+//! [store.id-templates]
+//! notes = "notes/{slug(name)}"
+//! ```
+//!
+//! Only the modules in `TEMPLATE_MODULES` build their ids from templates, templates for other
+//! modules are ignored with a warning when the store is loaded.
+//!
+//! A template consists of text and placeholders in braces. A placeholder is either the name of a
+//! variable (`{name}`) or a filter applied to a variable (`{slug(name)}`). The variables are
+//! provided by the module (see `IdTemplateValues`), the filters are:
+//!
+//! * `slug`: lowercase, all characters which are not alphanumeric are replaced by a `-`
+//! * `lower`: lowercase
+//! * `upper`: uppercase
+//!
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::Datelike;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+
+use error::StoreErrorKind as SEK;
+use error::StoreError as SE;
+use error::ResultExt;
+use store::Result;
+use storeid::StoreId;
+
+/// The modules which build the ids of their new entries from templates
+///
+/// A module which opts into templates has to be added here, so templates for it are loaded.
+pub const TEMPLATE_MODULES : &'static [&'static str] = &["notes"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Slug,
+    Lower,
+    Upper,
+}
+
+impl Filter {
+
+    fn from_name(name: &str) -> Option<Filter> {
+        match name {
+            "slug"  => Some(Filter::Slug),
+            "lower" => Some(Filter::Lower),
+            "upper" => Some(Filter::Upper),
+            _       => None,
+        }
+    }
+
+    fn apply(&self, s: &str) -> String {
+        match *self {
+            Filter::Slug  => slug(s),
+            Filter::Lower => s.to_lowercase(),
+            Filter::Upper => s.to_uppercase(),
+        }
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable { name: String, filter: Option<Filter> },
+}
+
+/// A parsed id template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+impl IdTemplate {
+
+    /// Parse the template for the entries of the module `module`
+    ///
+    /// Fails if the template cannot be parsed, uses an unknown filter or does not start with the
+    /// collection of the module, as all entries of a module have to be in its collection.
+    pub fn parse(module: &str, template: &str) -> Result<IdTemplate> {
+        let invalid = || SE::from_kind(SEK::IdTemplateInvalid(String::from(template)));
+
+        let mut parts = vec![];
+        let mut rest  = template;
+
+        while !rest.is_empty() {
+            match rest.find(|c| c == '{' || c == '}') {
+                None => {
+                    parts.push(Part::Text(String::from(rest)));
+                    rest = "";
+                },
+                Some(pos) if rest[pos..].starts_with('}') => return Err(invalid()),
+                Some(pos) => {
+                    if pos > 0 {
+                        parts.push(Part::Text(String::from(&rest[..pos])));
+                    }
+
+                    let end = try!(rest[pos..].find('}').ok_or_else(&invalid)) + pos;
+                    parts.push(try!(parse_placeholder(&rest[pos + 1..end]).ok_or_else(&invalid)));
+                    rest = &rest[end + 1..];
+                },
+            }
+        }
+
+        let in_module = match parts.first() {
+            Some(&Part::Text(ref text)) => text.split('/').next() == Some(module) &&
+                text.len() > module.len(),
+            _ => false,
+        };
+
+        if !in_module || template.starts_with('/') {
+            return Err(SE::from_kind(SEK::IdTemplateOutsideModule(String::from(template),
+                                                                 String::from(module))));
+        }
+
+        Ok(IdTemplate {
+            template: String::from(template),
+            parts: parts,
+        })
+    }
+
+    /// The template as it was configured
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// The names of the variables which are used in the template
+    pub fn variables(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .filter_map(|part| match *part {
+                Part::Variable { ref name, .. } => Some(name.as_ref()),
+                Part::Text(_)                   => None,
+            })
+            .collect()
+    }
+
+    /// Check that the template only uses the variables in `known`
+    ///
+    /// Modules can use this to tell the user about a misconfigured template before they create an
+    /// entry.
+    pub fn check_variables(&self, known: &[&str]) -> Result<()> {
+        match self.variables().into_iter().find(|var| !known.contains(var)) {
+            Some(var) => Err(SE::from_kind(SEK::IdTemplateUnknownVariable(String::from(var)))),
+            None      => Ok(()),
+        }
+    }
+
+    /// Build the id from the template
+    ///
+    /// Fails if a variable is missing in `values` or if the result is not a valid id for an entry,
+    /// for example because a component of the id is empty or `..`.
+    pub fn render(&self, values: &IdTemplateValues) -> Result<StoreId> {
+        let mut id = String::new();
+
+        for part in self.parts.iter() {
+            match *part {
+                Part::Text(ref text) => id.push_str(text),
+                Part::Variable { ref name, ref filter } => {
+                    let value = try!(values.get(name).ok_or_else(|| {
+                        SE::from_kind(SEK::IdTemplateUnknownVariable(name.clone()))
+                    }));
+
+                    match *filter {
+                        Some(ref filter) => id.push_str(&filter.apply(value)),
+                        None             => id.push_str(value),
+                    }
+                },
+            }
+        }
+
+        let valid = id.split('/').all(|c| !c.is_empty() && !c.starts_with('.'));
+        if !valid {
+            return Err(SE::from_kind(SEK::IdTemplateInvalidId(id)));
+        }
+
+        StoreId::new_baseless(PathBuf::from(id.clone()))
+            .chain_err(|| SEK::IdTemplateInvalidId(id))
+    }
+
+}
+
+/// The values for the variables of an id template
+#[derive(Debug, Clone, Default)]
+pub struct IdTemplateValues(BTreeMap<String, String>);
+
+impl IdTemplateValues {
+
+    pub fn new() -> IdTemplateValues {
+        IdTemplateValues(BTreeMap::new())
+    }
+
+    /// Set the variable `name` to `value`
+    pub fn with<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> IdTemplateValues {
+        self.0.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the date variables to `dt`
+    ///
+    /// These are `yyyy` (year), `mm` (month), `dd` (day), `HH` (hour), `MM` (minute) and `SS`
+    /// (second), all padded with zeros.
+    pub fn with_datetime(self, dt: &NaiveDateTime) -> IdTemplateValues {
+        self.with("yyyy", format!("{:04}", dt.year()))
+            .with("mm",   format!("{:02}", dt.month()))
+            .with("dd",   format!("{:02}", dt.day()))
+            .with("HH",   format!("{:02}", dt.hour()))
+            .with("MM",   format!("{:02}", dt.minute()))
+            .with("SS",   format!("{:02}", dt.second()))
+    }
+
+    /// Set the date variables to the current local time, see `with_datetime()`
+    pub fn with_now(self) -> IdTemplateValues {
+        self.with_datetime(&Local::now().naive_local())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.0.get(name)
+    }
+
+}
+
+/// Append a suffix (`-1`, `-2`, ...) to the id
+///
+/// Used to find an id for a new entry if the id built from the template is already taken.
+pub fn with_suffix(id: &StoreId, n: usize) -> Result<StoreId> {
+    id.to_str().and_then(|s| StoreId::new_baseless(PathBuf::from(format!("{}-{}", s, n))))
+}
+
+fn parse_placeholder(s: &str) -> Option<Part> {
+    let is_name = |s: &str| !s.is_empty() &&
+        s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+    match s.find('(') {
+        None if is_name(s) => Some(Part::Variable { name: String::from(s), filter: None }),
+        None               => None,
+        Some(pos) => {
+            if !s.ends_with(')') {
+                return None;
+            }
+
+            let name = &s[pos + 1..s.len() - 1];
+            match Filter::from_name(&s[..pos]) {
+                Some(filter) if is_name(name) => Some(Part::Variable {
+                    name: String::from(name),
+                    filter: Some(filter),
+                }),
+                _ => None,
+            }
+        },
+    }
+}
+
+fn slug(s: &str) -> String {
+    let mut slug = String::new();
+
+    for c in s.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+
+    slug
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_render_with_filter() {
+        let template = IdTemplate::parse("notes", "notes/{slug(name)}").unwrap();
+        let values   = IdTemplateValues::new().with("name", "Meeting: Project X / Review!");
+
+        assert_eq!(template.variables(), vec!["name"]);
+        assert_eq!(template.render(&values).unwrap().to_str().unwrap(),
+                   "notes/meeting-project-x-review");
+    }
+
+    #[test]
+    fn test_render_with_datetime() {
+        let template = IdTemplate::parse("diary", "diary/{name}/{yyyy}/{mm}/{dd}-{HH}{MM}").unwrap();
+        let dt       = NaiveDate::from_ymd(2017, 3, 9).and_hms(7, 5, 0);
+        let values   = IdTemplateValues::new().with("name", "work").with_datetime(&dt);
+
+        assert_eq!(template.render(&values).unwrap().to_str().unwrap(),
+                   "diary/work/2017/03/09-0705");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(IdTemplate::parse("notes", "notes/{name").is_err());
+        assert!(IdTemplate::parse("notes", "notes/name}").is_err());
+        assert!(IdTemplate::parse("notes", "notes/{}").is_err());
+        assert!(IdTemplate::parse("notes", "notes/{shout(name)}").is_err());
+        assert!(IdTemplate::parse("notes", "notes/{slug(name}").is_err());
+        assert!(IdTemplate::parse("notes", "{name}").is_err());
+        assert!(IdTemplate::parse("notes", "notes").is_err());
+        assert!(IdTemplate::parse("notes", "diary/{name}").is_err());
+        assert!(IdTemplate::parse("notes", "/notes/{name}").is_err());
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let template = IdTemplate::parse("notes", "notes/{name}").unwrap();
+
+        assert!(template.render(&IdTemplateValues::new()).is_err());
+        assert!(template.render(&IdTemplateValues::new().with("name", "..")).is_err());
+        assert!(template.render(&IdTemplateValues::new().with("name", "a//b")).is_err());
+        assert!(template.render(&IdTemplateValues::new().with("name", ".hidden")).is_err());
+
+        let template = IdTemplate::parse("notes", "notes/{slug(name)}").unwrap();
+        assert!(template.render(&IdTemplateValues::new().with("name", "???")).is_err());
+    }
+
+    #[test]
+    fn test_check_variables() {
+        let template = IdTemplate::parse("notes", "notes/{yyyy}/{slug(name)}").unwrap();
+
+        assert!(template.check_variables(&["name", "yyyy"]).is_ok());
+        assert!(template.check_variables(&["name"]).is_err());
+    }
+
+}
@@ -37,6 +37,10 @@
 
 #[macro_use] extern crate log;
 #[macro_use] extern crate version;
+extern crate chrono;
+extern crate crossbeam;
+extern crate diff;
+extern crate fs2;
 extern crate glob;
 #[macro_use] extern crate lazy_static;
 extern crate regex;
@@ -46,6 +50,7 @@ extern crate semver;
 extern crate walkdir;
 #[macro_use] extern crate is_match;
 extern crate serde_json;
+extern crate serde_yaml;
 #[macro_use] extern crate serde_derive;
 #[macro_use] extern crate error_chain;
 
@@ -56,7 +61,14 @@ extern crate libimagutil;
 
 pub mod storeid;
 pub mod error;
+pub mod hook;
 pub mod store;
-mod configuration;
+pub mod migration;
+pub mod conflict;
+pub mod trash;
+pub mod idtemplate;
+#[cfg(feature = "verify")]
+pub mod verify;
+pub mod configuration;
 pub mod file_abstraction;
 
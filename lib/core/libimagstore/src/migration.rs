@@ -0,0 +1,393 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Migrations of entry headers between versions of imag
+//!
+//! Every entry header carries the version of imag which wrote it in `imag.version`. If a library
+//! changes the layout of its header section, it provides a `Migration` which transforms the
+//! headers of entries written by older versions.
+//!
+//! The migrations are collected in a `MigrationRegistry`, which applies all pending migrations to
+//! an entry (in the order of their versions) and sets the `imag.version` of the entry to the
+//! target version of the registry.
+//!
+//! Libraries which provide migrations expose a `register_migrations()` function with the
+//! signature of `RegisterMigrations`, which registers them in a registry.
+
+use std::fmt::Debug;
+
+use semver::Version;
+use toml::Value;
+
+use error::StoreError as SE;
+use error::StoreErrorKind as SEK;
+use error::ResultExt;
+use store::Entry;
+use store::Result;
+
+/// A transformation of entries from one version of imag to another
+pub trait Migration : Debug {
+
+    /// The name of the migration, used in error messages and reports
+    fn name(&self) -> &'static str;
+
+    /// The migration is applied to entries with this version or newer
+    fn from_version(&self) -> Version;
+
+    /// The migration is applied to entries older than this version. After the migration, the
+    /// entry is considered to be of this version.
+    fn to_version(&self) -> Version;
+
+    /// Transform the entry
+    fn migrate(&self, entry: &mut Entry) -> Result<()>;
+
+}
+
+/// A function with which a library registers its migrations in a `MigrationRegistry`
+pub type RegisterMigrations = fn(&mut MigrationRegistry) -> Result<()>;
+
+/// A set of migrations, which migrates entries to a target version
+#[derive(Debug)]
+pub struct MigrationRegistry {
+    target: Version,
+    migrations: Vec<Box<Migration>>,
+}
+
+impl MigrationRegistry {
+
+    /// Create a registry which migrates entries to the version of this imag
+    pub fn new() -> MigrationRegistry {
+        let target = Version::parse(version!()).expect("Version of libimagstore is not semver");
+        MigrationRegistry::with_target_version(target)
+    }
+
+    /// Create a registry which migrates entries to `target`
+    pub fn with_target_version(target: Version) -> MigrationRegistry {
+        MigrationRegistry {
+            target: target,
+            migrations: vec![],
+        }
+    }
+
+    /// The version entries are migrated to
+    pub fn target_version(&self) -> &Version {
+        &self.target
+    }
+
+    /// Register a migration
+    ///
+    /// # Return value
+    ///
+    /// On error:
+    ///  - InvalidMigration(name) if the migration does not migrate to a newer version or migrates
+    ///    to a version newer than the target version of the registry
+    ///
+    pub fn register(&mut self, migration: Box<Migration>) -> Result<()> {
+        if migration.from_version() >= migration.to_version() ||
+            migration.to_version() > self.target
+        {
+            let name = String::from(migration.name());
+            return Err(SE::from_kind(SEK::InvalidMigration(name)));
+        }
+
+        debug!("Registering migration '{}' ({} -> {})",
+               migration.name(),
+               migration.from_version(),
+               migration.to_version());
+
+        let position = self.migrations
+            .iter()
+            .position(|m| m.to_version() > migration.to_version())
+            .unwrap_or(self.migrations.len());
+        self.migrations.insert(position, migration);
+        Ok(())
+    }
+
+    /// Register the migrations of several libraries
+    ///
+    /// # Return value
+    ///
+    /// On error:
+    ///  - Errors the registration functions might return
+    ///
+    pub fn register_all(&mut self, registrations: &[RegisterMigrations]) -> Result<()> {
+        for register in registrations {
+            try!(register(self));
+        }
+        Ok(())
+    }
+
+    /// Check whether an entry has to be migrated, because it is older than the target version
+    ///
+    /// # Return value
+    ///
+    /// On error:
+    ///  - Errors `entry_version()` might return
+    ///  - EntryVersionNewer(version) if the entry was written by a newer version of imag
+    ///
+    pub fn needs_migration(&self, entry: &Entry) -> Result<bool> {
+        let version = try!(entry_version(entry));
+        if version > self.target {
+            return Err(SE::from_kind(SEK::EntryVersionNewer(version.to_string())));
+        }
+        Ok(version < self.target)
+    }
+
+    /// Apply all pending migrations to the entry and set its version to the target version
+    ///
+    /// Returns whether the entry was altered, which is not the case if it is up to date already.
+    /// If a migration fails, the entry might be partially migrated, so it should not be written in
+    /// this case.
+    ///
+    /// # Return value
+    ///
+    /// On error:
+    ///  - Errors `MigrationRegistry::needs_migration()` might return
+    ///  - MigrationError(name) if a migration failed
+    ///
+    pub fn migrate_entry(&self, entry: &mut Entry) -> Result<bool> {
+        if !try!(self.needs_migration(entry)) {
+            return Ok(false);
+        }
+
+        let mut version = try!(entry_version(entry));
+        for migration in self.migrations.iter() {
+            if migration.from_version() <= version && version < migration.to_version() {
+                debug!("Applying migration '{}' to '{}'", migration.name(), entry.get_location());
+                let name = String::from(migration.name());
+                try!(migration.migrate(entry).chain_err(|| SEK::MigrationError(name)));
+                version = migration.to_version();
+            }
+        }
+
+        try!(set_entry_version(entry, &self.target));
+        Ok(true)
+    }
+
+}
+
+/// Get the version from the `imag.version` header field of an entry
+///
+/// # Return value
+///
+/// On error:
+///  - MissingVersionInfo() if the header field is missing or not a semantic version
+///
+pub fn entry_version(entry: &Entry) -> Result<Version> {
+    match *entry.get_header() {
+        Value::Table(ref t) => t.get("imag"),
+        _ => None,
+    }
+    .and_then(|imag| match *imag {
+        Value::Table(ref imag) => imag.get("version"),
+        _ => None,
+    })
+    .and_then(|version| match *version {
+        Value::String(ref s) => Version::parse(s).ok(),
+        _ => None,
+    })
+    .ok_or(SE::from_kind(SEK::MissingVersionInfo))
+}
+
+fn set_entry_version(entry: &mut Entry, version: &Version) -> Result<()> {
+    match *entry.get_header_mut() {
+        Value::Table(ref mut t) => match t.get_mut("imag") {
+            Some(&mut Value::Table(ref mut imag)) => {
+                imag.insert(String::from("version"), Value::String(version.to_string()));
+                Ok(())
+            },
+            _ => Err(SE::from_kind(SEK::MissingMainSection)),
+        },
+        _ => Err(SE::from_kind(SEK::HeaderTypeFailure)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use semver::Version;
+    use toml::Value;
+
+    use super::Migration;
+    use super::MigrationRegistry;
+    use super::entry_version;
+    use super::set_entry_version;
+    use error::StoreError as SE;
+    use error::StoreErrorKind as SEK;
+    use store::Entry;
+    use store::Result;
+    use storeid::StoreId;
+
+    /// Renames the header section "old" to "new"
+    #[derive(Debug)]
+    struct RenameSection(&'static str, &'static str);
+
+    impl Migration for RenameSection {
+        fn name(&self) -> &'static str { "rename-section" }
+        fn from_version(&self) -> Version { Version::parse(self.0).unwrap() }
+        fn to_version(&self) -> Version { Version::parse(self.1).unwrap() }
+
+        fn migrate(&self, entry: &mut Entry) -> Result<()> {
+            match *entry.get_header_mut() {
+                Value::Table(ref mut t) => {
+                    if let Some(old) = t.remove("old") {
+                        t.insert(String::from("new"), old);
+                    }
+                    Ok(())
+                },
+                _ => Err(SE::from_kind(SEK::HeaderTypeFailure)),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Failing;
+
+    impl Migration for Failing {
+        fn name(&self) -> &'static str { "failing" }
+        fn from_version(&self) -> Version { Version::parse("0.1.0").unwrap() }
+        fn to_version(&self) -> Version { Version::parse("0.2.0").unwrap() }
+
+        fn migrate(&self, _: &mut Entry) -> Result<()> {
+            Err(SE::from_kind(SEK::HeaderTypeFailure))
+        }
+    }
+
+    fn entry_with_version(version: &str) -> Entry {
+        let id        = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        let mut entry = Entry::new(id);
+        set_entry_version(&mut entry, &Version::parse(version).unwrap()).unwrap();
+
+        match *entry.get_header_mut() {
+            Value::Table(ref mut t) => {
+                t.insert(String::from("old"), Value::Table(::toml::value::Table::new()));
+            },
+            _ => panic!("Header is not a table"),
+        }
+        entry
+    }
+
+    fn registry() -> MigrationRegistry {
+        MigrationRegistry::with_target_version(Version::parse("0.4.0").unwrap())
+    }
+
+    fn has_section(entry: &Entry, name: &str) -> bool {
+        match *entry.get_header() {
+            Value::Table(ref t) => t.contains_key(name),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_register_rejects_invalid_migrations() {
+        let mut reg = registry();
+        assert!(reg.register(Box::new(RenameSection("0.3.0", "0.2.0"))).is_err());
+        assert!(reg.register(Box::new(RenameSection("0.3.0", "0.5.0"))).is_err());
+        assert!(reg.register(Box::new(RenameSection("0.2.0", "0.3.0"))).is_ok());
+    }
+
+    #[test]
+    fn test_migrate_entry_applies_pending_migration() {
+        let mut reg = registry();
+        assert!(reg.register(Box::new(RenameSection("0.2.0", "0.3.0"))).is_ok());
+
+        let mut entry = entry_with_version("0.2.1");
+        assert!(reg.migrate_entry(&mut entry).unwrap());
+        assert!(has_section(&entry, "new"));
+        assert!(!has_section(&entry, "old"));
+        assert_eq!(entry_version(&entry).unwrap(), Version::parse("0.4.0").unwrap());
+    }
+
+    #[test]
+    fn test_migrate_entry_skips_migrations_for_other_versions() {
+        let mut reg = registry();
+        assert!(reg.register(Box::new(RenameSection("0.1.0", "0.2.0"))).is_ok());
+
+        let mut entry = entry_with_version("0.3.0");
+        assert!(reg.migrate_entry(&mut entry).unwrap());
+        assert!(has_section(&entry, "old"));
+        assert_eq!(entry_version(&entry).unwrap(), Version::parse("0.4.0").unwrap());
+    }
+
+    #[test]
+    fn test_migrate_entry_chains_migrations() {
+        let mut reg = registry();
+        assert!(reg.register(Box::new(RenameSection("0.2.0", "0.3.0"))).is_ok());
+        assert!(reg.register(Box::new(Failing)).is_ok());
+
+        // Failing migrates 0.1.0 -> 0.2.0 and must be applied first
+        let mut entry = entry_with_version("0.1.0");
+        let res = reg.migrate_entry(&mut entry);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err().kind(), &SEK::MigrationError(_)));
+
+        let mut entry = entry_with_version("0.2.0");
+        assert!(reg.migrate_entry(&mut entry).unwrap());
+        assert!(has_section(&entry, "new"));
+    }
+
+    #[test]
+    fn test_migrate_entry_bumps_version_without_migrations() {
+        let reg       = registry();
+        let mut entry = entry_with_version("0.2.0");
+        assert!(reg.needs_migration(&entry).unwrap());
+        assert!(reg.migrate_entry(&mut entry).unwrap());
+        assert!(has_section(&entry, "old"));
+        assert_eq!(entry_version(&entry).unwrap(), Version::parse("0.4.0").unwrap());
+        assert!(!reg.needs_migration(&entry).unwrap());
+    }
+
+    #[test]
+    fn test_register_all() {
+        fn register(reg: &mut MigrationRegistry) -> Result<()> {
+            reg.register(Box::new(RenameSection("0.2.0", "0.3.0")))
+        }
+
+        fn register_invalid(reg: &mut MigrationRegistry) -> Result<()> {
+            reg.register(Box::new(RenameSection("0.3.0", "0.5.0")))
+        }
+
+        let mut reg = registry();
+        assert!(reg.register_all(&[register]).is_ok());
+
+        let mut entry = entry_with_version("0.2.0");
+        assert!(reg.migrate_entry(&mut entry).unwrap());
+        assert!(has_section(&entry, "new"));
+
+        assert!(registry().register_all(&[register, register_invalid]).is_err());
+    }
+
+    #[test]
+    fn test_migrate_entry_up_to_date() {
+        let reg       = registry();
+        let mut entry = entry_with_version("0.4.0");
+        assert!(!reg.migrate_entry(&mut entry).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_entry_newer_than_target() {
+        let reg       = registry();
+        let mut entry = entry_with_version("0.5.0");
+        let res       = reg.migrate_entry(&mut entry);
+        assert!(res.is_err());
+        assert!(is_match!(res.unwrap_err().kind(), &SEK::EntryVersionNewer(_)));
+    }
+
+}
@@ -23,6 +23,7 @@ use std::ops::Drop;
 use std::path::PathBuf;
 use std::result::Result as RResult;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
 use std::io::Read;
 use std::convert::Into;
@@ -42,6 +43,16 @@ use error::{StoreError as SE, StoreErrorKind as SEK};
 use error::ResultExt;
 use storeid::{IntoStoreId, StoreId, StoreIdIterator};
 use file_abstraction::FileAbstractionInstance;
+use file_abstraction::FileStamp;
+use file_abstraction::LockWait;
+use hook::Hook;
+use hook::aspect::Aspect;
+use hook::position::HookPosition;
+use conflict::Conflict;
+use idtemplate::{IdTemplate, IdTemplateValues, with_suffix};
+use util::transaction_file_path;
+#[cfg(feature = "verify")]
+use verify::{VerifyOptions, VerifyReport, verify_store};
 
 // We re-export the following things so tests can use them
 pub use file_abstraction::FileAbstraction;
@@ -50,6 +61,7 @@ pub use file_abstraction::InMemoryFileAbstraction;
 
 use libimagerror::trace::trace_error;
 use libimagutil::debug_result::*;
+use libimagutil::iter::FoldResult;
 
 use self::glob_store_iter::*;
 
@@ -70,6 +82,12 @@ struct StoreEntry {
     id: StoreId,
     file: Box<FileAbstractionInstance>,
     status: StoreEntryStatus,
+
+    /// The entry as it was read from (or last written to) the backend, `None` if it did not exist
+    base: Option<Entry>,
+
+    /// The stamp of the file of `base`, to detect modifications in the backend
+    stamp: Option<FileStamp>,
 }
 
 pub enum StoreObject {
@@ -143,20 +161,21 @@ impl StoreEntry {
     fn new(id: StoreId, backend: &Box<FileAbstraction>) -> Result<StoreEntry> {
         let pb = try!(id.clone().into_pathbuf());
 
-        #[cfg(feature = "fs-lock")]
-        {
-            try!(open_file(pb.clone())
-                .and_then(|f| f.lock_exclusive().chain_err(|| SEK::FileError))
-                .chain_err(|| SEK::IoError));
-        }
-
         Ok(StoreEntry {
             id: id,
             file: backend.new_instance(pb),
             status: StoreEntryStatus::Present,
+            base: None,
+            stamp: None,
         })
     }
 
+    /// Remember `base` as the version of the entry in the backend, with the stamp of its file
+    fn set_base(&mut self, base: Option<Entry>, stamp: Option<FileStamp>) {
+        self.base  = base;
+        self.stamp = stamp;
+    }
+
     /// The entry is currently borrowed, meaning that some thread is currently
     /// mutating it
     fn is_borrowed(&self) -> bool {
@@ -165,13 +184,20 @@ impl StoreEntry {
 
     fn get_entry(&mut self) -> Result<Entry> {
         if !self.is_borrowed() {
-            self.file
+            // The stamp is taken first, so a modification while reading is detected later
+            let stamp = try!(self.file.stamp());
+            let base  = try!(self.file
                 .get_file_content(self.id.clone())
+                .map(Some)
                 .or_else(|err| if is_match!(err.kind(), &SEK::FileNotFound) {
-                    Ok(Entry::new(self.id.clone()))
+                    Ok(None)
                 } else {
                     Err(err)
-                })
+                }));
+
+            let entry = base.clone().unwrap_or_else(|| Entry::new(self.id.clone()));
+            self.set_base(base, stamp);
+            Ok(entry)
         } else {
             Err(SE::from_kind(SEK::EntryAlreadyBorrowed))
         }
@@ -189,18 +215,6 @@ impl StoreEntry {
     }
 }
 
-#[cfg(feature = "fs-lock")]
-impl Drop for StoreEntry {
-
-    fn drop(self) {
-        self.get_entry()
-            .and_then(|entry| open_file(entry.get_location().clone()).chain_err(|| SEK::IoError))
-            .and_then(|f| f.unlock().chain_err(|| SEK::FileError))
-            .chain_err(|| SEK::IoError)
-    }
-
-}
-
 
 /// The Store itself, through this object one can interact with IMAG's entries
 pub struct Store {
@@ -214,16 +228,27 @@ pub struct Store {
     ///
     /// Internal Path->File cache map
     ///
-    /// Caches the files, so they remain flock()ed
+    /// Caches the files, so they remain flock()ed while they are borrowed
     ///
     /// Could be optimized for a threadsafe HashMap
     ///
     entries: Arc<RwLock<HashMap<StoreId, StoreEntry>>>,
 
+    ///
+    /// The hook aspects for each hook position, as configured in the store configuration
+    ///
+    aspects: Arc<Mutex<HashMap<HookPosition, Vec<Aspect>>>>,
+
     /// The backend to use
     ///
     /// This provides the filesystem-operation functions (or pretends to)
     backend: Box<FileAbstraction>,
+
+    /// How to wait for entries which are locked by another process
+    lock_wait: LockWait,
+
+    /// The templates for the ids of new entries, per module
+    id_templates: BTreeMap<String, IdTemplate>,
 }
 
 impl Store {
@@ -286,11 +311,37 @@ impl Store {
             return Err(SE::from_kind(SEK::StorePathExists));
         }
 
+        let aspects = HookPosition::all()
+            .into_iter()
+            .map(|position| {
+                let aspects = get_aspect_names_for_position(&position, store_config.as_ref())
+                    .into_iter()
+                    .map(|name| {
+                        let cfg = get_config_for_aspect(&name, store_config.as_ref());
+                        Aspect::new(name, cfg)
+                    })
+                    .collect::<Vec<Aspect>>();
+
+                debug!("Aspects for {}: {:?}", position, aspects);
+                (position, aspects)
+            })
+            .collect();
+
+        let lock_wait = config_lock_wait(store_config.as_ref());
+        debug!("Waiting for locked entries: {:?}", lock_wait);
+
+        let id_templates = try!(get_id_templates(store_config.as_ref())
+                                .chain_err(|| SEK::ConfigurationError));
+        debug!("Id templates: {:?}", id_templates);
+
         let store = Store {
             location: location.clone(),
             configuration: store_config,
             entries: Arc::new(RwLock::new(HashMap::new())),
+            aspects: Arc::new(Mutex::new(aspects)),
             backend: backend,
+            lock_wait: lock_wait,
+            id_templates: id_templates,
         };
 
         debug!("Store building succeeded");
@@ -333,62 +384,129 @@ impl Store {
         self.configuration.as_ref()
     }
 
-    /// Verify the store.
+    /// Register a hook `h` for the aspect named `aspect_name` at the hook position `position`
     ///
-    /// This function is not intended to be called by normal programs but only by `imag-store`.
-    #[cfg(feature = "verify")]
-    pub fn verify(&self) -> bool {
-        use libimagerror::trace::trace_error_dbg;
+    /// Before the hook is registered, it gets its configuration from `[store.hooks.<hook name>]`
+    /// (or an empty table, if there is no such section).
+    ///
+    /// # Return value
+    ///
+    /// On success: ()
+    ///
+    /// On error:
+    ///  - HookRegisterError(LockPoisoned()) if the internal lock is poisoned.
+    ///  - HookRegisterError(AspectNameNotFoundError()) if there is no aspect with this name
+    ///    configured for the position.
+    ///
+    pub fn register_hook(&self, position: HookPosition, aspect_name: &str, mut h: Box<Hook>)
+        -> Result<()>
+    {
+        use configuration::get_config_for_hook;
 
-        info!("Header | Content length | Path");
-        info!("-------+----------------+-----");
+        debug!("Registering hook '{}' for aspect '{}' at {}", h.name(), aspect_name, position);
 
-        WalkDir::new(self.location.clone())
-            .into_iter()
-            .all(|res| match res {
-                Ok(dent) => {
-                    if dent.file_type().is_file() {
-                        match self.get(PathBuf::from(dent.path())) {
-                            Ok(Some(fle)) => {
-                                let p           = fle.get_location();
-                                let content_len = fle.get_content().len();
-                                let header      = if fle.get_header().verify().is_ok() {
-                                    "ok"
-                                } else {
-                                    "broken"
-                                };
-
-                                info!("{: >6} | {: >14} | {:?}", header, content_len, p.deref());
-                                true
-                            },
+        let cfg = get_config_for_hook(h.name(), self.configuration.as_ref())
+            .unwrap_or_else(|| Value::Table(BTreeMap::new()));
+        h.set_config(&cfg);
 
-                            Ok(None) => {
-                                info!("{: >6} | {: >14} | {:?}", "?", "couldn't load", dent.path());
-                                true
-                            },
+        let mut aspects = try!(self.aspects
+            .lock()
+            .map_err(|_| SE::from_kind(SEK::LockPoisoned))
+            .chain_err(|| SEK::HookRegisterError));
 
-                            Err(e) => {
-                                trace_error_dbg(&e);
-                                if_cfg_panic!("Error verifying: {:?}", e);
-                                debug!("{:?}", e);
-                                false
-                            },
-                        }
-                    } else {
-                        info!("{: >6} | {: >14} | {:?}", "?", "<no file>", dent.path());
-                        true
-                    }
-                },
+        aspects
+            .get_mut(&position)
+            .and_then(|asps| asps.iter_mut().find(|a| a.name() == aspect_name))
+            .map(|aspect| aspect.register_hook(h))
+            .ok_or(SE::from_kind(SEK::AspectNameNotFoundError))
+            .chain_err(|| SEK::HookRegisterError)
+    }
 
-                Err(e) => {
-                    trace_error_dbg(&e);
-                    if_cfg_panic!("Error verifying: {:?}", e);
-                    debug!("{:?}", e);
-                    false
-                },
+    /// Remove all registered hooks from all aspects
+    ///
+    /// The aspects themselves stay configured, so hooks can be registered again afterwards.
+    ///
+    /// # Return value
+    ///
+    /// On success: ()
+    ///
+    /// On error:
+    ///  - LockPoisoned() if the internal lock is poisoned.
+    ///
+    pub fn unregister_hooks(&self) -> Result<()> {
+        debug!("Unregistering all hooks");
+        self.aspects
+            .lock()
+            .map_err(|_| SE::from_kind(SEK::LockPoisoned))
+            .map(|mut aspects| {
+                for aspect in aspects.values_mut().flat_map(|asps| asps.iter_mut()) {
+                    aspect.clear_hooks();
+                }
+            })
+    }
+
+    /// Get a copy of the aspects for `position`
+    ///
+    /// The aspects are copied so the lock is not held while the hooks are executed, as hooks may
+    /// call store operations themselves.
+    fn aspects_for(&self, position: HookPosition) -> Result<Vec<Aspect>> {
+        self.aspects
+            .lock()
+            .map_err(|_| SE::from_kind(SEK::LockPoisoned))
+            .map(|aspects| aspects.get(&position).cloned().unwrap_or_default())
+    }
+
+    fn execute_hooks_for_id(&self, position: HookPosition, id: &StoreId) -> Result<()> {
+        use hook::accessor::StoreIdAccessor;
+
+        debug!("Executing {} hooks for '{}'", position, id);
+        self.aspects_for(position)
+            .and_then(|aspects| {
+                aspects
+                    .iter()
+                    .fold_result(|aspect| StoreIdAccessor::access(aspect, id))
+                    .chain_err(|| SEK::HookExecutionError)
+            })
+    }
+
+    fn execute_hooks_for_mut_file(&self, position: HookPosition, fle: &mut FileLockEntry)
+        -> Result<()>
+    {
+        use hook::accessor::MutableHookDataAccessor;
+
+        debug!("Executing {} hooks for '{}'", position, fle.get_location());
+        self.aspects_for(position)
+            .and_then(|aspects| {
+                aspects
+                    .iter()
+                    .fold_result(|aspect| aspect.access_mut(fle))
+                    .chain_err(|| SEK::HookExecutionError)
+            })
+    }
+
+    fn execute_hooks_for_file(&self, position: HookPosition, fle: &FileLockEntry) -> Result<()> {
+        use hook::accessor::NonMutableHookDataAccessor;
+
+        debug!("Executing {} hooks for '{}'", position, fle.get_location());
+        self.aspects_for(position)
+            .and_then(|aspects| {
+                aspects
+                    .iter()
+                    .fold_result(|aspect| NonMutableHookDataAccessor::access(aspect, fle))
+                    .chain_err(|| SEK::HookExecutionError)
             })
     }
 
+    /// Verify the files of the store
+    ///
+    /// See the `verify` module for the problems which are found and how they are fixed.
+    ///
+    /// This function is not intended to be called by normal programs but only by `imag-store`.
+    #[cfg(feature = "verify")]
+    pub fn verify(&self, opts: &VerifyOptions) -> Result<VerifyReport> {
+        verify_store(&self.location, &*self.backend, &self.lock_wait, opts)
+    }
+
     /// Creates the Entry at the given location (inside the entry)
     ///
     /// # Return value
@@ -399,12 +517,19 @@ impl Store {
     ///  - Errors StoreId::into_storeid() might return
     ///  - CreateCallError(LockPoisoned()) if the internal lock is poisened.
     ///  - CreateCallError(EntryAlreadyExists()) if the entry exists already.
+    ///  - CreateCallError(PreHookExecuteError()) if a pre-create hook failed.
+    ///  - CreateCallError(PostHookExecuteError()) if a post-create hook failed. The entry is not
+    ///    written in this case.
     ///
     pub fn create<'a, S: IntoStoreId>(&'a self, id: S) -> Result<FileLockEntry<'a>> {
         let id = try!(id.into_storeid()).with_base(self.path().clone());
 
         debug!("Creating id: '{}'", id);
 
+        try!(self.execute_hooks_for_id(HookPosition::PreCreate, &id)
+             .chain_err(|| SEK::PreHookExecuteError)
+             .chain_err(|| SEK::CreateCallError));
+
         {
             let mut hsmap = match self.entries.write() {
                 Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)).chain_err(|| SEK::CreateCallError),
@@ -418,6 +543,7 @@ impl Store {
             hsmap.insert(id.clone(), {
                 debug!("Creating: '{}'", id);
                 let mut se = try!(StoreEntry::new(id.clone(), &self.backend));
+                try!(se.file.lock(&self.lock_wait).chain_err(|| SEK::CreateCallError));
                 se.status = StoreEntryStatus::Borrowed;
                 se
             });
@@ -425,7 +551,16 @@ impl Store {
 
         debug!("Constructing FileLockEntry: '{}'", id);
 
-        Ok(FileLockEntry::new(self, Entry::new(id)))
+        let mut fle = FileLockEntry::new(self, Entry::new(id));
+        if let Err(e) = self.execute_hooks_for_mut_file(HookPosition::PostCreate, &mut fle) {
+            // The create failed, so the entry must not be written when it is dropped
+            debug!("Post-create hook failed for '{}', releasing it", fle.get_location());
+            fle.discard();
+            return Err(e)
+                .chain_err(|| SEK::PostHookExecuteError)
+                .chain_err(|| SEK::CreateCallError);
+        }
+        Ok(fle)
     }
 
     /// Borrow a given Entry. When the `FileLockEntry` is either `update`d or
@@ -441,10 +576,17 @@ impl Store {
     /// On error:
     ///  - Errors StoreId::into_storeid() might return
     ///  - RetrieveCallError(LockPoisoned()) if the internal lock is poisened.
+    ///  - RetrieveCallError(PreHookExecuteError()) if a pre-retrieve hook failed.
+    ///  - RetrieveCallError(PostHookExecuteError()) if a post-retrieve hook failed. The changes
+    ///    of the hooks are not written in this case.
     ///
     pub fn retrieve<'a, S: IntoStoreId>(&'a self, id: S) -> Result<FileLockEntry<'a>> {
         let id = try!(id.into_storeid()).with_base(self.path().clone());
         debug!("Retrieving id: '{}'", id);
+
+        try!(self.execute_hooks_for_id(HookPosition::PreRetrieve, &id)
+             .chain_err(|| SEK::PreHookExecuteError)
+             .chain_err(|| SEK::RetrieveCallError));
         let entry = try!({
             self.entries
                 .write()
@@ -452,15 +594,36 @@ impl Store {
                 .and_then(|mut es| {
                     let new_se = try!(StoreEntry::new(id.clone(), &self.backend));
                     let se = es.entry(id.clone()).or_insert(new_se);
-                    let entry = se.get_entry();
-                    se.status = StoreEntryStatus::Borrowed;
-                    entry
+                    if se.is_borrowed() {
+                        return Err(SE::from_kind(SEK::EntryAlreadyBorrowed));
+                    }
+
+                    try!(se.file.lock(&self.lock_wait));
+                    match se.get_entry() {
+                        Ok(entry) => {
+                            se.status = StoreEntryStatus::Borrowed;
+                            Ok(entry)
+                        },
+                        Err(e) => {
+                            let _ = se.file.unlock();
+                            Err(e)
+                        },
+                    }
                 })
                 .chain_err(|| SEK::RetrieveCallError)
         });
 
         debug!("Constructing FileLockEntry: '{}'", id);
-        Ok(FileLockEntry::new(self, entry))
+        let mut fle = FileLockEntry::new(self, entry);
+        if let Err(e) = self.execute_hooks_for_mut_file(HookPosition::PostRetrieve, &mut fle) {
+            // The hooks might have altered the entry, which must not be written
+            debug!("Post-retrieve hook failed for '{}', releasing it", fle.get_location());
+            fle.discard();
+            return Err(e)
+                .chain_err(|| SEK::PostHookExecuteError)
+                .chain_err(|| SEK::RetrieveCallError);
+        }
+        Ok(fle)
     }
 
     /// Get an entry from the store if it exists.
@@ -478,14 +641,7 @@ impl Store {
 
         debug!("Getting id: '{}'", id);
 
-        let exists = try!(id.exists()) || try!(self.entries
-            .read()
-            .map(|map| map.contains_key(&id))
-            .map_err(|_| SE::from_kind(SEK::LockPoisoned))
-            .chain_err(|| SEK::GetCallError)
-        );
-
-        if !exists {
+        if !try!(self.entry_exists(&id).chain_err(|| SEK::GetCallError)) {
             debug!("Does not exist in internal cache or filesystem: {:?}", id);
             return Ok(None);
         }
@@ -493,6 +649,59 @@ impl Store {
         self.retrieve(id).map(Some).chain_err(|| SEK::GetCallError)
     }
 
+    /// Check whether an entry exists in the internal cache or the backend
+    fn entry_exists(&self, id: &StoreId) -> Result<bool> {
+        let id = id.clone().with_base(self.path().clone());
+
+        Ok(try!(id.clone().into_pathbuf().and_then(|pb| self.backend.exists(&pb)))
+            || try!(self.entries
+                .read()
+                .map(|map| map.contains_key(&id))
+                .map_err(|_| SE::from_kind(SEK::LockPoisoned))))
+    }
+
+    /// Get the template for the ids of new entries of the module `module`
+    ///
+    /// Templates are configured in `[store.id-templates]`, see the `idtemplate` module.
+    pub fn id_template(&self, module: &str) -> Option<&IdTemplate> {
+        self.id_templates.get(module)
+    }
+
+    /// Build the id for a new entry of the module `module` from its template
+    ///
+    /// Returns `None` if there is no template for the module, so the module can fall back to its
+    /// own ids. If an entry with the id from the template exists already, a suffix is appended
+    /// (`-1`, `-2`, ...) until the id is not taken.
+    ///
+    /// # Return value
+    ///
+    /// On success: The id, without the store path as base
+    ///
+    /// On error:
+    ///  - IdTemplateUnknownVariable(_) if the template uses a variable which is not in `values`
+    ///  - IdTemplateInvalidId(_) if the template resulted in an invalid id
+    ///
+    pub fn id_from_template(&self, module: &str, values: &IdTemplateValues)
+        -> Result<Option<StoreId>>
+    {
+        let template = match self.id_template(module) {
+            Some(template) => template,
+            None           => return Ok(None),
+        };
+
+        let id = try!(template.render(values));
+
+        let mut candidate = id.clone();
+        let mut n         = 0;
+        while try!(self.entry_exists(&candidate)) {
+            n += 1;
+            candidate = try!(with_suffix(&id, n));
+        }
+
+        debug!("Id from template '{}': '{}'", template.as_str(), candidate);
+        Ok(Some(candidate))
+    }
+
     /// Iterate over all StoreIds for one module name
     ///
     /// # Returns
@@ -533,11 +742,38 @@ impl Store {
 
     /// Return the `FileLockEntry` and write to disk
     ///
+    /// The pre-update hooks are executed before the entry is written and may alter the entry or
+    /// veto the write, the post-update hooks are executed afterwards.
+    ///
+    /// If the entry was modified in the backend since it was retrieved (for example by a text
+    /// editor), the entry is not written and the update fails with `EntryModifiedInBackend`. See
+    /// `Store::get_conflict()` and `Store::resolve_conflict()` on how to handle this.
+    ///
     /// See `Store::_update()`.
     ///
     pub fn update<'a>(&'a self, entry: &mut FileLockEntry<'a>) -> Result<()> {
         debug!("Updating FileLockEntry at '{}'", entry.get_location());
-        self._update(entry, false).chain_err(|| SEK::UpdateCallError)
+        self.update_with_hooks(entry, false).chain_err(|| SEK::UpdateCallError)
+    }
+
+    /// Internal method to write to the filesystem store, executing the update hooks around the
+    /// write.
+    fn update_with_hooks<'a>(&'a self, entry: &mut FileLockEntry<'a>, modify_presence: bool)
+        -> Result<()>
+    {
+        if let Err(e) = self.execute_hooks_for_mut_file(HookPosition::PreUpdate, entry) {
+            if modify_presence {
+                // The entry is not written, but it must not stay borrowed
+                debug!("Pre-update hook vetoed '{}', releasing it", entry.get_location());
+                try!(self.release(&entry.location));
+            }
+            return Err(e).chain_err(|| SEK::PreHookExecuteError);
+        }
+
+        try!(self._update(entry, modify_presence));
+
+        self.execute_hooks_for_file(HookPosition::PostUpdate, entry)
+            .chain_err(|| SEK::PostHookExecuteError)
     }
 
     /// Internal method to write to the filesystem store.
@@ -567,101 +803,409 @@ impl Store {
 
         assert!(se.is_borrowed(), "Tried to update a non borrowed entry.");
 
-        debug!("Verifying Entry");
-        try!(entry.entry.verify());
+        let res = self.write_unmodified(se, &entry.entry);
 
-        debug!("Writing Entry");
-        try!(se.write_entry(&entry.entry));
         if modify_presence {
+            // The entry is released even if it could not be written, otherwise it would stay
+            // borrowed (and locked) until the store is dropped
             se.status = StoreEntryStatus::Present;
+            try!(se.file.unlock());
+        }
+
+        res
+    }
+
+    /// Internal method to verify `entry` and write it to the file of `se`, if the file was not
+    /// modified in the backend since it was read.
+    fn write_unmodified(&self, se: &mut StoreEntry, entry: &Entry) -> Result<()> {
+        debug!("Verifying Entry");
+        try!(entry.verify());
+        try!(self.check_unmodified(se));
+
+        debug!("Writing Entry");
+        try!(se.write_entry(entry));
+        let stamp = try!(se.file.stamp());
+        se.set_base(Some(entry.clone()), stamp);
+        Ok(())
+    }
+
+    /// Internal method to check that the entry of `se` was not modified in the backend since it
+    /// was read.
+    ///
+    /// Only the stamp of the file is compared, the file is not read.
+    fn check_unmodified(&self, se: &StoreEntry) -> Result<()> {
+        debug!("Checking '{}' for modifications in the backend", se.id);
+        let current = try!(self.backend_stamp(&se.id));
+
+        if current != se.stamp {
+            debug!("'{}' was modified in the backend", se.id);
+            return Err(SE::from_kind(SEK::EntryModifiedInBackend));
         }
 
         Ok(())
     }
 
-    /// Retrieve a copy of a given entry, this cannot be used to mutate
-    /// the one on disk
+    /// Internal method to get the stamp of the file of an entry in the backend
+    fn backend_stamp(&self, id: &StoreId) -> Result<Option<FileStamp>> {
+        let pb = try!(id.clone().into_pathbuf());
+        self.backend.new_instance(pb).stamp()
+    }
+
+    /// Get the stamp of the file of an entry in the backend, without reading the entry
+    ///
+    /// The stamp changes whenever the entry is written, so it can be used to find out whether an
+    /// entry was changed since it was read, for example to keep a cache up to date.
     ///
     /// # Return value
     ///
-    /// On success: Entry
+    /// On success: `None` if the entry does not exist in the backend, the stamp otherwise.
     ///
     /// On error:
-    ///  - RetrieveCopyCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
-    ///  - RetrieveCopyCallError(IdLocked()) if the Entry is borrowed currently
-    ///  - Errors StoreEntry::new() might return
+    ///  - Errors StoreId::into_storeid() might return
+    ///  - Errors the backend might return
     ///
-    pub fn retrieve_copy<S: IntoStoreId>(&self, id: S) -> Result<Entry> {
+    pub fn file_stamp<S: IntoStoreId>(&self, id: S) -> Result<Option<FileStamp>> {
         let id = try!(id.into_storeid()).with_base(self.path().clone());
-        debug!("Retrieving copy of '{}'", id);
-        let entries = match self.entries.write() {
-            Err(_) => {
-                return Err(SE::from_kind(SEK::LockPoisoned))
-                    .chain_err(|| SEK::RetrieveCopyCallError);
-            },
+        self.backend_stamp(&id)
+    }
+
+    /// Internal method to read the current version of an entry from the backend, bypassing the
+    /// cache
+    fn read_backend(&self, id: &StoreId) -> Result<Option<Entry>> {
+        let pb = try!(id.clone().into_pathbuf());
+        match self.backend.new_instance(pb).get_file_content(id.clone()) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(ref e) if is_match!(e.kind(), &SEK::FileNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the conflict between a borrowed entry and its version in the backend
+    ///
+    /// # Return value
+    ///
+    /// On success: `None` if the entry was not modified in the backend since it was retrieved,
+    /// `Some(Conflict)` otherwise.
+    ///
+    /// On error:
+    ///  - LockPoisoned() if the internal lock is poisened.
+    ///  - IdNotFound() if the entry is not borrowed from this store
+    ///  - Errors the backend might return when reading the entry
+    ///
+    pub fn get_conflict<'a>(&'a self, entry: &FileLockEntry<'a>) -> Result<Option<Conflict>> {
+        let hsmap = match self.entries.read() {
+            Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)),
             Ok(e) => e,
         };
 
-        // if the entry is currently modified by the user, we cannot drop it
-        if entries.get(&id).map(|e| e.is_borrowed()).unwrap_or(false) {
-            return Err(SE::from_kind(SEK::IdLocked)).chain_err(|| SEK::RetrieveCopyCallError);
+        let se = try!(hsmap.get(&entry.location).ok_or(SE::from_kind(SEK::IdNotFound)));
+
+        if try!(self.backend_stamp(&entry.location)) == se.stamp {
+            return Ok(None);
         }
 
-        try!(StoreEntry::new(id, &self.backend)).get_entry()
+        let theirs = try!(self.read_backend(&entry.location));
+        Ok(Some(Conflict::new(se.base.clone(), entry.entry.clone(), theirs)))
     }
 
-    /// Delete an entry
+    /// Mark the conflict between a borrowed entry and its version in the backend as resolved
     ///
-    /// # Return value
+    /// The current version in the backend is taken as the new base of the entry, so the next
+    /// update of the entry overwrites it. The changes from the backend should be merged into the
+    /// entry before, see `Store::get_conflict()`.
+    pub fn resolve_conflict<'a>(&'a self, entry: &FileLockEntry<'a>) -> Result<()> {
+        let mut hsmap = match self.entries.write() {
+            Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)),
+            Ok(e) => e,
+        };
+
+        let se = try!(hsmap.get_mut(&entry.location).ok_or(SE::from_kind(SEK::IdNotFound)));
+
+        // If the file was replaced, the instance is re-opened on the new file, so the next update
+        // writes to the new file
+        try!(se.file.lock(&self.lock_wait));
+        let stamp  = try!(self.backend_stamp(&entry.location));
+        let theirs = try!(self.read_backend(&entry.location));
+        se.set_base(theirs, stamp);
+        Ok(())
+    }
+
+    /// Begin a new transaction on the store
     ///
-    /// On success: ()
+    /// See `Transaction` for details.
+    pub fn transaction<'a>(&'a self) -> Transaction<'a> {
+        Transaction::new(self)
+    }
+
+    /// Internal method to write several entries to the store in one go.
     ///
-    /// On error:
-    ///  - DeleteCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
-    ///  - DeleteCallError(FileNotFound()) if the StoreId refers to a non-existing entry.
-    ///  - DeleteCallError(FileError()) if the internals failed to remove the file.
+    /// All entries are written to temporary files next to their actual files first. Only if this
+    /// worked for all entries, the temporary files are moved in place, keeping backups of the old
+    /// files. If one of these moves fails, the backups are restored, so either all entries are
+    /// written or none of them.
     ///
-    pub fn delete<S: IntoStoreId>(&self, id: S) -> Result<()> {
-        let id = try!(id.into_storeid()).with_base(self.path().clone());
+    /// The update hooks are executed for all entries around the commit. A vetoing pre-update hook
+    /// aborts the whole transaction.
+    ///
+    /// # Assumptions
+    ///
+    /// This method assumes that the entries are released _right after_ the call, hence it is not
+    /// public.
+    ///
+    fn commit_transaction<'a>(&'a self, entries: &mut [FileLockEntry<'a>]) -> Result<()> {
+        for entry in entries.iter_mut() {
+            try!(self.execute_hooks_for_mut_file(HookPosition::PreUpdate, entry)
+                 .chain_err(|| SEK::PreHookExecuteError));
 
-        debug!("Deleting id: '{}'", id);
+            debug!("Verifying Entry");
+            try!(entry.entry.verify());
+        }
 
         {
-            let mut entries = match self.entries.write() {
-                Err(_) => return Err(SE::from_kind(SEK::LockPoisoned))
-                    .chain_err(|| SEK::DeleteCallError),
+            let hsmap = match self.entries.read() {
+                Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)),
                 Ok(e) => e,
             };
 
-            // if the entry is currently modified by the user, we cannot drop it
-            match entries.get(&id) {
-                None => {
-                    return Err(SE::from_kind(SEK::FileNotFound)).chain_err(|| SEK::DeleteCallError)
+            for entry in entries.iter() {
+                let se = try!(hsmap.get(&entry.location).ok_or(SE::from_kind(SEK::IdNotFound)));
+                try!(self.check_unmodified(se));
+            }
+        }
+
+        let pathes = try!(entries
+            .iter()
+            .map(|entry| entry.get_location().clone().into_pathbuf())
+            .collect::<Result<Vec<PathBuf>>>());
+
+        debug!("Writing {} entries to temporary files", entries.len());
+        for (n, (entry, path)) in entries.iter().zip(pathes.iter()).enumerate() {
+            let res = self.backend
+                .new_instance(transaction_file_path(path, "tmp"))
+                .write_file_content(&entry.entry);
+
+            if let Err(e) = res {
+                for path in pathes.iter().take(n + 1) {
+                    let _ = self.backend.remove_file(&transaction_file_path(path, "tmp"));
+                }
+                return Err(e);
+            }
+        }
+
+        debug!("Moving temporary files in place");
+        let mut moved : Vec<(&PathBuf, Option<PathBuf>)> = vec![];
+        for path in pathes.iter() {
+            match self.move_in_place(path) {
+                Ok(backup) => moved.push((path, backup)),
+                Err(e) => {
+                    for path in pathes.iter().skip(moved.len()) {
+                        let _ = self.backend.remove_file(&transaction_file_path(path, "tmp"));
+                    }
+                    return self.rollback_moves(moved).and(Err(e));
                 },
-                Some(e) => if e.is_borrowed() {
-                    return Err(SE::from_kind(SEK::IdLocked)).chain_err(|| SEK::DeleteCallError)
+            }
+        }
+
+        for (_, backup) in moved {
+            if let Some(backup) = backup {
+                if let Err(e) = self.backend.remove_file(&backup) {
+                    warn!("Could not remove backup file {:?}", backup);
+                    trace_error(&e);
                 }
             }
+        }
 
-            // remove the entry first, then the file
-            entries.remove(&id);
-            let pb = try!(id.clone().with_base(self.path().clone()).into_pathbuf());
-            if let Err(e) = self.backend.remove_file(&pb) {
-                return Err(e)
-                    .chain_err(|| SEK::FileError)
-                    .chain_err(|| SEK::DeleteCallError);
+        {
+            let mut hsmap = match self.entries.write() {
+                Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)),
+                Ok(e) => e,
+            };
+
+            for (entry, path) in entries.iter().zip(pathes.into_iter()) {
+                let se = try!(hsmap.get_mut(&entry.location).ok_or(SE::from_kind(SEK::IdNotFound)));
+
+                // The files were replaced, so the instances might point to the old files. The new
+                // files are locked until the entries are released.
+                let mut file = self.backend.new_instance(path);
+                if let Err(e) = file.lock(&self.lock_wait) {
+                    warn!("Could not lock '{}' after committing", entry.get_location());
+                    trace_error(&e);
+                }
+                let stamp = match file.stamp() {
+                    Ok(stamp) => stamp,
+                    Err(e) => {
+                        warn!("Could not get the stamp of '{}' after committing", entry.get_location());
+                        trace_error(&e);
+                        None
+                    },
+                };
+                se.file = file;
+                se.set_base(Some(entry.entry.clone()), stamp);
             }
         }
 
-        debug!("Deleted");
-        Ok(())
+        entries
+            .iter()
+            .fold_result(|entry| self.execute_hooks_for_file(HookPosition::PostUpdate, entry))
+            .chain_err(|| SEK::PostHookExecuteError)
     }
 
-    /// Save a copy of the Entry in another place
-    pub fn save_to(&self, entry: &FileLockEntry, new_id: StoreId) -> Result<()> {
-        debug!("Saving '{}' to '{}'", entry.get_location(), new_id);
-        self.save_to_other_location(entry, new_id, false)
-    }
+    /// Internal method to move the temporary file of a transaction to `path`.
+    ///
+    /// Returns the path of the backup of the old file, if there was an old file.
+    fn move_in_place(&self, path: &PathBuf) -> Result<Option<PathBuf>> {
+        let tmp    = transaction_file_path(path, "tmp");
+        let backup = if try!(self.backend.exists(path)) {
+            let backup = transaction_file_path(path, "bak");
+            try!(self.backend.rename(path, &backup));
+            Some(backup)
+        } else {
+            None
+        };
+
+        match self.backend.rename(&tmp, path) {
+            Ok(()) => Ok(backup),
+            Err(e) => {
+                if let Some(backup) = backup {
+                    try!(self.backend
+                         .rename(&backup, path)
+                         .chain_err(|| SEK::TransactionRollbackError));
+                }
+                Err(e)
+            },
+        }
+    }
+
+    /// Internal method to undo the moves of `Store::move_in_place()`.
+    ///
+    /// Tries to restore all files, even if restoring one of them fails.
+    fn rollback_moves(&self, moved: Vec<(&PathBuf, Option<PathBuf>)>) -> Result<()> {
+        debug!("Rolling back {} moved files", moved.len());
+        moved
+            .into_iter()
+            .rev()
+            .fold(Ok(()), |acc, (path, backup)| {
+                let res = match backup {
+                    Some(backup) => self.backend.rename(&backup, path),
+                    None         => self.backend.remove_file(path),
+                };
+                acc.and(res)
+            })
+            .chain_err(|| SEK::TransactionRollbackError)
+    }
+
+    /// Internal method to give a borrowed entry back to the store without writing it.
+    ///
+    /// If the entry does not exist in the backend (because it was created but never written),
+    /// it is removed from the cache.
+    fn release(&self, id: &StoreId) -> Result<()> {
+        let mut hsmap = match self.entries.write() {
+            Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)),
+            Ok(e) => e,
+        };
+
+        if !try!(id.clone().into_pathbuf().and_then(|pb| self.backend.exists(&pb))) {
+            debug!("Releasing '{}', which was never written", id);
+            return hsmap.remove(id).map(|_| ()).ok_or(SE::from_kind(SEK::IdNotFound));
+        }
+
+        let se = try!(hsmap.get_mut(id).ok_or(SE::from_kind(SEK::IdNotFound)));
+        se.status = StoreEntryStatus::Present;
+        se.file.unlock()
+    }
+
+    /// Retrieve a copy of a given entry, this cannot be used to mutate
+    /// the one on disk
+    ///
+    /// # Return value
+    ///
+    /// On success: Entry
+    ///
+    /// On error:
+    ///  - RetrieveCopyCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
+    ///  - RetrieveCopyCallError(IdLocked()) if the Entry is borrowed currently
+    ///  - Errors StoreEntry::new() might return
+    ///
+    pub fn retrieve_copy<S: IntoStoreId>(&self, id: S) -> Result<Entry> {
+        let id = try!(id.into_storeid()).with_base(self.path().clone());
+        debug!("Retrieving copy of '{}'", id);
+        let entries = match self.entries.write() {
+            Err(_) => {
+                return Err(SE::from_kind(SEK::LockPoisoned))
+                    .chain_err(|| SEK::RetrieveCopyCallError);
+            },
+            Ok(e) => e,
+        };
+
+        // if the entry is currently modified by the user, we cannot drop it
+        if entries.get(&id).map(|e| e.is_borrowed()).unwrap_or(false) {
+            return Err(SE::from_kind(SEK::IdLocked)).chain_err(|| SEK::RetrieveCopyCallError);
+        }
+
+        try!(StoreEntry::new(id, &self.backend)).get_entry()
+    }
+
+    /// Delete an entry
+    ///
+    /// # Return value
+    ///
+    /// On success: ()
+    ///
+    /// On error:
+    ///  - DeleteCallError(LockPoisoned()) if the internal write lock cannot be aquierd.
+    ///  - DeleteCallError(FileNotFound()) if the StoreId refers to a non-existing entry.
+    ///  - DeleteCallError(FileError()) if the internals failed to remove the file.
+    ///  - DeleteCallError(PreHookExecuteError()) if a pre-delete hook failed.
+    ///  - DeleteCallError(PostHookExecuteError()) if a post-delete hook failed.
+    ///
+    pub fn delete<S: IntoStoreId>(&self, id: S) -> Result<()> {
+        let id = try!(id.into_storeid()).with_base(self.path().clone());
+
+        debug!("Deleting id: '{}'", id);
+
+        try!(self.execute_hooks_for_id(HookPosition::PreDelete, &id)
+             .chain_err(|| SEK::PreHookExecuteError)
+             .chain_err(|| SEK::DeleteCallError));
+
+        {
+            let mut entries = match self.entries.write() {
+                Err(_) => return Err(SE::from_kind(SEK::LockPoisoned))
+                    .chain_err(|| SEK::DeleteCallError),
+                Ok(e) => e,
+            };
+
+            let pb = try!(id.clone().with_base(self.path().clone()).into_pathbuf());
+
+            // if the entry is currently modified by the user, we cannot drop it
+            match entries.get(&id) {
+                None => if !try!(self.backend.exists(&pb).chain_err(|| SEK::DeleteCallError)) {
+                    return Err(SE::from_kind(SEK::FileNotFound)).chain_err(|| SEK::DeleteCallError)
+                },
+                Some(e) => if e.is_borrowed() {
+                    return Err(SE::from_kind(SEK::IdLocked)).chain_err(|| SEK::DeleteCallError)
+                }
+            }
+
+            // remove the entry first, then the file
+            entries.remove(&id);
+            if let Err(e) = self.backend.remove_file(&pb) {
+                return Err(e)
+                    .chain_err(|| SEK::FileError)
+                    .chain_err(|| SEK::DeleteCallError);
+            }
+        }
+
+        debug!("Deleted");
+        self.execute_hooks_for_id(HookPosition::PostDelete, &id)
+            .chain_err(|| SEK::PostHookExecuteError)
+            .chain_err(|| SEK::DeleteCallError)
+    }
+
+    /// Save a copy of the Entry in another place
+    pub fn save_to(&self, entry: &FileLockEntry, new_id: StoreId) -> Result<()> {
+        debug!("Saving '{}' to '{}'", entry.get_location(), new_id);
+        self.save_to_other_location(entry, new_id, false)
+    }
 
     /// Save an Entry in another place
     /// Removes the original entry
@@ -718,6 +1262,8 @@ impl Store {
     /// * If the new path already exists
     /// * If the about-to-be-moved entry does not exist
     /// * If the FS-operation failed
+    /// * If a pre-move hook (executed with the old id) or a post-move hook (executed with the new
+    ///   id) failed
     ///
     /// # Warnings
     ///
@@ -733,7 +1279,8 @@ impl Store {
     /// * `a` links to `b`, which does not exist anymore.
     /// * `c` links to `a`, which does exist.
     ///
-    /// So the link is _partly dangling_, so to say.
+    /// So the link is _partly dangling_, so to say. `libimagentrylink` offers a move which
+    /// rewrites the links to the moved entry.
     ///
     pub fn move_by_id(&self, old_id: StoreId, new_id: StoreId) -> Result<()> {
         let new_id = new_id.with_base(self.path().clone());
@@ -741,6 +1288,10 @@ impl Store {
 
         debug!("Moving '{}' to '{}'", old_id, new_id);
 
+        try!(self.execute_hooks_for_id(HookPosition::PreMove, &old_id)
+             .chain_err(|| SEK::PreHookExecuteError)
+             .chain_err(|| SEK::MoveByIdCallError));
+
         {
             let mut hsmap = match self.entries.write() {
                 Err(_) => return Err(SE::from_kind(SEK::LockPoisoned)),
@@ -761,6 +1312,12 @@ impl Store {
             let old_id_pb = try!(old_id.clone().with_base(self.path().clone()).into_pathbuf());
             let new_id_pb = try!(new_id.clone().with_base(self.path().clone()).into_pathbuf());
 
+            if let Some(parent) = new_id_pb.parent() {
+                try!(self.backend
+                     .create_dir_all(&parent.to_path_buf())
+                     .chain_err(|| SEK::EntryRenameError));
+            }
+
             match self.backend.rename(&old_id_pb, &new_id_pb) {
                 Err(e) => return Err(e).chain_err(|| SEK::EntryRenameError),
                 Ok(_) => {
@@ -771,7 +1328,9 @@ impl Store {
                     assert!(hsmap
                             .remove(&old_id)
                             .and_then(|mut entry| {
-                                entry.id = new_id.clone();
+                                // The instance still refers to the old path
+                                entry.id   = new_id.clone();
+                                entry.file = self.backend.new_instance(new_id_pb.clone());
                                 hsmap.insert(new_id.clone(), entry)
                             }).is_none())
                 }
@@ -780,7 +1339,9 @@ impl Store {
         }
 
         debug!("Moved");
-        Ok(())
+        self.execute_hooks_for_id(HookPosition::PostMove, &new_id)
+            .chain_err(|| SEK::PostHookExecuteError)
+            .chain_err(|| SEK::MoveByIdCallError)
     }
 
     /// Get _all_ entries in the store (by id as iterator)
@@ -834,8 +1395,25 @@ impl Drop for Store {
     //
     /// TODO: Unlock them
     ///
+    /// Executes the store-unload hooks for each entry which was touched while the store was
+    /// loaded. Errors are only traced.
+    ///
     fn drop(&mut self) {
         debug!("Dropping store");
+
+        let ids = match self.entries.read() {
+            Err(_) => {
+                warn!("Internal lock poisoned, not executing store-unload hooks");
+                return;
+            },
+            Ok(entries) => entries.keys().cloned().collect::<Vec<StoreId>>(),
+        };
+
+        for id in ids {
+            if let Err(e) = self.execute_hooks_for_id(HookPosition::StoreUnload, &id) {
+                trace_error(&e);
+            }
+        }
     }
 
 }
@@ -844,6 +1422,7 @@ impl Drop for Store {
 pub struct FileLockEntry<'a> {
     store: &'a Store,
     entry: Entry,
+    write_on_drop: bool,
 }
 
 impl<'a> FileLockEntry<'a, > {
@@ -855,6 +1434,23 @@ impl<'a> FileLockEntry<'a, > {
         FileLockEntry {
             store: store,
             entry: entry,
+            write_on_drop: true,
+        }
+    }
+
+    /// Give the entry back to the store without writing it.
+    ///
+    /// Only for internal use.
+    fn discard(mut self) {
+        self.write_on_drop = false;
+    }
+
+    /// Internal method to write (or release) the entry when it is dropped
+    fn write_or_release(&mut self) -> Result<()> {
+        if self.write_on_drop {
+            self.store.update_with_hooks(self, true)
+        } else {
+            self.store.release(&self.entry.location)
         }
     }
 }
@@ -889,8 +1485,11 @@ impl<'a> Drop for FileLockEntry<'a> {
     /// intended for production use, though).
     fn drop(&mut self) {
         use libimagerror::trace::trace_error_dbg;
-        match self.store._update(self, true) {
+        match self.write_or_release() {
             Err(e) => {
+                if is_match!(e.kind(), &SEK::EntryModifiedInBackend) {
+                    warn!("'{}' was modified by someone else, not writing it", self.get_location());
+                }
                 trace_error_dbg(&e);
                 if_cfg_panic!("ERROR WHILE DROPPING: {:?}", e);
             },
@@ -904,12 +1503,86 @@ impl<'a> Drop for FileLockEntry<'a> {
 
     /// This will not silently ignore errors but prints the result of the _update() call for testing
     fn drop(&mut self) {
-        let _ = self.store._update(self, true).map_err(|e| trace_error(&e));
+        let _ = self.write_or_release().map_err(|e| trace_error(&e));
     }
 
 }
 
 
+/// A set of entries which are written to the store together
+///
+/// Entries are added to a transaction after they were altered. `Transaction::commit()` then
+/// writes either all of them or, if one of them cannot be written, none of them.
+///
+/// A transaction which is rolled back or dropped without being committed discards the changes
+/// of all its entries.
+pub struct Transaction<'a> {
+    store: &'a Store,
+    entries: Vec<FileLockEntry<'a>>,
+}
+
+impl<'a> Transaction<'a> {
+
+    fn new(store: &'a Store) -> Transaction<'a> {
+        Transaction {
+            store: store,
+            entries: vec![],
+        }
+    }
+
+    /// Add an entry to the transaction
+    pub fn add(&mut self, entry: FileLockEntry<'a>) -> &mut Transaction<'a> {
+        debug!("Adding '{}' to transaction", entry.get_location());
+        self.entries.push(entry);
+        self
+    }
+
+    /// Get the entries of the transaction, for altering them after they were added
+    pub fn entries_mut(&mut self) -> &mut [FileLockEntry<'a>] {
+        &mut self.entries[..]
+    }
+
+    /// Write all entries of the transaction to the store
+    ///
+    /// # Return value
+    ///
+    /// On success: ()
+    ///
+    /// On error:
+    ///  - TransactionCommitError(_) if the entries could not be written. None of the entries is
+    ///    written in this case and their changes are discarded.
+    ///  - TransactionCommitError(TransactionRollbackError()) if the entries could not be written
+    ///    and the files which were already replaced could not be restored.
+    ///
+    pub fn commit(mut self) -> Result<()> {
+        debug!("Committing transaction with {} entries", self.entries.len());
+        let mut entries = ::std::mem::replace(&mut self.entries, vec![]);
+        let res         = self.store.commit_transaction(&mut entries[..]);
+
+        for entry in entries {
+            entry.discard();
+        }
+
+        res.chain_err(|| SEK::TransactionCommitError)
+    }
+
+    /// Discard the changes of all entries of the transaction
+    pub fn rollback(self) {
+        debug!("Rolling back transaction with {} entries", self.entries.len());
+    }
+
+}
+
+impl<'a> Drop for Transaction<'a> {
+
+    fn drop(&mut self) {
+        for entry in self.entries.drain(..) {
+            entry.discard();
+        }
+    }
+
+}
+
 /// `EntryContent` type
 pub type EntryContent = String;
 
@@ -1391,14 +2064,14 @@ mod store_tests {
     #[test]
     fn test_store_create_with_io_backend() {
         use std::io::Cursor;
-        use std::rc::Rc;
-        use std::cell::RefCell;
+        use std::sync::Arc;
+        use std::sync::Mutex;
         use serde_json::Value;
 
         //let sink = vec![];
         //let output : Cursor<&mut [u8]> = Cursor::new(&mut sink);
-        //let output = Rc::new(RefCell::new(output));
-        let output = Rc::new(RefCell::new(vec![]));
+        //let output = Arc::new(Mutex::new(output));
+        let output = Arc::new(Mutex::new(vec![]));
 
         {
             let store = {
@@ -1413,7 +2086,7 @@ mod store_tests {
                 "#);
 
                 let mapper  = JsonMapper::new();
-                let backend = StdIoFileAbstraction::new(&mut input, output.clone(), mapper).unwrap();
+                let backend = StdIoFileAbstraction::new(&mut input, output.clone(), mapper, PathBuf::from("/")).unwrap();
                 let backend = Box::new(backend);
 
                 Store::new_with_backend(PathBuf::from("/"), None, backend).unwrap()
@@ -1429,7 +2102,7 @@ mod store_tests {
             }
         }
 
-        let vec    = Rc::try_unwrap(output).unwrap().into_inner();
+        let vec    = Arc::try_unwrap(output).unwrap().into_inner().unwrap();
 
         let errstr = format!("Not UTF8: '{:?}'", vec);
         let string = String::from_utf8(vec);
@@ -1452,7 +2125,7 @@ mod store_tests {
                 match map.get("store").unwrap() {
                     &Value::Object(ref objs) => {
                         for n in 1..100 {
-                            let s = format!("/test-{}", n);
+                            let s = format!("test-{}", n);
                             assert!(objs.get(&s).is_some(), format!("No entry: '{}'", s));
                             match objs.get(&s).unwrap() {
                                 &Value::Object(ref entry) => {
@@ -1592,6 +2265,74 @@ mod store_tests {
         }
     }
 
+    #[test]
+    fn test_get_and_delete_entries_from_io_backend() {
+        use std::io::Cursor;
+        use std::sync::Arc;
+        use std::sync::Mutex;
+        use file_abstraction::stdio::StdIoFileAbstraction;
+        use file_abstraction::stdio::mapper::json::JsonMapper;
+
+        let mut input = Cursor::new(r#"
+        { "version": "0.4.0",
+            "store": {
+                "a": { "header": { "imag": { "version": "0.4.0" } }, "content": "a" },
+                "b": { "header": { "imag": { "version": "0.4.0" } }, "content": "b" }
+            }
+        }
+        "#);
+
+        let output  = Arc::new(Mutex::new(::std::io::sink()));
+        let mapper  = JsonMapper::new();
+        let backend = StdIoFileAbstraction::new(&mut input, output, mapper, PathBuf::from("/tmp"))
+            .unwrap();
+        let store   = Store::new_with_backend(PathBuf::from("/tmp"), None, Box::new(backend))
+            .unwrap();
+
+        assert_eq!(2, store.entries().unwrap().count());
+
+        {
+            let a = store.get(PathBuf::from("a")).unwrap();
+            assert!(a.is_some());
+            assert_eq!("a", a.unwrap().get_content());
+        }
+
+        // "b" was never retrieved, so it is not in the internal cache
+        assert!(store.delete(PathBuf::from("b")).is_ok());
+        assert!(store.delete(PathBuf::from("b")).is_err());
+        assert!(store.get(PathBuf::from("b")).unwrap().is_none());
+        assert_eq!(1, store.entries().unwrap().count());
+    }
+
+    #[test]
+    fn test_io_backend_reads_absolute_pathes() {
+        use std::io::Cursor;
+        use std::sync::Arc;
+        use std::sync::Mutex;
+        use file_abstraction::stdio::StdIoFileAbstraction;
+        use file_abstraction::stdio::mapper::json::JsonMapper;
+
+        // Documents written before the pathes were relative to the store contain absolute pathes
+        let mut input = Cursor::new(r#"
+        { "version": "0.4.0",
+            "store": {
+                "/tmp/a": { "header": { "imag": { "version": "0.4.0" } }, "content": "a" }
+            }
+        }
+        "#);
+
+        let output  = Arc::new(Mutex::new(::std::io::sink()));
+        let mapper  = JsonMapper::new();
+        let backend = StdIoFileAbstraction::new(&mut input, output, mapper, PathBuf::from("/tmp"))
+            .unwrap();
+        let store   = Store::new_with_backend(PathBuf::from("/tmp"), None, Box::new(backend))
+            .unwrap();
+
+        let a = store.get(PathBuf::from("a")).unwrap();
+        assert!(a.is_some());
+        assert_eq!("a", a.unwrap().get_content());
+    }
+
     // Disabled because we cannot test this by now, as we rely on glob() in
     // Store::retieve_for_module(), which accesses the filesystem and tests run in-memory, so there
     // are no files on the filesystem in this test after Store::create().
@@ -1681,6 +2422,372 @@ mod store_tests {
         }
     }
 
+    #[test]
+    fn test_store_move_into_new_directory_keeps_content() {
+        use tempdir::TempDir;
+        use storeid::StoreId;
+
+        let dir   = TempDir::new("imag-store-move").unwrap();
+        let store = Store::new(dir.path().to_path_buf(), None).unwrap();
+
+        {
+            let mut entry = store.create(PathBuf::from("test-move")).unwrap();
+            *entry.get_content_mut() = String::from("content");
+        }
+
+        let old = StoreId::new_baseless(PathBuf::from("test-move")).unwrap();
+        let new = StoreId::new_baseless(PathBuf::from("sub/dir/test-move")).unwrap();
+        assert!(store.move_by_id(old, new).is_ok());
+
+        let entry = store.retrieve(PathBuf::from("sub/dir/test-move")).unwrap();
+        assert_eq!(entry.get_content(), "content");
+    }
+
+    #[test]
+    fn test_store_id_from_template() {
+        use idtemplate::IdTemplateValues;
+
+        let config = ::toml::de::from_str(r#"
+            [id-templates]
+            notes = "notes/{slug(name)}"
+        "#).unwrap();
+        let backend = Box::new(InMemoryFileAbstraction::new());
+        let store   = Store::new_with_backend(PathBuf::from("/"), Some(config), backend).unwrap();
+
+        let values = IdTemplateValues::new().with("name", "My Note");
+        let id_for = |store: &Store| store
+            .id_from_template("notes", &values)
+            .unwrap()
+            .unwrap()
+            .to_str()
+            .unwrap();
+
+        assert_eq!(id_for(&store), "notes/my-note");
+
+        let _ = store.create(PathBuf::from("notes/my-note")).unwrap();
+        assert_eq!(id_for(&store), "notes/my-note-1");
+
+        let _ = store.create(PathBuf::from("notes/my-note-1")).unwrap();
+        assert_eq!(id_for(&store), "notes/my-note-2");
+
+        assert!(store.id_from_template("diary", &values).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_store_invalid_id_template() {
+        let config = ::toml::de::from_str(r#"
+            [id-templates]
+            notes = "notes/{slug(name}"
+        "#).unwrap();
+        let backend = Box::new(InMemoryFileAbstraction::new());
+        assert!(Store::new_with_backend(PathBuf::from("/"), Some(config), backend).is_err());
+    }
+
+    #[test]
+    fn test_transaction_commit_writes_all_entries() {
+        let store = get_store();
+
+        {
+            let mut tx = store.transaction();
+            for n in 1..10 {
+                let mut entry = store.create(PathBuf::from(format!("test-{}", n))).unwrap();
+                *entry.get_content_mut() = format!("content {}", n);
+                tx.add(entry);
+            }
+            assert!(tx.commit().is_ok());
+        }
+
+        for n in 1..10 {
+            let id    = PathBuf::from(format!("test-{}", n));
+            let entry = store.retrieve_copy(id).unwrap();
+            assert_eq!(entry.get_content(), &format!("content {}", n));
+
+            let tmp = PathBuf::from(format!("/.test-{}.imag-tmp", n));
+            let bak = PathBuf::from(format!("/.test-{}.imag-bak", n));
+            assert!(!store.backend.exists(&tmp).unwrap());
+            assert!(!store.backend.exists(&bak).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_transaction_commit_replaces_entries() {
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("test-replace")).unwrap();
+            *entry.get_content_mut() = String::from("old");
+        }
+
+        {
+            let mut tx = store.transaction();
+            tx.add(store.retrieve(PathBuf::from("test-replace")).unwrap());
+            *tx.entries_mut()[0].get_content_mut() = String::from("new");
+            assert!(tx.commit().is_ok());
+        }
+
+        let entry = store.get(PathBuf::from("test-replace")).unwrap().unwrap();
+        assert_eq!(entry.get_content(), "new");
+    }
+
+    #[test]
+    fn test_transaction_rollback_discards_changes() {
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("test-existing")).unwrap();
+            *entry.get_content_mut() = String::from("old");
+        }
+
+        {
+            let mut existing = store.retrieve(PathBuf::from("test-existing")).unwrap();
+            *existing.get_content_mut() = String::from("new");
+
+            let mut tx = store.transaction();
+            tx.add(existing);
+            tx.add(store.create(PathBuf::from("test-new")).unwrap());
+            tx.rollback();
+        }
+
+        let entry = store.retrieve_copy(PathBuf::from("test-existing")).unwrap();
+        assert_eq!(entry.get_content(), "old");
+        assert!(store.get(PathBuf::from("test-new")).unwrap().is_none());
+
+        // The entries are not borrowed anymore
+        assert!(store.retrieve(PathBuf::from("test-existing")).is_ok());
+        assert!(store.create(PathBuf::from("test-new")).is_ok());
+    }
+
+    #[test]
+    fn test_transaction_dropped_discards_changes() {
+        let store = get_store();
+
+        {
+            let mut tx = store.transaction();
+            tx.add(store.create(PathBuf::from("test-dropped")).unwrap());
+        }
+
+        assert!(store.get(PathBuf::from("test-dropped")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_transaction_with_invalid_entry_writes_nothing() {
+        use toml::Value;
+        use toml::value::Table;
+        use error::StoreErrorKind as SEK;
+
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("test-valid")).unwrap();
+            *entry.get_content_mut() = String::from("old");
+        }
+
+        {
+            let mut valid = store.retrieve(PathBuf::from("test-valid")).unwrap();
+            *valid.get_content_mut() = String::from("new");
+
+            let mut invalid = store.create(PathBuf::from("test-invalid")).unwrap();
+            *invalid.get_header_mut() = Value::Table(Table::new());
+
+            let mut tx = store.transaction();
+            tx.add(valid).add(invalid);
+
+            let res = tx.commit();
+            assert!(res.is_err());
+            assert!(is_match!(res.unwrap_err().kind(), &SEK::TransactionCommitError));
+        }
+
+        let entry = store.retrieve_copy(PathBuf::from("test-valid")).unwrap();
+        assert_eq!(entry.get_content(), "old");
+        assert!(store.get(PathBuf::from("test-invalid")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_store_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() { }
+        assert_send_sync::<Store>();
+    }
+
+    #[test]
+    fn test_store_retrieve_from_multiple_threads() {
+        use storeid::StoreId;
+        use storeid::StoreIdIterator;
+
+        let store = get_store();
+        let ids   = (0..50)
+            .map(|n| StoreId::new_baseless(PathBuf::from(format!("test-{}", n))).unwrap())
+            .collect::<Vec<_>>();
+
+        let res = StoreIdIterator::new(Box::new(ids.clone().into_iter()))
+            .par_for_each(4, |id| {
+                store.retrieve(id.clone()).map(|mut entry| {
+                    *entry.get_content_mut() = id.to_str().unwrap();
+                })
+            });
+        assert!(res.is_ok());
+
+        for id in ids {
+            let entry = store.retrieve_copy(id.clone()).unwrap();
+            assert_eq!(entry.get_content(), &id.to_str().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_store_borrowed_entry_is_exclusive_across_threads() {
+        use crossbeam;
+
+        let store = get_store();
+        let entry = store.retrieve(PathBuf::from("test-exclusive")).unwrap();
+
+        crossbeam::scope(|scope| {
+            let other = scope.spawn(|| store.retrieve(PathBuf::from("test-exclusive")).is_err());
+            assert!(other.join());
+        });
+
+        drop(entry);
+        assert!(store.retrieve(PathBuf::from("test-exclusive")).is_ok());
+    }
+
+    #[test]
+    fn test_store_entry_locked_by_other_store() {
+        use tempdir::TempDir;
+        use error::StoreErrorKind as SEK;
+
+        let dir    = TempDir::new("imag-store-lock").unwrap();
+        let config = ::toml::de::from_str("lock-wait = false").unwrap();
+        let first  = Store::new(dir.path().to_path_buf(), Some(config)).unwrap();
+        let config = ::toml::de::from_str("lock-wait = false").unwrap();
+        let second = Store::new(dir.path().to_path_buf(), Some(config)).unwrap();
+
+        {
+            let _ = first.create(PathBuf::from("test-locked")).unwrap();
+        }
+
+        {
+            let _entry = first.retrieve(PathBuf::from("test-locked")).unwrap();
+
+            let res = second.retrieve(PathBuf::from("test-locked"));
+            assert!(res.is_err());
+            let err = res.unwrap_err();
+            assert!(err.iter().any(|e| e.to_string() == SEK::EntryLockedByOtherProcess.to_string()));
+        }
+
+        assert!(second.retrieve(PathBuf::from("test-locked")).is_ok());
+    }
+
+    /// Replace the file of `entry` in `dir` with a version with `content`, as a text editor would
+    fn replace_entry_file(dir: &::std::path::Path, entry: &super::Entry, content: &str) {
+        use std::fs::{File, rename};
+        use std::io::Write;
+
+        let mut changed = entry.clone();
+        *changed.get_content_mut() = String::from(content);
+
+        let path = dir.join("test-conflict");
+        let tmp  = dir.join("test-conflict.tmp");
+        File::create(&tmp).unwrap().write_all(changed.to_str().as_bytes()).unwrap();
+        rename(&tmp, &path).unwrap();
+    }
+
+    #[test]
+    fn test_store_update_detects_modification_in_backend() {
+        use tempdir::TempDir;
+        use error::StoreErrorKind as SEK;
+
+        let dir   = TempDir::new("imag-store-conflict").unwrap();
+        let store = Store::new(dir.path().to_path_buf(), None).unwrap();
+
+        {
+            let _ = store.create(PathBuf::from("test-conflict")).unwrap();
+        }
+
+        let mut entry = store.retrieve(PathBuf::from("test-conflict")).unwrap();
+        replace_entry_file(dir.path(), &entry, "theirs\n");
+        *entry.get_content_mut() = String::from("ours\n");
+
+        let res = store.update(&mut entry);
+        assert!(res.is_err());
+        let err = res.unwrap_err();
+        assert!(err.iter().any(|e| e.to_string() == SEK::EntryModifiedInBackend.to_string()));
+
+        let conflict = store.get_conflict(&entry).unwrap().unwrap();
+        assert_eq!(conflict.base().unwrap().get_content(), "");
+        assert_eq!(conflict.ours().get_content(), "ours\n");
+        assert_eq!(conflict.theirs().unwrap().get_content(), "theirs\n");
+
+        store.resolve_conflict(&entry).unwrap();
+        assert!(store.get_conflict(&entry).unwrap().is_none());
+        assert!(store.update(&mut entry).is_ok());
+        drop(entry);
+
+        let copy = store.retrieve_copy(PathBuf::from("test-conflict")).unwrap();
+        assert_eq!(copy.get_content(), "ours\n");
+    }
+
+    #[test]
+    fn test_store_releases_entry_modified_in_backend_on_drop() {
+        use tempdir::TempDir;
+
+        let dir   = TempDir::new("imag-store-conflict").unwrap();
+        let store = Store::new(dir.path().to_path_buf(), None).unwrap();
+
+        {
+            let _ = store.create(PathBuf::from("test-conflict")).unwrap();
+        }
+
+        {
+            let mut entry = store.retrieve(PathBuf::from("test-conflict")).unwrap();
+            replace_entry_file(dir.path(), &entry, "theirs\n");
+            *entry.get_content_mut() = String::from("ours\n");
+        }
+
+        // The entry was not written, but it is not borrowed (or locked) anymore either
+        let entry = store.retrieve(PathBuf::from("test-conflict")).unwrap();
+        assert_eq!(entry.get_content(), "theirs\n");
+    }
+
+    #[test]
+    fn test_store_update_without_modification_in_backend() {
+        use tempdir::TempDir;
+
+        let dir   = TempDir::new("imag-store-conflict").unwrap();
+        let store = Store::new(dir.path().to_path_buf(), None).unwrap();
+
+        {
+            let _ = store.create(PathBuf::from("test-conflict")).unwrap();
+        }
+
+        let mut entry = store.retrieve(PathBuf::from("test-conflict")).unwrap();
+        *entry.get_content_mut() = String::from("first\n");
+        assert!(store.update(&mut entry).is_ok());
+        assert!(store.get_conflict(&entry).unwrap().is_none());
+
+        *entry.get_content_mut() = String::from("second\n");
+        assert!(store.update(&mut entry).is_ok());
+    }
+
+    #[test]
+    fn test_transaction_detects_modification_in_backend() {
+        use tempdir::TempDir;
+
+        let dir   = TempDir::new("imag-store-conflict").unwrap();
+        let store = Store::new(dir.path().to_path_buf(), None).unwrap();
+
+        {
+            let _ = store.create(PathBuf::from("test-conflict")).unwrap();
+        }
+
+        let entry = store.retrieve(PathBuf::from("test-conflict")).unwrap();
+        replace_entry_file(dir.path(), &entry, "theirs\n");
+
+        let mut transaction = store.transaction();
+        transaction.add(entry);
+        assert!(transaction.commit().is_err());
+
+        let copy = store.retrieve_copy(PathBuf::from("test-conflict")).unwrap();
+        assert_eq!(copy.get_content(), "theirs\n");
+    }
+
     #[test]
     fn test_swap_backend_during_runtime() {
         use file_abstraction::InMemoryFileAbstraction;
@@ -1729,14 +2836,14 @@ mod store_tests {
     #[test]
     fn test_swap_backend_during_runtime_with_io() {
         use std::io::Cursor;
-        use std::rc::Rc;
-        use std::cell::RefCell;
+        use std::sync::Arc;
+        use std::sync::Mutex;
         use serde_json::Value;
         use file_abstraction::stdio::out::StdoutFileAbstraction;
         use file_abstraction::stdio::mapper::json::JsonMapper;
 
         // The output we later read from and check whether there is an entry
-        let output  = Rc::new(RefCell::new(vec![]));
+        let output  = Arc::new(Mutex::new(vec![]));
 
         {
             let mut store = {
@@ -1759,9 +2866,9 @@ mod store_tests {
                 }
                 "#);
 
-                let output  = Rc::new(RefCell::new(::std::io::sink()));
+                let output  = Arc::new(Mutex::new(::std::io::sink()));
                 let mapper  = JsonMapper::new();
-                let backend = StdIoFileAbstraction::new(&mut input, output, mapper).unwrap();
+                let backend = StdIoFileAbstraction::new(&mut input, output, mapper, PathBuf::from("/")).unwrap();
                 let backend = Box::new(backend);
 
                 Store::new_with_backend(PathBuf::from("/"), None, backend).unwrap()
@@ -1771,7 +2878,7 @@ mod store_tests {
 
             {
                 let mapper    = JsonMapper::new();
-                let backend   = StdoutFileAbstraction::new(output.clone(), mapper);
+                let backend   = StdoutFileAbstraction::new(output.clone(), mapper, PathBuf::from("/"));
                 let _         = assert!(backend.is_ok(), format!("Should be ok: {:?}", backend));
                 let backend   = backend.unwrap();
                 let backend   = Box::new(backend);
@@ -1780,7 +2887,7 @@ mod store_tests {
             }
         }
 
-        let vec    = Rc::try_unwrap(output).unwrap().into_inner();
+        let vec    = Arc::try_unwrap(output).unwrap().into_inner().unwrap();
         let errstr = format!("Not UTF8: '{:?}'", vec);
         let string = String::from_utf8(vec);
         assert!(string.is_ok(), errstr);
@@ -1827,3 +2934,225 @@ mod store_tests {
     }
 }
 
+
+#[cfg(test)]
+mod store_hook_tests {
+    use std::path::PathBuf;
+
+    use toml::Value;
+
+    use super::Store;
+    use super::FileLockEntry;
+    use file_abstraction::InMemoryFileAbstraction;
+    use hook::Hook;
+    use hook::accessor::HookDataAccessor as HDA;
+    use hook::accessor::HookDataAccessorProvider;
+    use hook::accessor::StoreIdAccessor;
+    use hook::accessor::MutableHookDataAccessor;
+    use hook::error::HookError as HE;
+    use hook::error::HookErrorKind as HEK;
+    use hook::error::Result as HookResult;
+    use hook::position::HookPosition;
+    use storeid::StoreId;
+
+    #[derive(Debug)]
+    struct VetoHook;
+
+    impl Hook for VetoHook {
+        fn name(&self) -> &'static str { "veto" }
+        fn set_config(&mut self, _: &Value) { }
+    }
+
+    impl HookDataAccessorProvider for VetoHook {
+        fn accessor(&self) -> HDA {
+            HDA::StoreIdAccess(self)
+        }
+    }
+
+    impl StoreIdAccessor for VetoHook {
+        fn access(&self, _: &StoreId) -> HookResult<()> {
+            Err(HE::from_kind(HEK::HookExecutionError))
+        }
+    }
+
+    #[derive(Debug)]
+    struct HeaderSettingHook;
+
+    impl Hook for HeaderSettingHook {
+        fn name(&self) -> &'static str { "header-setting" }
+        fn set_config(&mut self, _: &Value) { }
+    }
+
+    impl HookDataAccessorProvider for HeaderSettingHook {
+        fn accessor(&self) -> HDA {
+            HDA::MutableAccess(self)
+        }
+    }
+
+    impl MutableHookDataAccessor for HeaderSettingHook {
+        fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+            match *fle.get_header_mut() {
+                Value::Table(ref mut t) => {
+                    let _ = t.insert(String::from("hooktest"), Value::Table(::toml::value::Table::new()));
+                    Ok(())
+                },
+                _ => Err(HE::from_kind(HEK::HookExecutionError)),
+            }
+        }
+    }
+
+    /// Alters the entry like `HeaderSettingHook`, but fails afterwards
+    #[derive(Debug)]
+    struct FailingHook;
+
+    impl Hook for FailingHook {
+        fn name(&self) -> &'static str { "failing" }
+        fn set_config(&mut self, _: &Value) { }
+    }
+
+    impl HookDataAccessorProvider for FailingHook {
+        fn accessor(&self) -> HDA {
+            HDA::MutableAccess(self)
+        }
+    }
+
+    impl MutableHookDataAccessor for FailingHook {
+        fn access_mut(&self, fle: &mut FileLockEntry) -> HookResult<()> {
+            HeaderSettingHook.access_mut(fle)
+                .and_then(|_| Err(HE::from_kind(HEK::HookExecutionError)))
+        }
+    }
+
+    fn has_hooktest_section(store: &Store, id: &str) -> bool {
+        match *store.retrieve_copy(PathBuf::from(id)).unwrap().get_header() {
+            Value::Table(ref t) => t.contains_key("hooktest"),
+            _ => panic!("Header is not a table"),
+        }
+    }
+
+    fn get_store() -> Store {
+        let config = ::toml::de::from_str(r#"
+            pre-create-hook-aspects    = [ "test" ]
+            post-create-hook-aspects   = [ "test" ]
+            post-retrieve-hook-aspects = [ "test" ]
+            pre-delete-hook-aspects    = [ "test" ]
+
+            [aspects.test]
+        "#).unwrap();
+
+        let backend = Box::new(InMemoryFileAbstraction::new());
+        Store::new_with_backend(PathBuf::from("/"), Some(config), backend).unwrap()
+    }
+
+    #[test]
+    fn test_register_hook_for_unconfigured_aspect_fails() {
+        let store = get_store();
+
+        assert!(store.register_hook(HookPosition::PreCreate, "nonexistent", Box::new(VetoHook)).is_err());
+        assert!(store.register_hook(HookPosition::PreUpdate, "test", Box::new(VetoHook)).is_err());
+        assert!(store.register_hook(HookPosition::PreCreate, "test", Box::new(VetoHook)).is_ok());
+    }
+
+    #[test]
+    fn test_pre_create_hook_vetoes_create() {
+        let store = get_store();
+        assert!(store.register_hook(HookPosition::PreCreate, "test", Box::new(VetoHook)).is_ok());
+
+        assert!(store.create(PathBuf::from("test-veto")).is_err());
+        assert!(store.entries.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_post_create_hook_alters_entry() {
+        let store = get_store();
+        let hook  = Box::new(HeaderSettingHook);
+        assert!(store.register_hook(HookPosition::PostCreate, "test", hook).is_ok());
+
+        let entry = store.create(PathBuf::from("test-alter")).unwrap();
+        match *entry.get_header() {
+            Value::Table(ref t) => assert!(t.contains_key("hooktest")),
+            _ => panic!("Header is not a table"),
+        }
+    }
+
+    #[test]
+    fn test_failing_post_create_hook_does_not_write_entry() {
+        let store = get_store();
+        let hook = Box::new(FailingHook);
+        assert!(store.register_hook(HookPosition::PostCreate, "test", hook).is_ok());
+
+        assert!(store.create(PathBuf::from("test-post-create-fail")).is_err());
+        assert!(store.entries.read().unwrap().is_empty());
+        assert!(store.get(PathBuf::from("test-post-create-fail")).unwrap().is_none());
+
+        // The id was released, so it can be created once the hook is gone
+        assert!(store.unregister_hooks().is_ok());
+        assert!(store.create(PathBuf::from("test-post-create-fail")).is_ok());
+    }
+
+    #[test]
+    fn test_failing_post_retrieve_hook_does_not_write_entry() {
+        let store = get_store();
+
+        {
+            assert!(store.create(PathBuf::from("test-post-retrieve-fail")).is_ok());
+        }
+
+        let hook = Box::new(FailingHook);
+        assert!(store.register_hook(HookPosition::PostRetrieve, "test", hook).is_ok());
+        assert!(store.retrieve(PathBuf::from("test-post-retrieve-fail")).is_err());
+
+        // The entry was released without writing the changes of the hook
+        assert!(!has_hooktest_section(&store, "test-post-retrieve-fail"));
+    }
+
+    #[test]
+    fn test_pre_delete_hook_vetoes_delete() {
+        let store = get_store();
+
+        {
+            assert!(store.create(PathBuf::from("test-delete-veto")).is_ok());
+        }
+
+        assert!(store.register_hook(HookPosition::PreDelete, "test", Box::new(VetoHook)).is_ok());
+        assert!(store.delete(PathBuf::from("test-delete-veto")).is_err());
+        assert!(match store.get(PathBuf::from("test-delete-veto")) { Ok(Some(_)) => true, _ => false });
+    }
+
+    #[test]
+    fn test_pre_update_hook_veto_releases_entry() {
+        let config = ::toml::de::from_str(r#"
+            pre-update-hook-aspects = [ "test" ]
+
+            [aspects.test]
+        "#).unwrap();
+
+        let backend = Box::new(InMemoryFileAbstraction::new());
+        let store   = Store::new_with_backend(PathBuf::from("/"), Some(config), backend).unwrap();
+
+        {
+            assert!(store.create(PathBuf::from("test-update-veto")).is_ok());
+        }
+        assert!(store.register_hook(HookPosition::PreUpdate, "test", Box::new(VetoHook)).is_ok());
+
+        {
+            let mut entry = store.retrieve(PathBuf::from("test-update-veto")).unwrap();
+            assert!(store.update(&mut entry).is_err());
+        }
+
+        // The vetoed entry is released when it is dropped, so it can be borrowed again
+        assert!(store.retrieve_copy(PathBuf::from("test-update-veto")).is_ok());
+        assert!(match store.get(PathBuf::from("test-update-veto")) { Ok(Some(_)) => true, _ => false });
+    }
+
+    #[test]
+    fn test_unregister_hooks() {
+        let store = get_store();
+        assert!(store.register_hook(HookPosition::PreCreate, "test", Box::new(VetoHook)).is_ok());
+        assert!(store.unregister_hooks().is_ok());
+
+        assert!(store.create(PathBuf::from("test-unregistered")).is_ok());
+        assert!(store.register_hook(HookPosition::PreCreate, "test", Box::new(VetoHook)).is_ok());
+    }
+
+}
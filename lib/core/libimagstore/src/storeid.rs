@@ -146,6 +146,27 @@ impl StoreId {
             .all(|x| x)
     }
 
+    /// Check whether the StoreId is hidden, that is whether one of its components starts with a
+    /// dot (like the `.git` directory in the store).
+    pub fn is_hidden(&self) -> bool {
+        use std::path::Component;
+
+        self.id
+            .components()
+            .any(|c| match c {
+                Component::Normal(s) => s.to_str().map(|s| s.starts_with(".")).unwrap_or(false),
+                _ => false,
+            })
+    }
+
+    /// Check whether a StoreId found in the store directory is an entry, and not a directory or a
+    /// file of another tool (like the `.git` directory).
+    ///
+    /// The StoreId has to have a base, as the file is checked in the filesystem.
+    pub fn is_entry_file(&self) -> bool {
+        !self.is_hidden() && self.clone().into_pathbuf().map(|p| p.is_file()).unwrap_or(false)
+    }
+
     pub fn local_push<P: AsRef<Path>>(&mut self, path: P) {
         self.id.push(path)
     }
@@ -248,6 +269,49 @@ impl StoreIdIterator {
         }
     }
 
+    /// Apply `f` to all ids of the iterator in parallel, using (at most) `threads` threads
+    ///
+    /// The ids are collected and split into one chunk per thread. The results are returned in
+    /// the order of the ids.
+    ///
+    /// As the `Store` is `Sync`, `f` can borrow it, for example to retrieve the entries.
+    pub fn par_map<F, R>(self, threads: usize, f: F) -> Vec<R>
+        where F: Fn(StoreId) -> R + Sync,
+              R: Send
+    {
+        use crossbeam;
+
+        let ids = self.collect::<Vec<StoreId>>();
+        if ids.is_empty() {
+            return vec![];
+        }
+
+        let threads    = ::std::cmp::max(threads, 1);
+        let chunk_size = (ids.len() + threads - 1) / threads;
+        let f          = &f;
+
+        debug!("Processing {} ids in chunks of {}", ids.len(), chunk_size);
+        crossbeam::scope(|scope| {
+            let handles = ids
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || chunk.iter().cloned().map(f).collect::<Vec<R>>()))
+                .collect::<Vec<_>>();
+
+            handles.into_iter().flat_map(|handle| handle.join()).collect()
+        })
+    }
+
+    /// Apply `f` to all ids of the iterator in parallel, using (at most) `threads` threads
+    ///
+    /// All ids are processed, even if `f` fails for some of them. The first error (in the order of
+    /// the ids) is returned.
+    pub fn par_for_each<F, E>(self, threads: usize, f: F) -> RResult<(), E>
+        where F: Fn(StoreId) -> RResult<(), E> + Sync,
+              E: Send
+    {
+        self.par_map(threads, f).into_iter().collect()
+    }
+
 }
 
 impl Iterator for StoreIdIterator {
@@ -264,6 +328,7 @@ mod test {
     use std::path::PathBuf;
 
     use storeid::StoreId;
+    use storeid::StoreIdIterator;
     use storeid::IntoStoreId;
     use error::StoreErrorKind as SEK;
 
@@ -387,4 +452,32 @@ mod test {
         assert!(!p.is_in_collection(&["test", "1", "2", "3", "leet", "5", "6", "7"]));
     }
 
+    fn ids(n: usize) -> StoreIdIterator {
+        let ids = (0..n)
+            .map(|i| StoreId::new_baseless(PathBuf::from(format!("test-{}", i))).unwrap())
+            .collect::<Vec<_>>();
+        StoreIdIterator::new(Box::new(ids.into_iter()))
+    }
+
+    #[test]
+    fn storeid_iterator_par_map_keeps_order() {
+        for threads in 0..5 {
+            let names = ids(10).par_map(threads, |id| id.to_str().unwrap());
+            let expected = (0..10).map(|i| format!("test-{}", i)).collect::<Vec<_>>();
+            assert_eq!(names, expected);
+        }
+
+        assert!(ids(0).par_map(4, |id| id).is_empty());
+    }
+
+    #[test]
+    fn storeid_iterator_par_for_each_returns_first_error() {
+        let res = ids(10).par_for_each(3, |id| {
+            let name = id.to_str().unwrap();
+            if name == "test-4" || name == "test-7" { Err(name) } else { Ok(()) }
+        });
+
+        assert_eq!(res, Err(String::from("test-4")));
+    }
+
 }
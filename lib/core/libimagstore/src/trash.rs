@@ -0,0 +1,375 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The trash of the store
+//!
+//! Instead of deleting entries right away, modules can move them into the trash, from where they
+//! can be restored later. Trashed entries live in the `trash` collection of the store, at
+//! `trash/<deletion time>/<original id>`, and carry a `trash` section in their header:
+//!
+//! ```toml
+//! [trash]
+//! original = "diary/default/2017/06/01/12:00"
+//! deleted = "2017-06-02T10:00:00"
+//! module = "diary"
+//! ```
+//!
+//! Entries are only deleted for good when the trash is purged.
+
+use std::path::PathBuf;
+
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::Duration;
+use toml::Value;
+use toml::value::Table;
+
+use error::StoreError as SE;
+use error::StoreErrorKind as SEK;
+use error::ResultExt;
+use store::Entry;
+use store::Result;
+use store::Store;
+use storeid::IntoStoreId;
+use storeid::StoreId;
+
+/// The collection the trashed entries live in
+pub const TRASH_COLLECTION : &'static str = "trash";
+
+const DATETIME_FORMAT : &'static str = "%Y-%m-%dT%H:%M:%S";
+const DIRNAME_FORMAT  : &'static str = "%Y-%m-%d-%H-%M-%S";
+
+/// An entry in the trash
+#[derive(Debug, Clone)]
+pub struct TrashedEntry {
+    id: StoreId,
+    original: StoreId,
+    deleted: Option<NaiveDateTime>,
+    module: Option<String>,
+}
+
+impl TrashedEntry {
+
+    fn from_entry(entry: &Entry) -> Result<TrashedEntry> {
+        let id      = entry.get_location().clone().without_base();
+        let section = match *entry.get_header() {
+            Value::Table(ref t) => t.get("trash"),
+            _ => None,
+        };
+        let get = |key: &str| match section {
+            Some(&Value::Table(ref t)) => match t.get(key) {
+                Some(&Value::String(ref s)) => Some(s.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        // Fall back to the location of the entry, if the header section is missing
+        let original = match get("original") {
+            Some(original) => try!(StoreId::new_baseless(PathBuf::from(original))),
+            None => try!(StoreId::new_baseless(id.local().components().skip(2).collect())),
+        };
+
+        Ok(TrashedEntry {
+            id: id,
+            original: original,
+            deleted: get("deleted").and_then(|s| NaiveDateTime::parse_from_str(&s, DATETIME_FORMAT).ok()),
+            module: get("module"),
+        })
+    }
+
+    /// The id of the entry in the trash
+    pub fn id(&self) -> &StoreId {
+        &self.id
+    }
+
+    /// The id the entry had before it was moved to the trash
+    pub fn original(&self) -> &StoreId {
+        &self.original
+    }
+
+    /// When the entry was moved to the trash
+    pub fn deleted(&self) -> Option<&NaiveDateTime> {
+        self.deleted.as_ref()
+    }
+
+    /// The module which moved the entry to the trash
+    pub fn module(&self) -> Option<&String> {
+        self.module.as_ref()
+    }
+
+}
+
+/// The trash of a store
+pub struct Trash<'a> {
+    store: &'a Store,
+}
+
+impl<'a> Trash<'a> {
+
+    pub fn new(store: &'a Store) -> Trash<'a> {
+        Trash { store: store }
+    }
+
+    /// Move an entry into the trash
+    ///
+    /// `module` is the name of the module which deletes the entry.
+    ///
+    /// # Return value
+    ///
+    /// On success: The id of the entry in the trash
+    ///
+    /// On error:
+    ///  - EntryInTrash() if the entry is in the trash already
+    ///  - Errors Store::move_by_id() might return
+    ///
+    pub fn trash<S: IntoStoreId>(&self, id: S, module: &str) -> Result<StoreId> {
+        let id = try!(id.into_storeid()).without_base();
+        if id.is_in_collection(&[TRASH_COLLECTION]) {
+            return Err(SE::from_kind(SEK::EntryInTrash));
+        }
+
+        let now      = Local::now().naive_local();
+        let trash_id = try!(self.free_trash_id(&id, &now));
+        debug!("Moving '{}' to the trash as '{}'", id, trash_id);
+
+        try!(self.store.move_by_id(id.clone(), trash_id.clone()));
+
+        let mut entry = try!(self.store.retrieve(trash_id.clone()));
+        {
+            let mut section = Table::new();
+            section.insert(String::from("original"), Value::String(try!(id.to_str())));
+            section.insert(String::from("deleted"), Value::String(now.format(DATETIME_FORMAT).to_string()));
+            section.insert(String::from("module"), Value::String(String::from(module)));
+
+            match *entry.get_header_mut() {
+                Value::Table(ref mut t) => { t.insert(String::from("trash"), Value::Table(section)); },
+                _ => return Err(SE::from_kind(SEK::HeaderTypeFailure)),
+            }
+        }
+        try!(self.store.update(&mut entry));
+
+        Ok(trash_id)
+    }
+
+    /// Find an id in the trash for `id`, which is not taken yet
+    fn free_trash_id(&self, id: &StoreId, now: &NaiveDateTime) -> Result<StoreId> {
+        let stamp = now.format(DIRNAME_FORMAT).to_string();
+
+        for n in 0.. {
+            let dir = if n == 0 { stamp.clone() } else { format!("{}-{}", stamp, n) };
+            let trash_id = try!(StoreId::new_baseless(PathBuf::from(TRASH_COLLECTION)
+                                                      .join(dir)
+                                                      .join(id.local())));
+
+            if !try!(trash_id.clone().with_base(self.store.path().clone()).exists()) {
+                return Ok(trash_id);
+            }
+        }
+
+        unreachable!()
+    }
+
+    /// Get all entries in the trash, ordered by the time they were moved to the trash
+    pub fn entries(&self) -> Result<Vec<TrashedEntry>> {
+        let mut entries = try!(try!(self.store.retrieve_for_module(TRASH_COLLECTION))
+            .map(|id| self.store
+                 .retrieve_copy(id)
+                 .and_then(|entry| TrashedEntry::from_entry(&entry)))
+            .collect::<Result<Vec<TrashedEntry>>>());
+
+        entries.sort_by(|a, b| a.deleted.cmp(&b.deleted).then_with(|| a.id.cmp(&b.id)));
+        Ok(entries)
+    }
+
+    /// Move an entry from the trash back to its original location
+    ///
+    /// # Return value
+    ///
+    /// On success: The id the entry was restored to
+    ///
+    /// On error:
+    ///  - EntryNotInTrash() if `trash_id` does not point into the trash
+    ///  - EntryAlreadyExists() if there is an entry at the original location
+    ///  - Errors Store::move_by_id() might return
+    ///
+    pub fn restore(&self, trash_id: StoreId) -> Result<StoreId> {
+        let trash_id = trash_id.without_base();
+        if !trash_id.is_in_collection(&[TRASH_COLLECTION]) {
+            return Err(SE::from_kind(SEK::EntryNotInTrash));
+        }
+
+        let entry    = try!(self.store.retrieve_copy(trash_id.clone()));
+        let original = try!(TrashedEntry::from_entry(&entry)).original;
+
+        if try!(original.clone().with_base(self.store.path().clone()).exists()) {
+            return Err(SE::from_kind(SEK::EntryAlreadyExists));
+        }
+
+        debug!("Restoring '{}' to '{}'", trash_id, original);
+        try!(self.store.move_by_id(trash_id, original.clone()));
+
+        let mut entry = try!(self.store.retrieve(original.clone()));
+        if let Value::Table(ref mut t) = *entry.get_header_mut() {
+            t.remove("trash");
+        }
+        try!(self.store.update(&mut entry));
+
+        Ok(original)
+    }
+
+    /// Delete an entry in the trash for good
+    pub fn purge(&self, trash_id: StoreId) -> Result<()> {
+        let trash_id = trash_id.without_base();
+        if !trash_id.is_in_collection(&[TRASH_COLLECTION]) {
+            return Err(SE::from_kind(SEK::EntryNotInTrash));
+        }
+
+        debug!("Purging '{}'", trash_id);
+        self.store.delete(trash_id)
+    }
+
+    /// Delete all entries which were moved to the trash longer than `age` ago
+    ///
+    /// Entries without deletion time are purged as well.
+    ///
+    /// # Return value
+    ///
+    /// On success: The ids of the purged entries
+    ///
+    pub fn purge_older_than(&self, age: Duration) -> Result<Vec<StoreId>> {
+        let limit = Local::now().naive_local() - age;
+
+        try!(self.entries())
+            .into_iter()
+            .filter(|e| e.deleted.map(|d| d < limit).unwrap_or(true))
+            .map(|e| self.purge(e.id.clone()).map(|_| e.id))
+            .collect::<Result<Vec<StoreId>>>()
+            .chain_err(|| SEK::DeleteCallError)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use chrono::Duration;
+    use tempdir::TempDir;
+
+    use super::Trash;
+    use store::Store;
+    use storeid::StoreId;
+
+    fn get_store(dir: &TempDir) -> Store {
+        Store::new(dir.path().to_path_buf(), None).unwrap()
+    }
+
+    fn create(store: &Store, id: &str, content: &str) {
+        let mut entry = store.create(PathBuf::from(id)).unwrap();
+        *entry.get_content_mut() = String::from(content);
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let dir   = TempDir::new("imag-store-trash").unwrap();
+        let store = get_store(&dir);
+        create(&store, "notes/a", "content");
+
+        let trash_id = Trash::new(&store).trash(PathBuf::from("notes/a"), "notes").unwrap();
+        assert!(trash_id.is_in_collection(&["trash"]));
+        assert!(store.get(PathBuf::from("notes/a")).unwrap().is_none());
+
+        let entries = Trash::new(&store).entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id(), &trash_id);
+        assert_eq!(entries[0].original(), &StoreId::new_baseless(PathBuf::from("notes/a")).unwrap());
+        assert_eq!(entries[0].module(), Some(&String::from("notes")));
+        assert!(entries[0].deleted().is_some());
+
+        let restored = Trash::new(&store).restore(trash_id).unwrap();
+        assert_eq!(restored, StoreId::new_baseless(PathBuf::from("notes/a")).unwrap());
+
+        let entry = store.retrieve_copy(PathBuf::from("notes/a")).unwrap();
+        assert_eq!(entry.get_content(), "content");
+        assert!(entry.get_header().get("trash").is_none());
+        assert!(Trash::new(&store).entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trash_same_id_twice() {
+        let dir   = TempDir::new("imag-store-trash").unwrap();
+        let store = get_store(&dir);
+        let trash = Trash::new(&store);
+
+        create(&store, "notes/a", "first");
+        let first = trash.trash(PathBuf::from("notes/a"), "notes").unwrap();
+        create(&store, "notes/a", "second");
+        let second = trash.trash(PathBuf::from("notes/a"), "notes").unwrap();
+
+        assert!(first != second);
+        assert_eq!(trash.entries().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_restore_does_not_overwrite() {
+        let dir   = TempDir::new("imag-store-trash").unwrap();
+        let store = get_store(&dir);
+        let trash = Trash::new(&store);
+
+        create(&store, "notes/a", "first");
+        let trash_id = trash.trash(PathBuf::from("notes/a"), "notes").unwrap();
+        create(&store, "notes/a", "second");
+
+        assert!(trash.restore(trash_id).is_err());
+        assert_eq!(store.retrieve_copy(PathBuf::from("notes/a")).unwrap().get_content(), "second");
+    }
+
+    #[test]
+    fn test_purge() {
+        let dir   = TempDir::new("imag-store-trash").unwrap();
+        let store = get_store(&dir);
+        let trash = Trash::new(&store);
+
+        create(&store, "notes/a", "a");
+        create(&store, "notes/b", "b");
+        let _ = trash.trash(PathBuf::from("notes/a"), "notes").unwrap();
+        let b = trash.trash(PathBuf::from("notes/b"), "notes").unwrap();
+
+        assert!(trash.purge_older_than(Duration::days(1)).unwrap().is_empty());
+        assert_eq!(trash.entries().unwrap().len(), 2);
+
+        trash.purge(b).unwrap();
+        assert_eq!(trash.entries().unwrap().len(), 1);
+
+        assert_eq!(trash.purge_older_than(Duration::seconds(-1)).unwrap().len(), 1);
+        assert!(trash.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_purge_outside_trash_fails() {
+        let dir   = TempDir::new("imag-store-trash").unwrap();
+        let store = get_store(&dir);
+        create(&store, "notes/a", "a");
+
+        assert!(Trash::new(&store).purge(StoreId::new_baseless(PathBuf::from("notes/a")).unwrap()).is_err());
+        assert!(store.get(PathBuf::from("notes/a")).unwrap().is_some());
+    }
+
+}
@@ -17,6 +17,8 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::path::PathBuf;
+
 use regex::Regex;
 use toml::Value;
 
@@ -43,6 +45,12 @@ macro_rules! if_cfg_panic {
 
 pub fn entry_buffer_to_header_content(buf: &str) -> Result<(Value, String)> {
     debug!("Building entry from string");
+    let (header, content) = try!(split_entry_buffer(buf));
+    Ok((try!(Value::parse(header)), String::from(content)))
+}
+
+/// Split the buffer of an entry into the (unparsed) header and the content
+pub fn split_entry_buffer(buf: &str) -> Result<(&str, &str)> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"(?smx)
             ^---$
@@ -64,6 +72,16 @@ pub fn entry_buffer_to_header_content(buf: &str) -> Result<(Value, String)> {
 
     let content = matches.name("content").map(|r| r.as_str()).unwrap_or("");
 
-    Ok((try!(Value::parse(header.as_str())), String::from(content)))
+    Ok((header.as_str(), content))
 }
 
+/// Get the path of a file used while committing a transaction, for example
+/// `/store/mod/.entry.imag-tmp` for `/store/mod/entry`.
+pub fn transaction_file_path(path: &PathBuf, ext: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(".{}.imag-{}", name, ext))
+}
@@ -0,0 +1,631 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Verification of the files of the store
+//!
+//! `Store::verify()` reads all files of the store and returns a `VerifyReport`, which contains a
+//! `Finding` for each problem it found:
+//!
+//! * Files which are not separated into a header and a content by `---` lines
+//! * Headers which are not valid TOML
+//! * Headers without `[imag]` section, without `imag.version` or with a version which is not a
+//!   valid semantic version
+//! * Headers with values in the base table which are not tables
+//! * Files which are not in the collection of a known module
+//!
+//! Libraries can add their own findings to the report, for example `libimagentryref` reports
+//! references to the same file. These findings are only reported, the entries are neither fixed
+//! nor put into quarantine.
+//!
+//! Depending on the `VerifyOptions`, missing or invalid version information in the header is
+//! fixed and files which cannot be parsed at all are moved to the quarantine directory
+//! (`.quarantine` in the store), where they are ignored by imag. Other problems, like orphaned
+//! files, are only reported. Hidden files and directories in the store are not verified.
+//!
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fmt::Error as FmtError;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use serde_json;
+use toml::Value;
+use walkdir::{DirEntry, WalkDir, WalkDirIterator};
+
+use error::StoreErrorKind as SEK;
+use error::StoreError as SE;
+use error::ResultExt;
+use file_abstraction::FileAbstraction;
+use file_abstraction::LockWait;
+use store::Entry;
+use store::Result;
+use storeid::StoreId;
+use trash::TRASH_COLLECTION;
+use util::split_entry_buffer;
+use util::transaction_file_path;
+
+/// The directory in the store where files are moved to by the quarantine
+pub const QUARANTINE_DIR : &'static str = ".quarantine";
+
+/// A problem with a file in the store
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Problem {
+    /// The file cannot be read
+    Unreadable { error: String },
+
+    /// The file is not separated into header and content
+    MalformedEntry,
+
+    /// The header is not valid TOML
+    InvalidHeader { error: String },
+
+    /// The header has no `[imag]` section
+    MissingMainSection,
+
+    /// The `[imag]` section has no `version`
+    MissingVersion,
+
+    /// `imag.version` is not a valid semantic version
+    InvalidVersion { version: String },
+
+    /// The base table of the header contains values which are not tables
+    NonTableInBaseTable { keys: Vec<String> },
+
+    /// The file is not in the collection of a known module
+    OrphanedFile,
+
+    /// The entry is a reference to the same file as other entries
+    DuplicateRef { path: String, others: Vec<String> },
+}
+
+impl Problem {
+
+    /// Whether `Store::verify()` can fix the problem
+    pub fn is_fixable(&self) -> bool {
+        match *self {
+            Problem::MissingMainSection  |
+            Problem::MissingVersion      |
+            Problem::InvalidVersion {..} => true,
+            _                            => false,
+        }
+    }
+
+    /// Whether the file is moved to the quarantine by `Store::verify()`
+    ///
+    /// Only files which cannot be parsed as entries are put into quarantine.
+    pub fn needs_quarantine(&self) -> bool {
+        match *self {
+            Problem::Unreadable {..}    |
+            Problem::MalformedEntry     |
+            Problem::InvalidHeader {..} => true,
+            _                           => false,
+        }
+    }
+
+}
+
+impl Display for Problem {
+
+    fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        match *self {
+            Problem::Unreadable { ref error } => write!(fmt, "File cannot be read: {}", error),
+            Problem::MalformedEntry => write!(fmt, "Entry is not separated into header and content"),
+            Problem::InvalidHeader { ref error } => write!(fmt, "Header is not valid TOML: {}", error),
+            Problem::MissingMainSection => write!(fmt, "Header has no [imag] section"),
+            Problem::MissingVersion => write!(fmt, "Header has no imag.version"),
+            Problem::InvalidVersion { ref version } => {
+                write!(fmt, "imag.version is not a valid version: '{}'", version)
+            },
+            Problem::NonTableInBaseTable { ref keys } => {
+                write!(fmt, "Header has values which are not tables in its base: {}", keys.join(", "))
+            },
+            Problem::OrphanedFile => write!(fmt, "File is not in the collection of a known module"),
+            Problem::DuplicateRef { ref path, ref others } => {
+                write!(fmt, "Reference to '{}', as are: {}", path, others.join(", "))
+            },
+        }
+    }
+
+}
+
+/// What was done about a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// The header of the entry was fixed
+    Fixed,
+
+    /// The file was moved to the quarantine directory
+    Quarantined,
+}
+
+/// A problem with a file in the store
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// The path of the file, relative to the store
+    pub path: PathBuf,
+    pub problem: Problem,
+
+    /// What was done about the problem, `None` if the problem persists
+    pub action: Option<Action>,
+}
+
+impl Finding {
+
+    pub fn new(path: PathBuf, problem: Problem) -> Finding {
+        Finding {
+            path: path,
+            problem: problem,
+            action: None,
+        }
+    }
+
+}
+
+impl Display for Finding {
+
+    fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        try!(write!(fmt, "{}: {}", self.path.display(), self.problem));
+        match self.action {
+            Some(Action::Fixed)       => write!(fmt, " (fixed)"),
+            Some(Action::Quarantined) => write!(fmt, " (quarantined)"),
+            None                      => Ok(()),
+        }
+    }
+
+}
+
+/// The result of the verification of the store
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerifyReport {
+    /// The number of files which were checked
+    pub checked: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl VerifyReport {
+
+    pub fn new() -> VerifyReport {
+        VerifyReport {
+            checked: 0,
+            findings: vec![],
+        }
+    }
+
+    /// Whether all problems were fixed or quarantined (or there were none)
+    pub fn is_ok(&self) -> bool {
+        self.findings.iter().all(|f| f.action.is_some())
+    }
+
+    /// Render the report as JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).chain_err(|| SEK::IoError)
+    }
+
+}
+
+/// Options for `Store::verify()`
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    known_modules: Vec<String>,
+    fix: bool,
+    quarantine: bool,
+}
+
+impl VerifyOptions {
+
+    pub fn new() -> VerifyOptions {
+        VerifyOptions::default()
+    }
+
+    /// The collections of the modules, files outside of them are reported as orphaned
+    ///
+    /// If no modules are set, files are not checked for being orphaned.
+    pub fn with_known_modules(mut self, modules: Vec<String>) -> VerifyOptions {
+        self.known_modules = modules;
+        self
+    }
+
+    /// Fix missing and invalid version information in headers
+    pub fn with_fix(mut self, fix: bool) -> VerifyOptions {
+        self.fix = fix;
+        self
+    }
+
+    /// Move files which cannot be parsed as entries to the quarantine directory
+    pub fn with_quarantine(mut self, quarantine: bool) -> VerifyOptions {
+        self.quarantine = quarantine;
+        self
+    }
+
+}
+
+/// Verify the files in the store at `store_path`
+///
+/// Fixed entries are written through `backend`, the files are locked while they are written.
+pub fn verify_store(store_path: &Path,
+                    backend: &FileAbstraction,
+                    lock_wait: &LockWait,
+                    opts: &VerifyOptions)
+    -> Result<VerifyReport>
+{
+    let mut report = VerifyReport::new();
+
+    for dent in WalkDir::new(store_path).into_iter().filter_entry(|e| !is_hidden(e)) {
+        let dent = try!(dent.chain_err(|| SEK::IoError));
+        if !dent.file_type().is_file() {
+            continue;
+        }
+
+        let local = try!(dent.path()
+                         .strip_prefix(store_path)
+                         .map(PathBuf::from)
+                         .map_err(|_| SE::from_kind(SEK::StorePathError)));
+
+        debug!("Verifying {:?}", local);
+        report.checked += 1;
+
+        let (mut problems, parsed) = check_file(dent.path());
+        if is_orphaned(&local, &opts.known_modules) {
+            problems.push(Problem::OrphanedFile);
+        }
+
+        let mut findings : Vec<Finding> = problems
+            .into_iter()
+            .map(|p| Finding::new(local.clone(), p))
+            .collect();
+
+        if opts.quarantine && findings.iter().any(|f| f.problem.needs_quarantine()) {
+            try!(quarantine(store_path, &local));
+            for f in findings.iter_mut() {
+                f.action = Some(Action::Quarantined);
+            }
+        } else if opts.fix && findings.iter().any(|f| f.problem.is_fixable()) {
+            if let Some((mut header, content)) = parsed {
+                if fix_header(&mut header) {
+                    try!(write_file(backend, lock_wait, store_path, &local, header, content));
+                    for f in findings.iter_mut().filter(|f| f.problem.is_fixable()) {
+                        f.action = Some(Action::Fixed);
+                    }
+                }
+            }
+        }
+
+        report.findings.append(&mut findings);
+    }
+
+    Ok(report)
+}
+
+/// Move the file at `local` (relative to the store) to the quarantine directory
+///
+/// If there is a file with the same name in the quarantine already, a number is appended to the
+/// name.
+pub fn quarantine(store_path: &Path, local: &Path) -> Result<PathBuf> {
+    let base = store_path.join(QUARANTINE_DIR).join(local);
+    let mut dest = base.clone();
+    let mut n    = 0;
+    while dest.exists() {
+        n += 1;
+        dest = PathBuf::from(format!("{}-{}", base.display(), n));
+    }
+
+    if let Some(parent) = dest.parent() {
+        try!(fs::create_dir_all(parent).chain_err(|| SEK::DirNotCreated));
+    }
+
+    info!("Moving {:?} to quarantine: {:?}", local, dest);
+    fs::rename(store_path.join(local), &dest)
+        .chain_err(|| SEK::FileNotRenamed)
+        .map(|_| dest)
+}
+
+fn is_hidden(dent: &DirEntry) -> bool {
+    dent.depth() > 0 && dent.file_name().to_str().map(|s| s.starts_with('.')).unwrap_or(false)
+}
+
+fn is_orphaned(local: &Path, known_modules: &[String]) -> bool {
+    if known_modules.is_empty() {
+        return false;
+    }
+
+    let mut components = local.components();
+    match (components.next(), components.next()) {
+        (Some(module), Some(_)) => {
+            let module = module.as_os_str().to_str().unwrap_or("");
+            module != TRASH_COLLECTION && !known_modules.iter().any(|m| m == module)
+        },
+        _ => true, // a file in the root of the store
+    }
+}
+
+/// Check the file at `path`, returning its problems and its header and content if it could be
+/// parsed
+fn check_file(path: &Path) -> (Vec<Problem>, Option<(Value, String)>) {
+    let mut buf = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut buf)) {
+        return (vec![Problem::Unreadable { error: format!("{}", e) }], None);
+    }
+
+    let (header, content) = match split_entry_buffer(&buf) {
+        Ok(parts) => parts,
+        Err(_)    => return (vec![Problem::MalformedEntry], None),
+    };
+
+    let header : Value = match ::toml::de::from_str(header) {
+        Ok(header) => header,
+        Err(e)     => return (vec![Problem::InvalidHeader { error: format!("{}", e) }], None),
+    };
+
+    (check_header(&header), Some((header, String::from(content))))
+}
+
+fn check_header(header: &Value) -> Vec<Problem> {
+    let mut problems = vec![];
+    let table = match *header {
+        Value::Table(ref t) => t,
+        _                   => return vec![Problem::MissingMainSection],
+    };
+
+    match table.get("imag") {
+        Some(&Value::Table(ref imag)) => match imag.get("version") {
+            Some(&Value::String(ref v)) => if Version::parse(v).is_err() {
+                problems.push(Problem::InvalidVersion { version: v.clone() });
+            },
+            Some(v) => problems.push(Problem::InvalidVersion { version: format!("{}", v) }),
+            None    => problems.push(Problem::MissingVersion),
+        },
+        _ => problems.push(Problem::MissingMainSection),
+    }
+
+    let keys : Vec<String> = table
+        .iter()
+        .filter(|&(_, v)| !is_match!(*v, Value::Table(_)))
+        .map(|(k, _)| k.clone())
+        .collect();
+
+    if !keys.is_empty() {
+        problems.push(Problem::NonTableInBaseTable { keys: keys });
+    }
+
+    problems
+}
+
+/// Add the `[imag]` section and set `imag.version` to the current version, if it is missing or
+/// invalid
+///
+/// An `imag` value which is not a table is replaced by a new `[imag]` section.
+///
+/// Returns whether the header was changed.
+fn fix_header(header: &mut Value) -> bool {
+    let t = match *header {
+        Value::Table(ref mut t) => t,
+        _                       => return false,
+    };
+
+    let mut changed = false;
+    if !is_match!(t.get("imag"), Some(&Value::Table(_))) {
+        t.insert(String::from("imag"), Value::Table(BTreeMap::new()));
+        changed = true;
+    }
+
+    if let Some(&mut Value::Table(ref mut imag)) = t.get_mut("imag") {
+        let valid = match imag.get("version") {
+            Some(&Value::String(ref v)) => Version::parse(v).is_ok(),
+            _                           => false,
+        };
+
+        if !valid {
+            imag.insert(String::from("version"), Value::String(String::from(version!())));
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+/// Write the fixed entry at `local` (relative to the store)
+///
+/// The entry is written to a temporary file which is then moved in place, while the file is
+/// locked, so no other process reads a half written entry.
+fn write_file(backend: &FileAbstraction,
+              lock_wait: &LockWait,
+              store_path: &Path,
+              local: &Path,
+              header: Value,
+              content: String)
+    -> Result<()>
+{
+    let id    = try!(StoreId::new(Some(store_path.to_path_buf()), local.to_path_buf()));
+    let path  = try!(id.clone().into_pathbuf());
+    let tmp   = transaction_file_path(&path, "tmp");
+    let mut entry = Entry::new(id);
+    *entry.get_header_mut()  = header;
+    *entry.get_content_mut() = content;
+
+    let mut file = backend.new_instance(path.clone());
+    try!(file.lock(lock_wait));
+
+    let res = backend
+        .new_instance(tmp.clone())
+        .write_file_content(&entry)
+        .and_then(|_| backend.rename(&tmp, &path));
+
+    if res.is_err() {
+        let _ = backend.remove_file(&tmp);
+    }
+
+    let unlocked = file.unlock();
+    try!(res.chain_err(|| SEK::FileNotWritten));
+    unlocked
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{self, File};
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+
+    use tempdir::TempDir;
+
+    use file_abstraction::FSFileAbstraction;
+    use file_abstraction::LockWait;
+
+    use super::*;
+
+    fn verify(store: &Path, opts: &VerifyOptions) -> VerifyReport {
+        verify_store(store, &FSFileAbstraction::new(), &LockWait::NoWait, opts).unwrap()
+    }
+
+    fn write(store: &Path, local: &str, s: &str) {
+        let path = store.join(local);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap().write_all(s.as_bytes()).unwrap();
+    }
+
+    fn problems_of(report: &VerifyReport, local: &str) -> Vec<Problem> {
+        report.findings
+            .iter()
+            .filter(|f| f.path == PathBuf::from(local))
+            .map(|f| f.problem.clone())
+            .collect()
+    }
+
+    fn setup() -> TempDir {
+        let dir = TempDir::new("imag-store-verify").unwrap();
+        write(dir.path(), "notes/ok", "---\n[imag]\nversion = \"0.4.0\"\nlinks = []\n---\ntext");
+        write(dir.path(), "notes/malformed", "no header");
+        write(dir.path(), "notes/invalid", "---\n[imag\n---\n");
+        write(dir.path(), "notes/noversion", "---\n[imag]\nlinks = []\n---\ntext");
+        write(dir.path(), "notes/badversion", "---\n[imag]\nversion = \"x\"\n---\ntext");
+        write(dir.path(), "notes/nontable", "---\nfoo = 1\n[imag]\nversion = \"0.4.0\"\n---\n");
+        write(dir.path(), "unknown/a", "---\n[imag]\nversion = \"0.4.0\"\n---\n");
+        write(dir.path(), ".git/HEAD", "ref: refs/heads/master");
+        dir
+    }
+
+    #[test]
+    fn test_verify_finds_problems() {
+        let dir     = setup();
+        let opts    = VerifyOptions::new().with_known_modules(vec![String::from("notes")]);
+        let report  = verify(dir.path(), &opts);
+
+        assert_eq!(report.checked, 7);
+        assert!(!report.is_ok());
+        assert!(problems_of(&report, "notes/ok").is_empty());
+        assert_eq!(problems_of(&report, "notes/malformed"), vec![Problem::MalformedEntry]);
+        assert!(is_match!(problems_of(&report, "notes/invalid")[..],
+                          [Problem::InvalidHeader { .. }]));
+        assert_eq!(problems_of(&report, "notes/noversion"), vec![Problem::MissingVersion]);
+        assert_eq!(problems_of(&report, "notes/badversion"),
+                   vec![Problem::InvalidVersion { version: String::from("x") }]);
+        assert_eq!(problems_of(&report, "notes/nontable"),
+                   vec![Problem::NonTableInBaseTable { keys: vec![String::from("foo")] }]);
+        assert_eq!(problems_of(&report, "unknown/a"), vec![Problem::OrphanedFile]);
+        assert!(report.findings.iter().all(|f| f.action.is_none()));
+
+        let json = report.to_json().unwrap();
+        assert!(json.contains("\"kind\": \"missing-version\""));
+    }
+
+    #[test]
+    fn test_verify_fix_and_quarantine() {
+        let dir     = setup();
+        let opts    = VerifyOptions::new()
+            .with_known_modules(vec![String::from("notes")])
+            .with_fix(true)
+            .with_quarantine(true);
+        let report  = verify(dir.path(), &opts);
+
+        // The orphaned file and the file with non-table values are only reported
+        assert!(!report.is_ok());
+
+        let mut s = String::new();
+        File::open(dir.path().join("notes/noversion")).unwrap().read_to_string(&mut s).unwrap();
+        assert!(s.contains(&format!("version = \"{}\"", version!())));
+        assert!(s.ends_with("---\ntext"));
+        assert!(!dir.path().join("notes/.noversion.imag-tmp").exists());
+
+        for local in &["notes/malformed", "notes/invalid"] {
+            assert!(!dir.path().join(local).exists());
+            assert!(dir.path().join(QUARANTINE_DIR).join(local).exists());
+        }
+
+        let report = verify(dir.path(), &opts);
+        assert_eq!(report.checked, 5);
+        assert_eq!(problems_of(&report, "notes/nontable"),
+                   vec![Problem::NonTableInBaseTable { keys: vec![String::from("foo")] }]);
+        assert_eq!(problems_of(&report, "unknown/a"), vec![Problem::OrphanedFile]);
+        assert_eq!(report.findings.len(), 2);
+    }
+
+    #[test]
+    fn test_verify_fix_replaces_non_table_main_section() {
+        let dir     = TempDir::new("imag-store-verify").unwrap();
+        write(dir.path(), "notes/a", "---\nimag = 1\n---\ntext");
+        let opts    = VerifyOptions::new().with_fix(true);
+        let report  = verify(dir.path(), &opts);
+
+        assert!(problems_of(&report, "notes/a").contains(&Problem::MissingMainSection));
+        assert!(report.findings
+                .iter()
+                .filter(|f| f.problem == Problem::MissingMainSection)
+                .all(|f| f.action == Some(Action::Fixed)));
+
+        let mut s = String::new();
+        File::open(dir.path().join("notes/a")).unwrap().read_to_string(&mut s).unwrap();
+        assert!(s.contains("[imag]"));
+        assert!(s.contains(&format!("version = \"{}\"", version!())));
+
+        let report = verify(dir.path(), &opts);
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn test_fix_header_reports_unchanged_header() {
+        let mut header : Value = ::toml::de::from_str("[imag]\nversion = \"0.4.0\"").unwrap();
+        assert!(!fix_header(&mut header));
+
+        let mut header : Value = ::toml::de::from_str("imag = 1").unwrap();
+        assert!(fix_header(&mut header));
+        assert!(check_header(&header).is_empty());
+    }
+
+    #[test]
+    fn test_quarantine_keeps_orphaned_entries() {
+        let dir     = setup();
+        let opts    = VerifyOptions::new()
+            .with_known_modules(vec![String::from("notes")])
+            .with_quarantine(true);
+        let report  = verify(dir.path(), &opts);
+
+        assert_eq!(problems_of(&report, "unknown/a"), vec![Problem::OrphanedFile]);
+        assert!(report.findings
+                .iter()
+                .filter(|f| f.path == PathBuf::from("unknown/a"))
+                .all(|f| f.action.is_none()));
+        assert!(dir.path().join("unknown/a").exists());
+        assert!(!dir.path().join(QUARANTINE_DIR).join("unknown/a").exists());
+    }
+
+}
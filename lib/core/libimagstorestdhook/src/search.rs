@@ -0,0 +1,240 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Hooks for keeping the search index from `libimagsearch` up to date
+//!
+//! The hooks do not load the index, they only append the changes to the journal of the index
+//! directory in the runtime path.
+//!
+//! The hooks are configured in `[store.hooks.search-index]`. The `aspect` key names the aspect
+//! the hooks get registered for (by `libimagrt`), the `header-fields` key lists the header fields
+//! which should be indexed in addition to the content.
+//!
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use toml::Value;
+
+use libimagsearch::config::header_fields;
+use libimagsearch::index::Index;
+use libimagsearch::index::Journal;
+use libimagstore::hook::Hook;
+use libimagstore::hook::accessor::HookDataAccessor as HDA;
+use libimagstore::hook::accessor::HookDataAccessorProvider;
+use libimagstore::hook::accessor::NonMutableHookDataAccessor;
+use libimagstore::hook::accessor::StoreIdAccessor;
+use libimagstore::hook::error::HookError as HE;
+use libimagstore::hook::error::HookErrorKind as HEK;
+use libimagstore::hook::error::ResultExt;
+use libimagstore::hook::error::Result as HookResult;
+use libimagstore::hook::position::HookPosition;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+
+/// A hook which records changes to entries in the journal of the search index
+#[derive(Debug)]
+pub struct SearchIndexHook {
+    journal: Journal,
+    position: HookPosition,
+    header_fields: Vec<String>,
+    moved_from: Arc<Mutex<Option<StoreId>>>,
+}
+
+impl SearchIndexHook {
+
+    /// Create the hooks for all positions where the index has to be updated
+    ///
+    /// These are the post-update, post-delete, pre-move and post-move positions. The index
+    /// directory is found in the runtime path `rtp`.
+    pub fn new_set(rtp: &Path) -> Vec<(HookPosition, SearchIndexHook)> {
+        let journal    = Journal::new(Index::path_for_rtp(rtp));
+        let moved_from = Arc::new(Mutex::new(None));
+
+        vec![
+            HookPosition::PostUpdate,
+            HookPosition::PostDelete,
+            HookPosition::PreMove,
+            HookPosition::PostMove,
+        ]
+        .into_iter()
+        .map(|position| {
+            (position, SearchIndexHook {
+                journal: journal.clone(),
+                position: position,
+                header_fields: vec![],
+                moved_from: moved_from.clone(),
+            })
+        })
+        .collect()
+    }
+
+}
+
+impl Hook for SearchIndexHook {
+
+    fn name(&self) -> &'static str {
+        "search-index"
+    }
+
+    fn set_config(&mut self, cfg: &Value) {
+        self.header_fields = header_fields(Some(cfg));
+    }
+
+}
+
+impl HookDataAccessorProvider for SearchIndexHook {
+
+    fn accessor(&self) -> HDA {
+        match self.position {
+            HookPosition::PostUpdate => HDA::NonMutableAccess(self),
+            _                        => HDA::StoreIdAccess(self),
+        }
+    }
+
+}
+
+impl NonMutableHookDataAccessor for SearchIndexHook {
+
+    fn access(&self, fle: &FileLockEntry) -> HookResult<()> {
+        debug!("[SearchIndexHook] Indexing '{}'", fle.get_location());
+        self.journal
+            .add(fle, &self.header_fields)
+            .chain_err(|| HEK::HookExecutionError)
+    }
+
+}
+
+impl StoreIdAccessor for SearchIndexHook {
+
+    fn access(&self, id: &StoreId) -> HookResult<()> {
+        match self.position {
+            HookPosition::PostDelete => {
+                debug!("[SearchIndexHook] Removing '{}' from index", id);
+                self.journal.remove(id).chain_err(|| HEK::HookExecutionError)
+            },
+
+            HookPosition::PreMove => {
+                let mut moved_from = try!(self.moved_from
+                    .lock()
+                    .map_err(|_| HE::from_kind(HEK::HookExecutionError)));
+                *moved_from = Some(id.clone());
+                Ok(())
+            },
+
+            HookPosition::PostMove => {
+                let from = try!(self.moved_from
+                    .lock()
+                    .map_err(|_| HE::from_kind(HEK::HookExecutionError)))
+                    .take();
+
+                match from {
+                    Some(from) => {
+                        debug!("[SearchIndexHook] Moving '{}' to '{}' in index", from, id);
+                        self.journal.rename(&from, id).chain_err(|| HEK::HookExecutionError)
+                    },
+                    None => {
+                        warn!("[SearchIndexHook] Source of move to '{}' unknown", id);
+                        Ok(())
+                    },
+                }
+            },
+
+            _ => Err(HE::from_kind(HEK::AccessTypeViolation)),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagsearch::index::Index;
+    use libimagsearch::query::Query;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use super::SearchIndexHook;
+
+    fn setup_store(rtp: &PathBuf) -> Store {
+        let config : Value = toml_from_str(r#"
+            post-update-hook-aspects = [ "index" ]
+            post-delete-hook-aspects = [ "index" ]
+            pre-move-hook-aspects    = [ "index" ]
+            post-move-hook-aspects   = [ "index" ]
+
+            [hooks.search-index]
+            header-fields = [ "note.name" ]
+        "#).unwrap();
+
+        let backend = Box::new(InMemoryFileAbstraction::new());
+        let store   = Store::new_with_backend(PathBuf::from("/"), Some(config), backend).unwrap();
+        for (position, hook) in SearchIndexHook::new_set(rtp) {
+            store.register_hook(position, "index", Box::new(hook)).unwrap();
+        }
+        store
+    }
+
+    fn search(rtp: &PathBuf, query: &str) -> Vec<String> {
+        Index::load(&Index::path_for_rtp(rtp))
+            .unwrap()
+            .search(&Query::parse(query).unwrap())
+            .into_iter()
+            .map(|hit| format!("{}", hit.id()))
+            .collect()
+    }
+
+    #[test]
+    fn test_index_is_updated() {
+        let dir   = TempDir::new("imag-search-hook-test").unwrap();
+        let rtp   = dir.path().to_path_buf();
+        let store = setup_store(&rtp);
+
+        {
+            let mut entry = store.create(PathBuf::from("notes/a")).unwrap();
+            *entry.get_content_mut() = String::from("Some searchable content");
+        }
+        {
+            let mut entry = store.create(PathBuf::from("notes/b")).unwrap();
+            entry.get_header_mut()
+                .as_table_mut()
+                .unwrap()
+                .insert(String::from("note"), toml_from_str("name = \"Groceries\"").unwrap());
+        }
+
+        assert_eq!(search(&rtp, "searchable"), vec!["notes/a"]);
+        assert_eq!(search(&rtp, "groceries"), vec!["notes/b"]);
+
+        let from = StoreId::new_baseless(PathBuf::from("notes/a")).unwrap();
+        let to   = StoreId::new_baseless(PathBuf::from("notes/c")).unwrap();
+        store.move_by_id(from, to).unwrap();
+        assert_eq!(search(&rtp, "searchable"), vec!["notes/c"]);
+
+        store.delete(PathBuf::from("notes/c")).unwrap();
+        assert!(search(&rtp, "searchable").is_empty());
+    }
+
+}
@@ -0,0 +1,72 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use toml::Value;
+
+use libimagstore::storeid::StoreId;
+
+/// Build the commit message for a hook
+///
+/// Uses the `message` key from the hook configuration `cfg` or `default` if there is none, and
+/// replaces the `{id}` and `{module}` placeholders.
+pub fn commit_message(cfg: Option<&Value>, default: &str, id: &StoreId, module: &str) -> String {
+    let template = match cfg {
+        Some(&Value::Table(ref t)) => match t.get("message") {
+            Some(&Value::String(ref s)) => s.clone(),
+            Some(_) => {
+                warn!("Key 'message' in git hook configuration is not a String, using default");
+                String::from(default)
+            },
+            None => String::from(default),
+        },
+        _ => String::from(default),
+    };
+
+    template
+        .replace("{id}", &format!("{}", id))
+        .replace("{module}", module)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagstore::storeid::StoreId;
+
+    use super::commit_message;
+
+    #[test]
+    fn test_default_message() {
+        let id  = StoreId::new_baseless(PathBuf::from("notes/foo")).unwrap();
+        let msg = commit_message(None, "Update {id} ({module})", &id, "imag-notes");
+        assert_eq!(msg, "Update notes/foo (imag-notes)");
+    }
+
+    #[test]
+    fn test_configured_message() {
+        let id  = StoreId::new_baseless(PathBuf::from("notes/foo")).unwrap();
+        let cfg : Value = toml_from_str(r#"message = "{module}: {id}""#).unwrap();
+        let msg = commit_message(Some(&cfg), "Update {id} ({module})", &id, "imag-notes");
+        assert_eq!(msg, "imag-notes: notes/foo");
+    }
+
+}
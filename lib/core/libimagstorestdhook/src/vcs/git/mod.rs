@@ -0,0 +1,145 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Hooks for committing changes of entries to a git repository
+//!
+//! The store directory has to be a git repository already, the hooks do not initialize one.
+//! Each hook stages and commits only the files of the entry it was executed for, so other
+//! (uncommitted) changes in the store are left alone.
+//!
+//! The hooks are configured in `[store.hooks.<name>]`, where `<name>` is the name of the hook
+//! (`git-update`, `git-delete` or `git-move`). The `aspect` key names the aspect the hook gets
+//! registered for (by `libimagrt`), the `message` key can be used to set the commit message. The
+//! following placeholders can be used in the commit message:
+//!
+//! * `{id}` - The StoreId of the entry
+//! * `{from}` - The StoreId the entry was moved from (only for `git-move`)
+//! * `{module}` - The name of the imag module which altered the entry
+//!
+
+pub mod config;
+pub mod delete;
+pub mod movehook;
+pub mod runner;
+pub mod update;
+
+pub use self::delete::GitDeleteHook;
+pub use self::movehook::GitMoveHook;
+pub use self::update::GitUpdateHook;
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use tempdir::TempDir;
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagstore::hook::position::HookPosition;
+    use libimagstore::store::Store;
+
+    use super::*;
+
+    fn git(dir: &PathBuf, args: &[&str]) -> String {
+        let out = Command::new("git").arg("-C").arg(dir).args(args).output().unwrap();
+        assert!(out.status.success(), "git {:?} failed", args);
+        String::from_utf8(out.stdout).unwrap()
+    }
+
+    fn setup_repo() -> (TempDir, PathBuf) {
+        let dir  = TempDir::new("imag-git-hook-test").unwrap();
+        let path = dir.path().to_path_buf();
+        git(&path, &["init", "-q"]);
+        git(&path, &["config", "user.name", "imag"]);
+        git(&path, &["config", "user.email", "imag@example.com"]);
+        (dir, path)
+    }
+
+    fn setup_store(path: &PathBuf) -> Store {
+        let config : Value = toml_from_str(r#"
+            post-update-hook-aspects = [ "vcs" ]
+            post-delete-hook-aspects = [ "vcs" ]
+            pre-move-hook-aspects    = [ "vcs" ]
+            post-move-hook-aspects   = [ "vcs" ]
+        "#).unwrap();
+        let store = Store::new(path.clone(), Some(config)).unwrap();
+        let module = String::from("imag-test");
+
+        let update = GitUpdateHook::new(path.clone(), module.clone());
+        store.register_hook(HookPosition::PostUpdate, "vcs", Box::new(update)).unwrap();
+
+        let delete = GitDeleteHook::new(path.clone(), module.clone());
+        store.register_hook(HookPosition::PostDelete, "vcs", Box::new(delete)).unwrap();
+
+        let (pre, post) = GitMoveHook::new_pair(path.clone(), module);
+        store.register_hook(HookPosition::PreMove, "vcs", Box::new(pre)).unwrap();
+        store.register_hook(HookPosition::PostMove, "vcs", Box::new(post)).unwrap();
+
+        store
+    }
+
+    fn log(path: &PathBuf) -> Vec<String> {
+        git(path, &["log", "--format=%s"]).lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_update_is_committed() {
+        let (_dir, path) = setup_repo();
+        let store = setup_store(&path);
+
+        {
+            let mut entry = store.create(PathBuf::from("test/update")).unwrap();
+            entry.get_content_mut().push_str("content");
+        }
+
+        assert_eq!(log(&path), vec!["[imag] Update 'test/update' (imag-test)"]);
+        assert!(git(&path, &["status", "--porcelain"]).is_empty());
+    }
+
+    #[test]
+    fn test_delete_is_committed() {
+        let (_dir, path) = setup_repo();
+        let store = setup_store(&path);
+
+        let _ = store.create(PathBuf::from("test/delete")).unwrap();
+        store.delete(PathBuf::from("test/delete")).unwrap();
+
+        assert_eq!(log(&path), vec![
+                   "[imag] Delete 'test/delete' (imag-test)",
+                   "[imag] Update 'test/delete' (imag-test)",
+        ]);
+        assert!(git(&path, &["ls-files"]).is_empty());
+    }
+
+    #[test]
+    fn test_move_is_committed() {
+        let (_dir, path) = setup_repo();
+        let store = setup_store(&path);
+
+        let _ = store.create(PathBuf::from("test/old")).unwrap();
+        let old = ::libimagstore::storeid::StoreId::new_baseless(PathBuf::from("test/old")).unwrap();
+        let new = ::libimagstore::storeid::StoreId::new_baseless(PathBuf::from("test/new")).unwrap();
+        store.move_by_id(old, new).unwrap();
+
+        assert_eq!(log(&path)[0], "[imag] Move 'test/old' to 'test/new' (imag-test)");
+        assert_eq!(git(&path, &["ls-files"]).trim(), "test/new");
+    }
+
+}
@@ -0,0 +1,142 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use toml::Value;
+
+use libimagstore::hook::Hook;
+use libimagstore::hook::accessor::HookDataAccessor as HDA;
+use libimagstore::hook::accessor::HookDataAccessorProvider;
+use libimagstore::hook::accessor::StoreIdAccessor;
+use libimagstore::hook::error::HookError as HE;
+use libimagstore::hook::error::HookErrorKind as HEK;
+use libimagstore::hook::error::ResultExt;
+use libimagstore::hook::error::Result as HookResult;
+use libimagstore::hook::position::HookPosition;
+use libimagstore::storeid::StoreId;
+
+use vcs::git::config::commit_message;
+use vcs::git::runner::GitRunner;
+
+/// A hook which commits the move of an entry
+///
+/// The post-move hooks only get the new StoreId of the entry, so this hook comes as a pair (see
+/// `GitMoveHook::new_pair()`): One instance has to be registered for the pre-move position and
+/// remembers the old StoreId, the other one has to be registered for the post-move position and
+/// commits the removal of the old and the addition of the new file.
+#[derive(Debug)]
+pub struct GitMoveHook {
+    git: GitRunner,
+    module: String,
+    config: Option<Value>,
+    position: HookPosition,
+    moved_from: Arc<Mutex<Option<StoreId>>>,
+}
+
+impl GitMoveHook {
+
+    /// Create the hook for the pre-move and the hook for the post-move position (in this order)
+    pub fn new_pair(storepath: PathBuf, module: String) -> (GitMoveHook, GitMoveHook) {
+        let moved_from = Arc::new(Mutex::new(None));
+        let pre = GitMoveHook {
+            git: GitRunner::new(storepath),
+            module: module,
+            config: None,
+            position: HookPosition::PreMove,
+            moved_from: moved_from,
+        };
+
+        let post = GitMoveHook {
+            git: pre.git.clone(),
+            module: pre.module.clone(),
+            config: None,
+            position: HookPosition::PostMove,
+            moved_from: pre.moved_from.clone(),
+        };
+
+        (pre, post)
+    }
+
+}
+
+impl Hook for GitMoveHook {
+
+    fn name(&self) -> &'static str {
+        "git-move"
+    }
+
+    fn set_config(&mut self, cfg: &Value) {
+        self.config = Some(cfg.clone());
+    }
+
+}
+
+impl HookDataAccessorProvider for GitMoveHook {
+
+    fn accessor(&self) -> HDA {
+        HDA::StoreIdAccess(self)
+    }
+
+}
+
+impl StoreIdAccessor for GitMoveHook {
+
+    fn access(&self, id: &StoreId) -> HookResult<()> {
+        let mut moved_from = try!(self.moved_from
+            .lock()
+            .map_err(|_| HE::from_kind(HEK::HookExecutionError)));
+
+        match self.position {
+            HookPosition::PreMove => {
+                debug!("[GitMoveHook] Remembering '{}'", id);
+                *moved_from = Some(id.clone());
+                Ok(())
+            },
+
+            HookPosition::PostMove => {
+                let mut paths = vec![id.local().clone()];
+                let from = moved_from.take();
+
+                if let Some(ref from) = from {
+                    paths.push(from.local().clone());
+                } else {
+                    warn!("[GitMoveHook] Source of move to '{}' unknown", id);
+                }
+
+                let from = from.map(|f| format!("{}", f)).unwrap_or_else(|| String::from("?"));
+                let msg  = commit_message(self.config.as_ref(),
+                                          "[imag] Move '{from}' to '{id}' ({module})",
+                                          id,
+                                          &self.module)
+                    .replace("{from}", &from);
+
+                debug!("[GitMoveHook] Committing move of '{}' to '{}'", from, id);
+                self.git
+                    .commit_paths(&paths, &msg)
+                    .chain_err(|| HEK::HookExecutionError)
+            },
+
+            _ => Err(HE::from_kind(HEK::AccessTypeViolation)),
+        }
+    }
+
+}
@@ -0,0 +1,126 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use error::StdHookError as SHE;
+use error::StdHookErrorKind as SHEK;
+use error::Result;
+
+/// A helper for running `git` in the store directory
+#[derive(Debug, Clone)]
+pub struct GitRunner {
+    workdir: PathBuf,
+}
+
+impl GitRunner {
+
+    pub fn new(workdir: PathBuf) -> GitRunner {
+        GitRunner {
+            workdir: workdir,
+        }
+    }
+
+    /// Get a `git` command which runs in the store directory
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(&self.workdir);
+        cmd
+    }
+
+    /// Check whether the store directory is a git repository
+    pub fn is_repository(&self) -> bool {
+        self.command()
+            .arg("rev-parse")
+            .arg("--git-dir")
+            .output()
+            .map(|out| out.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Check whether `path` (relative to the store directory) is known to git
+    pub fn is_tracked(&self, path: &PathBuf) -> bool {
+        self.command()
+            .arg("ls-files")
+            .arg("--error-unmatch")
+            .arg("--")
+            .arg(path)
+            .output()
+            .map(|out| out.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Stage `paths` (relative to the store directory) and commit them with `message`
+    ///
+    /// Paths which neither exist nor are known to git are ignored. If there is nothing to
+    /// commit, no commit is made.
+    pub fn commit_paths(&self, paths: &[PathBuf], message: &str) -> Result<()> {
+        if !self.is_repository() {
+            return Err(SHE::from_kind(SHEK::NotAGitRepository));
+        }
+
+        let paths : Vec<&PathBuf> = paths
+            .iter()
+            .filter(|p| {
+                let mut full = self.workdir.clone();
+                full.push(p);
+                full.exists() || self.is_tracked(p)
+            })
+            .collect();
+
+        if paths.is_empty() {
+            debug!("No paths to commit");
+            return Ok(());
+        }
+
+        try!(self.run(self.command().arg("add").arg("-A").arg("--").args(&paths), "git add"));
+
+        let nothing_staged = try!(self.command()
+            .arg("diff")
+            .arg("--cached")
+            .arg("--quiet")
+            .arg("--")
+            .args(&paths)
+            .status())
+            .success();
+
+        if nothing_staged {
+            debug!("Nothing changed in {:?}, not committing", paths);
+            return Ok(());
+        }
+
+        self.run(self.command().arg("commit").arg("-m").arg(message).arg("--").args(&paths),
+                 "git commit")
+    }
+
+    fn run(&self, cmd: &mut Command, name: &str) -> Result<()> {
+        debug!("Running: {:?}", cmd);
+        let output = try!(cmd.output());
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            debug!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+            debug!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+            Err(SHE::from_kind(SHEK::GitCommandFailed(String::from(name))))
+        }
+    }
+
+}
@@ -0,0 +1,91 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+
+use toml::Value;
+
+use libimagstore::hook::Hook;
+use libimagstore::hook::accessor::HookDataAccessor as HDA;
+use libimagstore::hook::accessor::HookDataAccessorProvider;
+use libimagstore::hook::accessor::NonMutableHookDataAccessor;
+use libimagstore::hook::error::HookErrorKind as HEK;
+use libimagstore::hook::error::ResultExt;
+use libimagstore::hook::error::Result as HookResult;
+use libimagstore::store::FileLockEntry;
+
+use vcs::git::config::commit_message;
+use vcs::git::runner::GitRunner;
+
+/// A hook which commits an entry after it was written
+///
+/// Meant to be registered for the post-update position.
+#[derive(Debug)]
+pub struct GitUpdateHook {
+    git: GitRunner,
+    module: String,
+    config: Option<Value>,
+}
+
+impl GitUpdateHook {
+
+    pub fn new(storepath: PathBuf, module: String) -> GitUpdateHook {
+        GitUpdateHook {
+            git: GitRunner::new(storepath),
+            module: module,
+            config: None,
+        }
+    }
+
+}
+
+impl Hook for GitUpdateHook {
+
+    fn name(&self) -> &'static str {
+        "git-update"
+    }
+
+    fn set_config(&mut self, cfg: &Value) {
+        self.config = Some(cfg.clone());
+    }
+
+}
+
+impl HookDataAccessorProvider for GitUpdateHook {
+
+    fn accessor(&self) -> HDA {
+        HDA::NonMutableAccess(self)
+    }
+
+}
+
+impl NonMutableHookDataAccessor for GitUpdateHook {
+
+    fn access(&self, fle: &FileLockEntry) -> HookResult<()> {
+        let id  = fle.get_location();
+        let msg = commit_message(self.config.as_ref(), "[imag] Update '{id}' ({module})", id,
+                                 &self.module);
+
+        debug!("[GitUpdateHook] Committing '{}'", id);
+        self.git
+            .commit_paths(&[id.local().clone()], &msg)
+            .chain_err(|| HEK::HookExecutionError)
+    }
+
+}
@@ -17,6 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::ops::Deref;
 use std::ops::DerefMut;
 
 use toml::Value;
@@ -27,7 +28,9 @@ use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Display;
 
+use libimagstore::store::Entry;
 use libimagstore::store::Store;
+use libimagstore::trash::Trash;
 use libimagstore::storeid::StoreIdIterator;
 use libimagstore::store::FileLockEntry;
 use libimagstore::storeid::StoreId;
@@ -54,6 +57,11 @@ impl CounterUnit {
     pub fn new<S: Into<String>>(unit: S) -> CounterUnit {
         CounterUnit(unit.into())
     }
+
+    /// Get the name of the unit, without the parentheses of the `Display` implementation
+    pub fn name(&self) -> &str {
+        &self.0
+    }
 }
 
 pub struct Counter<'a> {
@@ -198,12 +206,16 @@ impl<'a> Counter<'a> {
         Counter::from_storeid(store, id)
     }
 
+    /// Move the counter to the trash of the store
     pub fn delete(name: CounterName, store: &Store) -> Result<()> {
         debug!("Deleting counter: '{}'", name);
         let id = try!(ModuleEntryPath::new(name)
                       .into_storeid()
                       .chain_err(|| CEK::StoreWriteError));
-        store.delete(id).chain_err(|| CEK::StoreWriteError)
+        Trash::new(store)
+            .trash(id, "counter")
+            .map(|_| ())
+            .chain_err(|| CEK::StoreWriteError)
     }
 
     pub fn all_counters(store: &Store) -> Result<CounterIterator> {
@@ -214,6 +226,26 @@ impl<'a> Counter<'a> {
 
 }
 
+/// The title of a counter entry, which is the name of the counter
+///
+/// Returns `None` if the entry is not a counter.
+pub fn title(entry: &Entry) -> Option<String> {
+    match entry.get_header().read("counter.name") {
+        Ok(Some(&Value::String(ref s))) => Some(s.clone()),
+        _                               => None,
+    }
+}
+
+impl<'a> Deref for Counter<'a> {
+
+    type Target = FileLockEntry<'a>;
+
+    fn deref(&self) -> &FileLockEntry<'a> {
+        &self.fle
+    }
+
+}
+
 trait FromStoreId {
     fn from_storeid(&Store, StoreId) -> Result<Counter>;
 }
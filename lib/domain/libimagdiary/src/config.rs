@@ -20,6 +20,7 @@
 use toml::Value;
 
 use libimagrt::runtime::Runtime;
+use libimagutil::schema::{ConfigSchema, ValueType};
 
 use toml_query::read::TomlValueReadExt;
 
@@ -41,3 +42,8 @@ pub fn get_diary_config_section<'a>(rt: &'a Runtime) -> Option<&'a Value> {
             Err(_) => None,
         })
 }
+
+/// The configuration keys read by this library
+pub fn config_schema() -> ConfigSchema {
+    ConfigSchema::new().key("diary.default_diary", ValueType::String)
+}
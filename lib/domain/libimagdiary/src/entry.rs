@@ -38,3 +38,18 @@ impl DiaryEntry for Entry {
 
 }
 
+/// The title of a diary entry, which is the name of the diary and the date and time of the entry
+///
+/// Returns `None` if the entry is not a diary entry.
+pub fn title(entry: &Entry) -> Option<String> {
+    use is_in_diary::IsInDiary;
+
+    if !entry.is_a_diary_entry() {
+        return None;
+    }
+
+    entry.diary_id().ok().map(|id| {
+        format!("{} {:0>4}-{:0>2}-{:0>2} {:0>2}:{:0>2}",
+                id.diary_name(), id.year(), id.month(), id.day(), id.hour(), id.minute())
+    })
+}
@@ -47,6 +47,7 @@ extern crate libimagerror;
 extern crate libimagentryedit;
 extern crate libimagentryview;
 extern crate libimagrt;
+extern crate libimagutil;
 
 module_entry_path_mod!("diary");
 
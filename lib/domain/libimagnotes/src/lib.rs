@@ -38,7 +38,9 @@
 #[macro_use] extern crate log;
 extern crate toml;
 extern crate toml_query;
+extern crate semver;
 #[macro_use] extern crate error_chain;
+#[cfg(test)] extern crate tempdir;
 
 extern crate libimagrt;
 #[macro_use] extern crate libimagstore;
@@ -48,5 +50,6 @@ extern crate libimagentryedit;
 module_entry_path_mod!("notes");
 
 pub mod error;
+pub mod migration;
 pub mod note;
 
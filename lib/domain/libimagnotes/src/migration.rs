@@ -0,0 +1,134 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Migrations of note headers
+
+use semver::Version;
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadExt;
+
+use libimagstore::error::StoreError as SE;
+use libimagstore::error::StoreErrorKind as SEK;
+use libimagstore::error::ResultExt;
+use libimagstore::migration::Migration;
+use libimagstore::migration::MigrationRegistry;
+use libimagstore::store::Entry;
+use libimagstore::store::Result;
+
+/// Register the migrations of `libimagnotes`
+pub fn register_migrations(registry: &mut MigrationRegistry) -> Result<()> {
+    registry.register(Box::new(NoteNameMigration))
+}
+
+/// Versions before 0.4.0 did not store the name of a note in the `note.name` header field
+///
+/// The name of such notes is the last part of their id.
+#[derive(Debug)]
+struct NoteNameMigration;
+
+impl Migration for NoteNameMigration {
+
+    fn name(&self) -> &'static str {
+        "note-name"
+    }
+
+    fn from_version(&self) -> Version {
+        Version::parse("0.0.0").unwrap() // is a valid version
+    }
+
+    fn to_version(&self) -> Version {
+        Version::parse("0.4.0").unwrap() // is a valid version
+    }
+
+    fn migrate(&self, entry: &mut Entry) -> Result<()> {
+        if !entry.get_location().is_in_collection(&["notes"]) {
+            return Ok(());
+        }
+
+        let has_name = try!(entry.get_header()
+            .read("note.name")
+            .chain_err(|| SEK::HeaderTypeFailure))
+            .is_some();
+
+        if has_name {
+            return Ok(());
+        }
+
+        let name = try!(entry
+            .get_location()
+            .local()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(String::from)
+            .ok_or(SE::from_kind(SEK::EncodingError)));
+
+        debug!("Setting the name of note '{}' to '{}'", entry.get_location(), name);
+        entry.get_header_mut()
+            .insert("note.name", Value::String(name))
+            .map(|_| ())
+            .chain_err(|| SEK::HeaderTypeFailure)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use toml::Value;
+    use toml_query::read::TomlValueReadExt;
+    use toml_query::set::TomlValueSetExt;
+
+    use libimagstore::migration::MigrationRegistry;
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::StoreId;
+
+    use super::register_migrations;
+
+    fn entry(id: &str) -> Entry {
+        let mut entry = Entry::new(StoreId::new_baseless(PathBuf::from(id)).unwrap());
+        let _ = entry.get_header_mut()
+            .set("imag.version", Value::String(String::from("0.3.0")))
+            .unwrap();
+        entry
+    }
+
+    fn name(entry: &Entry) -> Option<String> {
+        match entry.get_header().read("note.name") {
+            Ok(Some(&Value::String(ref s))) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_note_name_is_added() {
+        let mut registry = MigrationRegistry::new();
+        register_migrations(&mut registry).unwrap();
+
+        let mut note = entry("notes/shopping");
+        assert!(registry.migrate_entry(&mut note).unwrap());
+        assert_eq!(name(&note), Some(String::from("shopping")));
+
+        let mut other = entry("diary/2017/01/01");
+        assert!(registry.migrate_entry(&mut other).unwrap());
+        assert_eq!(name(&other), None);
+    }
+
+}
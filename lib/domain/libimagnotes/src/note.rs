@@ -28,11 +28,14 @@ use libimagentryedit::error::Result as EditResult;
 use libimagstore::storeid::IntoStoreId;
 use libimagstore::storeid::StoreId;
 use libimagstore::storeid::StoreIdIterator;
+use libimagstore::store::Entry;
 use libimagstore::store::FileLockEntry;
 use libimagstore::store::Store;
+use libimagstore::idtemplate::IdTemplateValues;
+use libimagstore::trash::Trash;
 
+use toml_query::insert::TomlValueInsertExt;
 use toml_query::read::TomlValueReadExt;
-use toml_query::set::TomlValueSetExt;
 
 use module_path::ModuleEntryPath;
 use error::Result;
@@ -47,28 +50,39 @@ pub struct Note<'a> {
 
 impl<'a> Note<'a> {
 
+    /// Create a new note
+    ///
+    /// If an id template is configured for the "notes" module, the id of the note is built from
+    /// it, with the variable `name` and the date variables of the current time. Otherwise the id
+    /// is `notes/<name>`.
     pub fn new(store: &Store, name: String, text: String) -> Result<Note> {
         use std::ops::DerefMut;
 
         debug!("Creating new Note: '{}'", name);
         let fle = {
-            let mut lockentry = try!(ModuleEntryPath::new(name.clone())
-                .into_storeid()
-                .and_then(|id| store.create(id))
-                .chain_err(|| NEK::StoreWriteError));
+            let values = IdTemplateValues::new().with("name", name.clone()).with_now();
+            let id     = match try!(store.id_from_template("notes", &values)
+                                    .chain_err(|| NEK::StoreWriteError)) {
+                Some(id) => id,
+                None     => try!(ModuleEntryPath::new(name.clone())
+                                 .into_storeid()
+                                 .chain_err(|| NEK::StoreWriteError)),
+            };
+
+            let mut lockentry = try!(store.create(id).chain_err(|| NEK::StoreWriteError));
 
             {
                 let entry  = lockentry.deref_mut();
 
                 {
                     let header = entry.get_header_mut();
-                    let _ = header
-                        .set("note", Value::Table(BTreeMap::new()))
-                        .chain_err(|| NEK::StoreWriteError);
+                    let _ = try!(header
+                        .insert("note", Value::Table(BTreeMap::new()))
+                        .chain_err(|| NEK::StoreWriteError));
 
-                    let _ = header
-                        .set("note.name", Value::String(name))
-                        .chain_err(|| NEK::StoreWriteError);
+                    let _ = try!(header
+                        .insert("note.name", Value::String(name))
+                        .chain_err(|| NEK::StoreWriteError));
                 }
 
                 *entry.get_content_mut() = text;
@@ -83,7 +97,7 @@ impl<'a> Note<'a> {
     pub fn set_name(&mut self, n: String) -> Result<()> {
         self.entry
             .get_header_mut()
-            .set("note.name", Value::String(n))
+            .insert("note.name", Value::String(n))
             .chain_err(|| NEK::StoreWriteError)
             .map(|_| ())
     }
@@ -107,27 +121,81 @@ impl<'a> Note<'a> {
         self.entry.get_content()
     }
 
+    /// Move the note to the trash of the store
     pub fn delete(store: &Store, name: String) -> Result<()> {
-        ModuleEntryPath::new(name)
-            .into_storeid()
-            .and_then(|id| store.delete(id))
+        let id = match try!(Note::find_id(store, &name)) {
+            Some(id) => id,
+            None     => try!(ModuleEntryPath::new(name)
+                             .into_storeid()
+                             .chain_err(|| NEK::StoreWriteError)),
+        };
+
+        Trash::new(store)
+            .trash(id, "notes")
+            .map(|_| ())
             .chain_err(|| NEK::StoreWriteError)
     }
 
     pub fn retrieve(store: &Store, name: String) -> Result<Note> {
-        ModuleEntryPath::new(name)
-            .into_storeid()
-            .and_then(|id| store.retrieve(id))
-            .chain_err(|| NEK::StoreWriteError)
-            .map(|entry| Note { entry: entry })
+        match try!(Note::find_id(store, &name)) {
+            Some(id) => store
+                .retrieve(id)
+                .chain_err(|| NEK::StoreWriteError)
+                .map(|entry| Note { entry: entry }),
+            None => Note::new(store, name, String::new()),
+        }
     }
 
     pub fn get(store: &Store, name: String) -> Result<Option<Note>> {
-        ModuleEntryPath::new(name)
-            .into_storeid()
-            .and_then(|id| store.get(id))
-            .chain_err(|| NEK::StoreWriteError)
-            .map(|o| o.map(|entry| Note { entry: entry }))
+        match try!(Note::find_id(store, &name)) {
+            Some(id) => store
+                .get(id)
+                .chain_err(|| NEK::StoreWriteError)
+                .map(|o| o.map(|entry| Note { entry: entry })),
+            None => Ok(None),
+        }
+    }
+
+    /// Find the id of the note with the name `name`
+    ///
+    /// Without an id template for the "notes" module, the id is `notes/<name>`. With a template,
+    /// the id does not have to contain the name, so the notes are searched for the name in their
+    /// header. Notes which were created before the template was configured are found as well.
+    ///
+    /// Notes which cannot be read, for example because they are borrowed at the moment, are
+    /// skipped. If no other note has the name, the error is returned, so no second note with the
+    /// same name is created.
+    fn find_id(store: &Store, name: &str) -> Result<Option<StoreId>> {
+        let default_id = try!(ModuleEntryPath::new(name)
+                              .into_storeid()
+                              .chain_err(|| NEK::StoreReadError));
+
+        if store.id_template("notes").is_none() {
+            return Ok(Some(default_id));
+        }
+
+        let mut unreadable = None;
+        for id in try!(store.retrieve_for_module("notes").chain_err(|| NEK::StoreReadError)) {
+            if id.clone().without_base() == default_id {
+                return Ok(Some(id));
+            }
+
+            match store.retrieve_copy(id.clone()) {
+                Ok(entry) => match entry.get_header().read("note.name") {
+                    Ok(Some(&Value::String(ref s))) if s == name => return Ok(Some(id)),
+                    _ => continue,
+                },
+                Err(e) => {
+                    debug!("Cannot read the name of '{}': {:?}", id, e);
+                    unreadable = Some(e);
+                },
+            }
+        }
+
+        match unreadable {
+            Some(e) => Err(e).chain_err(|| NEK::StoreReadError),
+            None    => Ok(None),
+        }
     }
 
     pub fn all_notes(store: &Store) -> Result<NoteIterator> {
@@ -138,6 +206,16 @@ impl<'a> Note<'a> {
 
 }
 
+/// The title of a note entry, which is the name of the note
+///
+/// Returns `None` if the entry is not a note.
+pub fn title(entry: &Entry) -> Option<String> {
+    match entry.get_header().read("note.name") {
+        Ok(Some(&Value::String(ref s))) => Some(s.clone()),
+        _                               => None,
+    }
+}
+
 impl<'a> Edit for Note<'a> {
 
     fn edit_content(&mut self, rt: &Runtime) -> EditResult<()> {
@@ -200,3 +278,88 @@ impl<'a> Iterator for NoteIterator<'a> {
 
 }
 
+#[cfg(test)]
+mod test {
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+
+    use super::Note;
+
+    // Notes are found with `Store::retrieve_for_module()`, which only works on the filesystem
+    fn get_store(dir: &TempDir, config: &str) -> Store {
+        let config = ::toml::de::from_str(config).unwrap();
+        Store::new(dir.path().to_path_buf(), Some(config)).unwrap()
+    }
+
+    #[test]
+    fn test_note_with_id_template() {
+        let dir   = TempDir::new("imag-notes-test").unwrap();
+        let store = get_store(&dir, r#"
+            [id-templates]
+            notes = "notes/topics/{slug(name)}"
+        "#);
+
+        {
+            let note = Note::new(&store, String::from("Shopping List"), String::new()).unwrap();
+            assert_eq!(note.get_location().clone().without_base().to_str().unwrap(),
+                       "notes/topics/shopping-list");
+        }
+
+        {
+            let note = Note::new(&store, String::from("shopping list"), String::new()).unwrap();
+            assert_eq!(note.get_location().clone().without_base().to_str().unwrap(),
+                       "notes/topics/shopping-list-1");
+        }
+
+        {
+            let note = Note::get(&store, String::from("Shopping List")).unwrap().unwrap();
+            assert_eq!(note.get_name().unwrap(), "Shopping List");
+        }
+
+        assert!(Note::get(&store, String::from("Other")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_note_while_other_note_is_borrowed() {
+        let dir   = TempDir::new("imag-notes-test").unwrap();
+        let store = get_store(&dir, r#"
+            [id-templates]
+            notes = "notes/topics/{slug(name)}"
+        "#);
+
+        {
+            let _ = Note::new(&store, String::from("Groceries"), String::new()).unwrap();
+            let _ = Note::new(&store, String::from("Shopping List"), String::new()).unwrap();
+        }
+
+        let borrowed = Note::get(&store, String::from("Shopping List")).unwrap().unwrap();
+
+        {
+            let note = Note::get(&store, String::from("Groceries")).unwrap().unwrap();
+            assert_eq!(note.get_name().unwrap(), "Groceries");
+        }
+
+        // The borrowed note could have the name, so it is not reported as missing
+        assert!(Note::get(&store, String::from("Other")).is_err());
+
+        drop(borrowed);
+        assert!(Note::get(&store, String::from("Other")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_note_without_id_template() {
+        let dir   = TempDir::new("imag-notes-test").unwrap();
+        let store = get_store(&dir, "");
+
+        {
+            let note = Note::new(&store, String::from("Shopping List"), String::new()).unwrap();
+            assert_eq!(note.get_location().clone().without_base().to_str().unwrap(),
+                       "notes/Shopping List");
+        }
+
+        let note = Note::get(&store, String::from("Shopping List")).unwrap().unwrap();
+        assert_eq!(note.get_name().unwrap(), "Shopping List");
+    }
+
+}
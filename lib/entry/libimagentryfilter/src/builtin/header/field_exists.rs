@@ -41,7 +41,10 @@ impl FieldExists {
 impl Filter<Entry> for FieldExists {
 
     fn filter(&self, e: &Entry) -> bool {
-        e.get_header().read(&self.header_field_path[..]).is_ok()
+        e.get_header()
+            .read(&self.header_field_path[..])
+            .map(|val| val.is_some())
+            .unwrap_or(false)
     }
 
 }
@@ -36,15 +36,15 @@ impl Predicate for EqLt {
         match self.comp {
             Value::Integer(i) => {
                 match *v {
-                    Value::Integer(j) => i < j,
-                    Value::Float(f) => (i as f64) < f,
+                    Value::Integer(j) => j < i,
+                    Value::Float(f) => f < (i as f64),
                     _ => false,
                 }
             },
             Value::Float(f) => {
                 match *v {
-                    Value::Integer(i) => f < (i as f64),
-                    Value::Float(d) => f < d,
+                    Value::Integer(i) => (i as f64) < f,
+                    Value::Float(d) => d < f,
                     _ => false,
                 }
             },
@@ -77,3 +77,65 @@ impl Filter<Entry> for FieldLt {
 
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use filters::filter::Filter;
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::StoreId;
+
+    use super::FieldLt;
+
+    fn entry() -> Entry {
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        let mut entry = Entry::new(id);
+        *entry.get_header_mut() = toml_from_str(r#"
+            [todo]
+            priority = 3
+            effort = 1.5
+            status = "pending"
+        "#).unwrap();
+        entry
+    }
+
+    fn filter(path: &str, value: Value) -> bool {
+        FieldLt::new(String::from(path), value).filter(&entry())
+    }
+
+    #[test]
+    fn test_integer_field() {
+        let matches = |v| filter("todo.priority", v);
+
+        // The entry matches if the header value is less than the value of the filter
+        assert!(!matches(Value::Integer(2)));
+        assert!(!matches(Value::Integer(3)));
+        assert!(matches(Value::Integer(4)));
+
+        assert!(!matches(Value::Float(2.5)));
+        assert!(!matches(Value::Float(3.0)));
+        assert!(matches(Value::Float(3.5)));
+    }
+
+    #[test]
+    fn test_float_field() {
+        let matches = |v| filter("todo.effort", v);
+
+        assert!(!matches(Value::Float(1.0)));
+        assert!(!matches(Value::Float(1.5)));
+        assert!(matches(Value::Float(2.0)));
+
+        assert!(!matches(Value::Integer(1)));
+        assert!(matches(Value::Integer(2)));
+    }
+
+    #[test]
+    fn test_other_fields() {
+        assert!(!filter("todo.status", Value::Integer(1)));
+        assert!(!filter("todo.missing", Value::Integer(1)));
+    }
+
+}
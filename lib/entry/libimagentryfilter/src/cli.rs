@@ -17,3 +17,571 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+//! A textual language for filters, to be used on the commandline
+//!
+//! With this language, filters can be passed to all imag binaries the same way, via the
+//! `--filter` argument (see `filter_argument()` and `filter_from_matches()`). For example:
+//!
+//! ```ignore
+//! header.todo.status == "pending" and tag:work and not content ~ /draft/
+//! ```
+//!
+//! The following filters are available:
+//!
+//! * `header.<path> == <value>` and `header.<path> != <value>`
+//! * `header.<path> > <number>`, `<`, `>=` and `<=`
+//! * `header.<path> ~ /<regex>/` - the header field is a string which matches the regex
+//! * `header.<path> exists`
+//! * `header.<path> is empty`
+//! * `header.<path> is <type>` - with type being one of `string`, `integer`, `float`,
+//!   `boolean`, `array` or `table`
+//! * `content ~ /<regex>/`
+//! * `content.length > <number>` and `content.length < <number>`
+//! * `tag:<tag>` - the entry is tagged with `<tag>`
+//! * `true` and `false`
+//!
+//! Values are strings (`"foo"`), integers, floats or booleans (`true`, `false`). The path of a
+//! header field is a `toml-query` path.
+//!
+//! Filters can be combined with `and`, `or` and `not`, where `not` binds strongest and `or`
+//! weakest. Parentheses can be used for grouping.
+//!
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use clap::{Arg, ArgMatches};
+use filters::filter::Filter;
+use regex::Regex;
+use toml::Value;
+
+use libimagstore::store::Entry;
+use libimagentrytag::tag::is_tag_str;
+
+use builtin::bool_filter::BoolFilter;
+use builtin::content::grep::ContentGrep;
+use builtin::content::length::is_over::ContentLengthIsOver;
+use builtin::content::length::is_under::ContentLengthIsUnder;
+use builtin::header::field_eq::FieldEq;
+use builtin::header::field_exists::FieldExists;
+use builtin::header::field_grep::FieldGrep;
+use builtin::header::field_gt::FieldGt;
+use builtin::header::field_isempty::FieldIsEmpty;
+use builtin::header::field_istype::FieldIsType;
+use builtin::header::field_istype::Type;
+use builtin::header::field_lt::FieldLt;
+use error::FilterError as FE;
+use error::FilterErrorKind as FEK;
+use error::Result;
+use tags::HasTag;
+
+/// The name of the `--filter` argument
+pub const FILTER_ARG_NAME : &'static str = "filter";
+
+/// Get the `--filter` argument, so all binaries accept filters the same way
+pub fn filter_argument<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(FILTER_ARG_NAME)
+        .long("filter")
+        .takes_value(true)
+        .required(false)
+        .help("Only use entries which match this filter, for example: 'header.todo.status == \"pending\" and tag:work'")
+        .value_name("FILTER")
+}
+
+/// Parse the filter which was passed with the `--filter` argument, if any
+pub fn filter_from_matches(matches: &ArgMatches) -> Result<Option<EntryFilter>> {
+    match matches.value_of(FILTER_ARG_NAME) {
+        Some(s) => EntryFilter::parse(s).map(Some),
+        None    => Ok(None),
+    }
+}
+
+/// A filter built from the filter language
+///
+/// Wraps the filters from the `builtin` and `tags` modules, combined with the operators of the
+/// `filters` crate.
+pub struct EntryFilter(Box<Filter<Entry>>);
+
+impl EntryFilter {
+
+    fn new<F: Filter<Entry> + 'static>(f: F) -> EntryFilter {
+        EntryFilter(Box::new(f))
+    }
+
+    /// Parse a filter expression
+    pub fn parse(s: &str) -> Result<EntryFilter> {
+        let tokens = try!(tokenize(s));
+        let mut parser = Parser {
+            tokens: tokens,
+            pos: 0,
+            end: s.len(),
+        };
+
+        let filter = try!(parser.parse_or());
+        match parser.peek() {
+            None => Ok(filter),
+            Some(&(pos, ref tok)) => Err(parse_error(pos, format!("Unexpected {:?}", tok))),
+        }
+    }
+
+}
+
+impl Filter<Entry> for EntryFilter {
+
+    fn filter(&self, e: &Entry) -> bool {
+        self.0.filter(e)
+    }
+
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Neq,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    Match,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Regex(String),
+    Integer(i64),
+    Float(f64),
+    Op(Op),
+    Colon,
+    LParen,
+    RParen,
+}
+
+fn parse_error(pos: usize, msg: String) -> FE {
+    FE::from_kind(FEK::FilterParseError(pos, msg))
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '[' || c == ']'
+}
+
+/// Read a string or regex literal, after the opening delimiter
+fn read_delimited(chars: &mut Peekable<CharIndices>, start: usize, delim: char, keep_escapes: bool)
+    -> Result<String>
+{
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            None => return Err(parse_error(start, format!("Missing closing '{}'", delim))),
+            Some((_, c)) if c == delim => return Ok(s),
+            Some((pos, '\\')) => match chars.next() {
+                None => return Err(parse_error(pos, format!("Missing closing '{}'", delim))),
+                Some((_, c)) if c == delim => s.push(c),
+                Some((_, c)) if keep_escapes => {
+                    s.push('\\');
+                    s.push(c);
+                },
+                Some((_, 'n')) => s.push('\n'),
+                Some((_, 't')) => s.push('\t'),
+                Some((_, c))   => s.push(c),
+            },
+            Some((_, c)) => s.push(c),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = vec![];
+    let mut chars  = s.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ':' => Token::Colon,
+            '~' => Token::Op(Op::Match),
+            '"' => Token::Str(try!(read_delimited(&mut chars, pos, '"', false))),
+            '/' => Token::Regex(try!(read_delimited(&mut chars, pos, '/', true))),
+
+            '=' | '!' | '>' | '<' => {
+                let with_eq = next == Some('=');
+                if with_eq {
+                    let _ = chars.next();
+                }
+
+                match (c, with_eq) {
+                    ('=', true)  => Token::Op(Op::Eq),
+                    ('!', true)  => Token::Op(Op::Neq),
+                    ('>', true)  => Token::Op(Op::Ge),
+                    ('>', false) => Token::Op(Op::Gt),
+                    ('<', true)  => Token::Op(Op::Le),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('!', false) => return Err(parse_error(pos, String::from("Use 'not' for negation"))),
+                    _            => return Err(parse_error(pos, String::from("Use '==' for comparison"))),
+                }
+            },
+
+            c if c.is_digit(10) || (c == '-' && next.map(|n| n.is_digit(10)).unwrap_or(false)) => {
+                let mut num = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_digit(10) || c == '.' {
+                        num.push(c);
+                        let _ = chars.next();
+                    } else {
+                        break;
+                    }
+                }
+
+                if num.contains('.') {
+                    match num.parse::<f64>() {
+                        Ok(f)  => Token::Float(f),
+                        Err(_) => return Err(parse_error(pos, format!("Invalid number '{}'", num))),
+                    }
+                } else {
+                    match num.parse::<i64>() {
+                        Ok(i)  => Token::Integer(i),
+                        Err(_) => return Err(parse_error(pos, format!("Invalid number '{}'", num))),
+                    }
+                }
+            },
+
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if is_ident_char(c) {
+                        ident.push(c);
+                        let _ = chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(ident)
+            },
+
+            c => return Err(parse_error(pos, format!("Unexpected character '{}'", c))),
+        };
+
+        tokens.push((pos, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token)> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(t) => {
+                self.pos += 1;
+                Ok(t)
+            },
+            None => Err(parse_error(self.end, String::from("Unexpected end of filter"))),
+        }
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        let is_kw = match self.peek() {
+            Some(&(_, Token::Ident(ref s))) => s == kw,
+            _ => false,
+        };
+
+        if is_kw {
+            self.pos += 1;
+        }
+        is_kw
+    }
+
+    fn parse_or(&mut self) -> Result<EntryFilter> {
+        let mut filter = try!(self.parse_and());
+        while self.eat_keyword("or") {
+            let other = try!(self.parse_and());
+            filter = EntryFilter::new(filter.or(other));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<EntryFilter> {
+        let mut filter = try!(self.parse_unary());
+        while self.eat_keyword("and") {
+            let other = try!(self.parse_unary());
+            filter = EntryFilter::new(filter.and(other));
+        }
+        Ok(filter)
+    }
+
+    fn parse_unary(&mut self) -> Result<EntryFilter> {
+        if self.eat_keyword("not") {
+            return self.parse_unary().map(|f| EntryFilter::new(f.not()));
+        }
+
+        if let Some(&(_, Token::LParen)) = self.peek() {
+            self.pos += 1;
+            let filter = try!(self.parse_or());
+            return match try!(self.next()) {
+                (_, Token::RParen) => Ok(filter),
+                (pos, tok) => Err(parse_error(pos, format!("Expected ')', found {:?}", tok))),
+            };
+        }
+
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<EntryFilter> {
+        let (pos, token) = try!(self.next());
+        let ident = match token {
+            Token::Ident(s) => s,
+            tok => return Err(parse_error(pos, format!("Expected filter, found {:?}", tok))),
+        };
+
+        match &ident[..] {
+            "true"           => Ok(EntryFilter::new(BoolFilter::new(true))),
+            "false"          => Ok(EntryFilter::new(BoolFilter::new(false))),
+            "tag"            => self.parse_tag(),
+            "content"        => self.parse_content_grep(),
+            "content.length" => self.parse_content_length(),
+            s if s.starts_with("header.") && s.len() > "header.".len() => {
+                self.parse_header(String::from(&s["header.".len()..]))
+            },
+            s => Err(parse_error(pos, format!("Expected filter, found '{}'", s))),
+        }
+    }
+
+    fn parse_tag(&mut self) -> Result<EntryFilter> {
+        match try!(self.next()) {
+            (_, Token::Colon) => {},
+            (pos, tok) => return Err(parse_error(pos, format!("Expected ':', found {:?}", tok))),
+        }
+
+        match try!(self.next()) {
+            (pos, Token::Ident(tag)) => is_tag_str(&tag)
+                .map(|_| EntryFilter::new(HasTag::new(tag)))
+                .map_err(|e| parse_error(pos, e)),
+            (pos, tok) => Err(parse_error(pos, format!("Expected tag, found {:?}", tok))),
+        }
+    }
+
+    fn parse_content_grep(&mut self) -> Result<EntryFilter> {
+        match try!(self.next()) {
+            (_, Token::Op(Op::Match)) => {},
+            (pos, tok) => return Err(parse_error(pos, format!("Expected '~', found {:?}", tok))),
+        }
+
+        self.parse_regex().map(|(_, regex)| {
+            EntryFilter::new(ContentGrep::new(regex).unwrap()) // already a valid regex
+        })
+    }
+
+    fn parse_content_length(&mut self) -> Result<EntryFilter> {
+        let op = try!(self.next());
+        let (pos, value) = try!(self.next());
+
+        let len = match value {
+            Token::Integer(i) if i >= 0 => i as usize,
+            tok => return Err(parse_error(pos, format!("Expected length, found {:?}", tok))),
+        };
+
+        match op {
+            (_, Token::Op(Op::Gt)) => Ok(EntryFilter::new(ContentLengthIsOver::new(len))),
+            (_, Token::Op(Op::Lt)) => Ok(EntryFilter::new(ContentLengthIsUnder::new(len))),
+            (pos, tok) => Err(parse_error(pos, format!("Expected '>' or '<', found {:?}", tok))),
+        }
+    }
+
+    fn parse_header(&mut self, path: String) -> Result<EntryFilter> {
+        let (pos, token) = try!(self.next());
+        match token {
+            Token::Ident(ref s) if s == "exists" => Ok(EntryFilter::new(FieldExists::new(path))),
+            Token::Ident(ref s) if s == "is"     => self.parse_header_is(path),
+
+            Token::Op(Op::Match) => {
+                self.parse_regex().map(|(_, regex)| EntryFilter::new(FieldGrep::new(path, regex)))
+            },
+
+            Token::Op(op) => {
+                let value = try!(self.parse_value());
+                match op {
+                    Op::Eq  => Ok(EntryFilter::new(FieldEq::new(path, value))),
+                    Op::Neq => Ok(EntryFilter::new(FieldEq::new(path, value).not())),
+                    Op::Gt  => Ok(EntryFilter::new(FieldGt::new(path, value))),
+                    Op::Lt  => Ok(EntryFilter::new(FieldLt::new(path, value))),
+                    Op::Ge  => {
+                        let eq = FieldEq::new(path.clone(), value.clone());
+                        Ok(EntryFilter::new(FieldGt::new(path, value).or(eq)))
+                    },
+                    Op::Le  => {
+                        let eq = FieldEq::new(path.clone(), value.clone());
+                        Ok(EntryFilter::new(FieldLt::new(path, value).or(eq)))
+                    },
+                    Op::Match => unreachable!(),
+                }
+            },
+
+            tok => Err(parse_error(pos, format!("Expected comparison, found {:?}", tok))),
+        }
+    }
+
+    fn parse_header_is(&mut self, path: String) -> Result<EntryFilter> {
+        let (pos, token) = try!(self.next());
+        let ty = match token {
+            Token::Ident(ref s) => match &s[..] {
+                "empty"             => return Ok(EntryFilter::new(FieldIsEmpty::new(path))),
+                "string"            => Type::String,
+                "integer" | "int"   => Type::Integer,
+                "float"             => Type::Float,
+                "boolean" | "bool"  => Type::Boolean,
+                "array"             => Type::Array,
+                "table"             => Type::Table,
+                s => return Err(parse_error(pos, format!("Unknown type '{}'", s))),
+            },
+            tok => return Err(parse_error(pos, format!("Expected type, found {:?}", tok))),
+        };
+
+        Ok(EntryFilter::new(FieldIsType::new(path, ty)))
+    }
+
+    fn parse_regex(&mut self) -> Result<(usize, Regex)> {
+        let (pos, token) = try!(self.next());
+        let regex = match token {
+            Token::Regex(s) | Token::Str(s) => s,
+            tok => return Err(parse_error(pos, format!("Expected regex, found {:?}", tok))),
+        };
+
+        Regex::new(&regex)
+            .map(|r| (pos, r))
+            .map_err(|e| parse_error(pos, format!("Invalid regex: {}", e)))
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        match try!(self.next()) {
+            (_, Token::Str(s))     => Ok(Value::String(s)),
+            (_, Token::Integer(i)) => Ok(Value::Integer(i)),
+            (_, Token::Float(f))   => Ok(Value::Float(f)),
+            (_, Token::Ident(ref s)) if s == "true"  => Ok(Value::Boolean(true)),
+            (_, Token::Ident(ref s)) if s == "false" => Ok(Value::Boolean(false)),
+            (pos, tok) => Err(parse_error(pos, format!("Expected value, found {:?}", tok))),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use filters::filter::Filter;
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::StoreId;
+
+    use super::EntryFilter;
+
+    fn entry() -> Entry {
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        let mut entry = Entry::new(id);
+        let header : Value = toml_from_str(r#"
+            [imag]
+            version = "0.4.0"
+            tags = [ "work", "urgent" ]
+
+            [todo]
+            status = "pending"
+            priority = 3
+            effort = 1.5
+            done = false
+            notes = ""
+        "#).unwrap();
+        *entry.get_header_mut() = header;
+        *entry.get_content_mut() = String::from("This is a draft");
+        entry
+    }
+
+    fn matches(s: &str) -> bool {
+        EntryFilter::parse(s).unwrap().filter(&entry())
+    }
+
+    #[test]
+    fn test_header_comparisons() {
+        assert!(matches(r#"header.todo.status == "pending""#));
+        assert!(!matches(r#"header.todo.status == "done""#));
+        assert!(matches(r#"header.todo.status != "done""#));
+        assert!(matches("header.todo.priority == 3"));
+        assert!(matches("header.todo.priority > 2"));
+        assert!(!matches("header.todo.priority > 3"));
+        assert!(matches("header.todo.priority >= 3"));
+        assert!(matches("header.todo.priority < 4"));
+        assert!(matches("header.todo.priority <= 3"));
+        assert!(matches("header.todo.effort < 2.0"));
+        assert!(matches("header.todo.done == false"));
+    }
+
+    #[test]
+    fn test_header_predicates() {
+        assert!(matches(r#"header.todo.status ~ /^pend/"#));
+        assert!(!matches(r#"header.todo.status ~ "^done""#));
+        assert!(matches("header.todo.status exists"));
+        assert!(!matches("header.todo.nonexistent exists"));
+        assert!(matches("header.todo.notes is empty"));
+        assert!(matches("header.todo.priority is integer"));
+        assert!(matches("header.imag.tags is array"));
+        assert!(!matches("header.todo is string"));
+    }
+
+    #[test]
+    fn test_content_and_tags() {
+        assert!(matches("content ~ /draft/"));
+        assert!(!matches("content ~ /final/"));
+        assert!(matches("content.length > 5"));
+        assert!(matches("content.length < 100"));
+        assert!(matches("tag:work"));
+        assert!(!matches("tag:home"));
+    }
+
+    #[test]
+    fn test_combination() {
+        assert!(!matches(r#"header.todo.status == "pending" and tag:work and not content ~ /draft/"#));
+        assert!(matches(r#"header.todo.status == "pending" and tag:work and not content ~ /final/"#));
+        assert!(matches("tag:home or tag:urgent"));
+        assert!(matches("false or true and true"));
+        assert!(!matches("(false or true) and false"));
+        assert!(matches("not not true"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let invalid = vec![
+            "",
+            "header.foo",
+            "header.foo = 1",
+            "header.foo == ",
+            r#"header.foo == "unterminated"#,
+            "content ~ /[/",
+            "tag:",
+            "tag:Invalid",
+            "true and",
+            "(true",
+            "true false",
+            "header.foo is something",
+            "! true",
+        ];
+
+        for s in invalid {
+            assert!(EntryFilter::parse(s).is_err(), "'{}' should not parse", s);
+        }
+    }
+
+}
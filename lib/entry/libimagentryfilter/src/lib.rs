@@ -17,6 +17,8 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+#![recursion_limit="256"]
+
 #![deny(
     dead_code,
     non_camel_case_types,
@@ -33,11 +35,13 @@
     while_true,
 )]
 
+extern crate clap;
 extern crate filters;
 extern crate regex;
 extern crate semver;
 extern crate toml;
 extern crate toml_query;
+#[macro_use] extern crate error_chain;
 
 extern crate libimagstore;
 extern crate libimagentrytag;
@@ -47,6 +51,7 @@ extern crate libimagentrytag;
 
 pub mod cli;
 pub mod builtin;
+pub mod error;
 
 // extended functionality of the crate
 // these depend on other internal libraries than libimagstore and use the upper core modules for
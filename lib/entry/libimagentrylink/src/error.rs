@@ -24,6 +24,11 @@ error_chain! {
         LinkError, LinkErrorKind, ResultExt, Result;
     }
 
+    foreign_links {
+        Io(::std::io::Error);
+        Json(::serde_json::Error);
+    }
+
     errors {
         EntryHeaderReadError    {
             description("Error while reading an entry header")
@@ -60,6 +65,11 @@ error_chain! {
             display("Link cannot be parsed: Field type wrong")
         }
 
+        UnknownLinkKind(kind: String) {
+            description("Unknown link kind")
+            display("Unknown link kind: '{}'", kind)
+        }
+
         InternalConversionError {
             description("Error while converting values internally")
             display("Error while converting values internally")
@@ -99,6 +109,16 @@ error_chain! {
             description("Error while talking to the store")
             display("Error while talking to the store")
         }
+
+        LinkIndexReadError {
+            description("Error while reading the link index")
+            display("Error while reading the link index")
+        }
+
+        LinkIndexWriteError {
+            description("Error while writing the link index")
+            display("Error while writing the link index")
+        }
     }
 }
 
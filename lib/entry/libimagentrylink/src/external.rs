@@ -263,11 +263,11 @@ pub mod iter {
                     .map(|id| {
                         debug!("Retrieving entry for id: '{:?}'", id);
                         self.1
-                            .retrieve(id.clone())
+                            .retrieve_copy(id.clone())
                             .chain_err(|| LEK::StoreReadError)
                             .map_dbg_err(|_| format!("Retrieving entry for id: '{:?}' failed", id))
                             .and_then(|f| {
-                                debug!("Store::retrieve({:?}) succeeded", id);
+                                debug!("Store::retrieve_copy({:?}) succeeded", id);
                                 debug!("getting external link from file now");
                                 f.get_link_uri_from_filelockentry()
                                     .map_dbg_err(|e| format!("URL -> Err = {:?}", e))
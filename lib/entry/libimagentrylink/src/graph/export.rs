@@ -0,0 +1,203 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Export a `LinkGraph` for visualization
+//!
+//! The graph can be written in the Graphviz DOT format and in GraphML. As internal links are
+//! two-way links, the exported graphs are undirected. External links are exported as nodes for
+//! the URLs, which are linked with the entries they are attached to.
+//!
+//! The labels of the entry nodes are generated by a function the caller passes, so modules can
+//! label their entries with their titles instead of their ids.
+
+use std::fmt::Write;
+
+use libimagstore::storeid::StoreId;
+
+use graph::LinkGraph;
+
+/// Write the graph in the Graphviz DOT format
+pub fn to_dot<F>(graph: &LinkGraph, label: F) -> String
+    where F: Fn(&StoreId) -> String
+{
+    let mut out = String::from("graph imag {\n");
+
+    for id in graph.ids() {
+        let _ = writeln!(out, "    {} [label={}];", dot_quote(&id.to_string()), dot_quote(&label(id)));
+    }
+
+    for url in urls(graph) {
+        let _ = writeln!(out, "    {} [label={}, shape=box, URL={}];",
+                         dot_quote(url), dot_quote(url), dot_quote(url));
+    }
+
+    for (a, b) in edges(graph) {
+        let _ = writeln!(out, "    {} -- {};", dot_quote(&a.to_string()), dot_quote(&b.to_string()));
+    }
+
+    for (id, url) in external_edges(graph) {
+        let _ = writeln!(out, "    {} -- {};", dot_quote(&id.to_string()), dot_quote(url));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Write the graph in the GraphML format
+///
+/// Each node has a `label` and a `kind`, which is either "entry" or "url".
+pub fn to_graphml<F>(graph: &LinkGraph, label: F) -> String
+    where F: Fn(&StoreId) -> String
+{
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    out.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+    out.push_str("  <graph id=\"imag\" edgedefault=\"undirected\">\n");
+
+    for id in graph.ids() {
+        graphml_node(&mut out, &id.to_string(), &label(id), "entry");
+    }
+
+    for url in urls(graph) {
+        graphml_node(&mut out, url, url, "url");
+    }
+
+    for (a, b) in edges(graph) {
+        graphml_edge(&mut out, &a.to_string(), &b.to_string());
+    }
+
+    for (id, url) in external_edges(graph) {
+        graphml_edge(&mut out, &id.to_string(), url);
+    }
+
+    out.push_str("  </graph>\n");
+    out.push_str("</graphml>\n");
+    out
+}
+
+/// All links, each one only once
+fn edges(graph: &LinkGraph) -> Vec<(&StoreId, &StoreId)> {
+    let mut edges = vec![];
+    for id in graph.ids() {
+        for neighbor in graph.neighbors(id) {
+            if id < neighbor {
+                edges.push((id, neighbor));
+            }
+        }
+    }
+    edges
+}
+
+/// All external links, as pairs of entry and URL
+fn external_edges(graph: &LinkGraph) -> Vec<(&StoreId, &String)> {
+    graph.ids()
+        .into_iter()
+        .flat_map(|id| graph.external_links(id).into_iter().map(move |url| (id, url)))
+        .collect()
+}
+
+/// All URLs in the graph, each one only once
+fn urls(graph: &LinkGraph) -> Vec<&String> {
+    let mut urls = external_edges(graph).into_iter().map(|(_, url)| url).collect::<Vec<_>>();
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace("\\", "\\\\").replace("\"", "\\\""))
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&apos;")
+}
+
+fn graphml_node(out: &mut String, id: &str, label: &str, kind: &str) {
+    let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(id));
+    let _ = writeln!(out, "      <data key=\"label\">{}</data>", xml_escape(label));
+    let _ = writeln!(out, "      <data key=\"kind\">{}</data>", kind);
+    let _ = writeln!(out, "    </node>");
+}
+
+fn graphml_edge(out: &mut String, source: &str, target: &str) {
+    let _ = writeln!(out, "    <edge source=\"{}\" target=\"{}\"/>", xml_escape(source), xml_escape(target));
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::storeid::StoreId;
+
+    use super::{to_dot, to_graphml};
+    use graph::LinkGraph;
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    fn get_graph() -> LinkGraph {
+        let mut graph = LinkGraph::new();
+        graph.add_entry(id("notes/a"), vec![id("notes/b")]);
+        graph.add_entry(id("notes/b"), vec![id("notes/a")]);
+        graph.add_external_links(id("notes/a"), vec![String::from("https://imag-pim.org/?a&b")]);
+        graph
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = to_dot(&get_graph(), |id| format!("Note {}", id));
+
+        assert!(dot.starts_with("graph imag {\n"));
+        assert!(dot.contains("    \"notes/a\" [label=\"Note notes/a\"];\n"));
+        assert!(dot.contains("    \"notes/a\" -- \"notes/b\";\n"));
+        assert!(!dot.contains("    \"notes/b\" -- \"notes/a\";\n"));
+        assert!(dot.contains("    \"notes/a\" -- \"https://imag-pim.org/?a&b\";\n"));
+        assert!(dot.contains("shape=box"));
+    }
+
+    #[test]
+    fn test_dot_quoting() {
+        let mut graph = LinkGraph::new();
+        graph.add_entry(id("a"), vec![]);
+
+        let dot = to_dot(&graph, |_| String::from("say \"hi\""));
+        assert!(dot.contains("[label=\"say \\\"hi\\\"\"]"));
+    }
+
+    #[test]
+    fn test_graphml() {
+        let graphml = to_graphml(&get_graph(), |id| format!("Note {}", id));
+
+        assert!(graphml.contains("<graph id=\"imag\" edgedefault=\"undirected\">"));
+        assert!(graphml.contains("<node id=\"notes/a\">"));
+        assert!(graphml.contains("<data key=\"label\">Note notes/a</data>"));
+        assert!(graphml.contains("<node id=\"https://imag-pim.org/?a&amp;b\">"));
+        assert!(graphml.contains("<data key=\"kind\">url</data>"));
+        assert!(graphml.contains("<edge source=\"notes/a\" target=\"notes/b\"/>"));
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+    }
+
+}
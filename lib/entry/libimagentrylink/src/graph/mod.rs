@@ -0,0 +1,553 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Queries over the network of internal links
+//!
+//! Internal links are stored in the headers of both linked entries, so finding all entries an
+//! entry is linked with only requires the entry itself. Questions like "which entries of the diary
+//! link to this note" or "which entries are at most two links away" require the links of all
+//! entries in the store though.
+//!
+//! A `LinkGraph` holds the links of all entries in the store and answers these questions. Loading
+//! it means reading every entry of the store, which can be avoided with the cached link index:
+//! `LinkGraph::load_cached()` keeps the links of all entries in a file and only reads the entries
+//! which were changed since the index was written.
+//!
+//! External links are not part of the graph by default, they can be added with
+//! `LinkGraph::load_external_links()`.
+//!
+//! The `export` module writes the graph in formats other tools understand.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions, rename};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagstore::trash::TRASH_COLLECTION;
+
+use error::LinkError as LE;
+use error::LinkErrorKind as LEK;
+use error::Result;
+use error::ResultExt;
+use external::ExternalLinker;
+use internal::InternalLinker;
+
+pub mod export;
+
+/// The graph of internal links between the entries of a store
+///
+/// All ids in the graph are without base.
+#[derive(Debug, Default, Clone)]
+pub struct LinkGraph {
+    outgoing: BTreeMap<StoreId, BTreeSet<StoreId>>,
+    incoming: BTreeMap<StoreId, BTreeSet<StoreId>>,
+    external: BTreeMap<StoreId, BTreeSet<String>>,
+}
+
+impl LinkGraph {
+
+    pub fn new() -> LinkGraph {
+        LinkGraph::default()
+    }
+
+    /// Load the graph by reading all entries of the store
+    pub fn load(store: &Store) -> Result<LinkGraph> {
+        let mut graph = LinkGraph::new();
+        for id in try!(entry_ids(store)) {
+            let links = try!(read_links(store, id.clone()));
+            graph.add_entry(id, links);
+        }
+        Ok(graph)
+    }
+
+    /// Load the graph with the help of the link index at `path`
+    ///
+    /// Only entries which were changed since the index was written (or which are not in the index
+    /// at all) are read from the store. The updated index is written back to `path` afterwards.
+    pub fn load_cached(store: &Store, path: &Path) -> Result<LinkGraph> {
+        let mut index = try!(LinkIndex::load(path));
+        let mut graph = LinkGraph::new();
+        let mut seen  = BTreeSet::new();
+
+        for id in try!(entry_ids(store)) {
+            let key      = try!(id.to_str().chain_err(|| LEK::StoreIdError));
+            let modified = try!(store.file_stamp(id.clone()).chain_err(|| LEK::StoreReadError))
+                .map(|stamp| (stamp.len(), stamp.version()));
+
+            let cached = match (index.entries.get(&key), modified) {
+                (Some(e), Some(m)) if e.modified == m => Some(e.links.clone()),
+                _ => None,
+            };
+
+            let links = match cached {
+                Some(links) => try!(links
+                    .into_iter()
+                    .map(|l| StoreId::new_baseless(PathBuf::from(l)).chain_err(|| LEK::StoreIdError))
+                    .collect::<Result<Vec<StoreId>>>()),
+                None => {
+                    debug!("Reading links of '{}' from the store", id);
+                    let links = try!(read_links(store, id.clone()));
+
+                    if let Some(modified) = modified {
+                        let strs = try!(links
+                            .iter()
+                            .map(|l| l.to_str().chain_err(|| LEK::StoreIdError))
+                            .collect::<Result<Vec<String>>>());
+                        index.entries.insert(key.clone(), IndexedEntry {
+                            modified: modified,
+                            links: strs,
+                        });
+                    }
+
+                    links
+                },
+            };
+
+            seen.insert(key);
+            graph.add_entry(id, links);
+        }
+
+        index.entries = index.entries.into_iter().filter(|&(ref k, _)| seen.contains(k)).collect();
+        try!(index.save(path));
+        Ok(graph)
+    }
+
+    /// Get the path of the link index for the runtime path `rtp`
+    pub fn index_path_for_rtp(rtp: &Path) -> PathBuf {
+        rtp.join("link-index.json")
+    }
+
+    /// Add an entry and its (outgoing) links to the graph
+    ///
+    /// The targets of the links are added to the graph as well, even if they do not exist.
+    pub fn add_entry(&mut self, id: StoreId, links: Vec<StoreId>) {
+        let id = id.without_base();
+        self.incoming.entry(id.clone()).or_insert_with(BTreeSet::new);
+
+        let mut targets = BTreeSet::new();
+        for link in links.into_iter().map(StoreId::without_base) {
+            self.incoming.entry(link.clone()).or_insert_with(BTreeSet::new).insert(id.clone());
+            self.outgoing.entry(link.clone()).or_insert_with(BTreeSet::new);
+            targets.insert(link);
+        }
+
+        self.outgoing.entry(id).or_insert_with(BTreeSet::new).extend(targets);
+    }
+
+    /// Add the external links (URLs) of an entry to the graph
+    pub fn add_external_links(&mut self, id: StoreId, urls: Vec<String>) {
+        let id = id.without_base();
+        self.outgoing.entry(id.clone()).or_insert_with(BTreeSet::new);
+        self.incoming.entry(id.clone()).or_insert_with(BTreeSet::new);
+        self.external.entry(id).or_insert_with(BTreeSet::new).extend(urls);
+    }
+
+    /// Read the external links of all entries in the graph from the store
+    pub fn load_external_links(&mut self, store: &Store) -> Result<()> {
+        let ids = self.outgoing.keys().cloned().collect::<Vec<StoreId>>();
+
+        for id in ids {
+            // A copy is read, so the entry is not written back. Link targets which do not exist
+            // are empty and have no links.
+            let entry = try!(store.retrieve_copy(id.clone()).chain_err(|| LEK::StoreReadError));
+
+            let urls = try!(try!(entry.get_external_links(store))
+                .map(|url| url.map(|url| url.into_string()))
+                .collect::<Result<Vec<String>>>());
+
+            if !urls.is_empty() {
+                self.add_external_links(id, urls);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The subgraph of the entries for which `pred` returns true
+    ///
+    /// Links to entries which are not in the subgraph are left out.
+    pub fn filtered<F>(&self, pred: F) -> LinkGraph
+        where F: Fn(&StoreId) -> bool
+    {
+        let mut graph = LinkGraph::new();
+
+        for (id, targets) in self.outgoing.iter().filter(|&(id, _)| pred(id)) {
+            let targets = targets.iter().filter(|t| pred(t)).cloned().collect();
+            graph.add_entry(id.clone(), targets);
+
+            if let Some(urls) = self.external.get(id) {
+                graph.add_external_links(id.clone(), urls.iter().cloned().collect());
+            }
+        }
+
+        graph
+    }
+
+    /// All ids in the graph
+    pub fn ids(&self) -> Vec<&StoreId> {
+        self.outgoing.keys().collect()
+    }
+
+    /// Check whether `id` is in the graph
+    pub fn contains(&self, id: &StoreId) -> bool {
+        self.outgoing.contains_key(id)
+    }
+
+    /// The ids `id` links to
+    pub fn outgoing(&self, id: &StoreId) -> Vec<&StoreId> {
+        self.outgoing.get(id).map(|s| s.iter().collect()).unwrap_or_default()
+    }
+
+    /// The ids which link to `id`
+    pub fn incoming(&self, id: &StoreId) -> Vec<&StoreId> {
+        self.incoming.get(id).map(|s| s.iter().collect()).unwrap_or_default()
+    }
+
+    /// The external links (URLs) of `id`
+    pub fn external_links(&self, id: &StoreId) -> Vec<&String> {
+        self.external.get(id).map(|s| s.iter().collect()).unwrap_or_default()
+    }
+
+    /// The ids which link to `id` or are linked from `id`
+    ///
+    /// As links are always two-way links, this is the same as `outgoing()` for a consistent
+    /// store.
+    pub fn neighbors(&self, id: &StoreId) -> Vec<&StoreId> {
+        let mut n = self.outgoing(id);
+        n.extend(self.incoming(id));
+        n.sort();
+        n.dedup();
+        n
+    }
+
+    /// All ids which are at most `hops` links away from `id`, with their distance to `id`
+    ///
+    /// `id` itself is not part of the result. The result is ordered by distance.
+    pub fn within_hops<'a>(&'a self, id: &'a StoreId, hops: usize) -> Vec<(&'a StoreId, usize)> {
+        let mut result  = vec![];
+        let mut visited = BTreeSet::new();
+        let mut queue   = VecDeque::new();
+
+        if self.contains(id) {
+            visited.insert(id);
+            queue.push_back((id, 0));
+        }
+
+        while let Some((current, distance)) = queue.pop_front() {
+            if distance >= hops {
+                continue;
+            }
+
+            for next in self.neighbors(current) {
+                if visited.insert(next) {
+                    result.push((next, distance + 1));
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// The shortest path of links from `from` to `to`, including both of them
+    ///
+    /// Returns `None` if there is no such path.
+    pub fn shortest_path<'a>(&'a self, from: &'a StoreId, to: &'a StoreId) -> Option<Vec<&'a StoreId>> {
+        let mut predecessors : BTreeMap<&StoreId, &StoreId> = BTreeMap::new();
+        let mut queue = VecDeque::new();
+
+        if !self.contains(from) {
+            return None;
+        }
+        queue.push_back(from);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![current];
+                let mut node = current;
+                while let Some(prev) = predecessors.get(node) {
+                    path.push(prev);
+                    node = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for next in self.neighbors(current) {
+                if next != from && !predecessors.contains_key(next) {
+                    predecessors.insert(next, current);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The connected components of the graph
+    ///
+    /// The components are ordered by size, biggest first.
+    pub fn connected_components(&self) -> Vec<Vec<&StoreId>> {
+        let mut visited    = BTreeSet::new();
+        let mut components = vec![];
+
+        for id in self.outgoing.keys() {
+            if visited.contains(id) {
+                continue;
+            }
+
+            let mut component = vec![id];
+            visited.insert(id);
+            component.extend(self.within_hops(id, usize::max_value()).into_iter().map(|(id, _)| id));
+            for &member in component.iter() {
+                visited.insert(member);
+            }
+
+            component.sort();
+            components.push(component);
+        }
+
+        components.sort_by(|a, b| b.len().cmp(&a.len()));
+        components
+    }
+
+}
+
+/// The ids of all entries in the store which take part in linking
+///
+/// Hidden files, external links and trashed entries are left out.
+fn entry_ids(store: &Store) -> Result<Vec<StoreId>> {
+    stored_ids(store).map(|ids| ids.into_iter().filter(|id| !is_external(id)).collect())
+}
+
+/// The (baseless) ids of all entries in the store which can have links, including the entries for
+/// external links
+///
+/// Hidden files and trashed entries are left out.
+pub fn stored_ids(store: &Store) -> Result<Vec<StoreId>> {
+    let ids = try!(store.entries().chain_err(|| LEK::StoreReadError));
+
+    Ok(ids
+        .map(StoreId::without_base)
+        .filter(|id| !id.is_hidden() && !id.is_in_collection(&[TRASH_COLLECTION]))
+        .filter(|id| {
+            // The filesystem backend lists directories as well
+            id.clone()
+                .with_base(store.path().clone())
+                .into_pathbuf()
+                .map(|p| !p.is_dir())
+                .unwrap_or(false)
+        })
+        .collect())
+}
+
+/// Read the internal links of an entry, without external links
+fn read_links(store: &Store, id: StoreId) -> Result<Vec<StoreId>> {
+    let entry = try!(store.retrieve_copy(id).chain_err(|| LEK::StoreReadError));
+    let links = try!(entry.get_internal_links());
+
+    Ok(links
+        .map(|link| link.get_store_id().clone().without_base())
+        .filter(|id| !is_external(id))
+        .collect())
+}
+
+fn is_external(id: &StoreId) -> bool {
+    id.is_in_collection(&["links", "external"])
+}
+
+/// The on-disk format of the link index
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LinkIndex {
+    entries: BTreeMap<String, IndexedEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedEntry {
+    /// The stamp of the entry file in the store backend (length, version)
+    modified: (u64, u64),
+    links: Vec<String>,
+}
+
+impl LinkIndex {
+
+    fn load(path: &Path) -> Result<LinkIndex> {
+        if !path.exists() {
+            debug!("No link index at {:?}", path);
+            return Ok(LinkIndex::default());
+        }
+
+        let file = try!(File::open(path).chain_err(|| LEK::LinkIndexReadError));
+
+        // The index is only a cache, so a broken index is rebuilt
+        serde_json::from_reader(BufReader::new(file))
+            .or_else(|e| {
+                warn!("Ignoring broken link index at {:?}: {}", path, e);
+                Ok(LinkIndex::default())
+            })
+    }
+
+    /// Write the index to a temporary file first, which is then moved in place
+    fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+
+        let write_index = || -> Result<()> {
+            let file       = try!(OpenOptions::new().write(true).create(true).truncate(true).open(&tmp));
+            let mut writer = BufWriter::new(file);
+            try!(serde_json::to_writer(&mut writer, self));
+
+            // The index must be completely on disk before it replaces the old one
+            try!(writer.flush());
+            try!(writer.get_ref().sync_all());
+            rename(&tmp, path).map_err(LE::from)
+        };
+
+        write_index().chain_err(|| LEK::LinkIndexWriteError)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use super::LinkGraph;
+    use internal::InternalLinker;
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    /// a - b - c - d, e - f, g
+    fn get_graph() -> LinkGraph {
+        let mut graph = LinkGraph::new();
+        graph.add_entry(id("a"), vec![id("b")]);
+        graph.add_entry(id("b"), vec![id("a"), id("c")]);
+        graph.add_entry(id("c"), vec![id("b"), id("d")]);
+        graph.add_entry(id("d"), vec![id("c")]);
+        graph.add_entry(id("e"), vec![id("f")]);
+        graph.add_entry(id("f"), vec![id("e")]);
+        graph.add_entry(id("g"), vec![]);
+        graph
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let graph = get_graph();
+        assert_eq!(graph.neighbors(&id("b")), vec![&id("a"), &id("c")]);
+        assert!(graph.neighbors(&id("g")).is_empty());
+        assert!(graph.neighbors(&id("unknown")).is_empty());
+    }
+
+    #[test]
+    fn test_incoming_of_one_directional_link() {
+        let mut graph = LinkGraph::new();
+        graph.add_entry(id("a"), vec![id("b")]);
+
+        assert_eq!(graph.incoming(&id("b")), vec![&id("a")]);
+        assert!(graph.outgoing(&id("b")).is_empty());
+        assert_eq!(graph.neighbors(&id("b")), vec![&id("a")]);
+    }
+
+    #[test]
+    fn test_within_hops() {
+        let graph = get_graph();
+        assert_eq!(graph.within_hops(&id("a"), 1), vec![(&id("b"), 1)]);
+        assert_eq!(graph.within_hops(&id("a"), 2), vec![(&id("b"), 1), (&id("c"), 2)]);
+        assert_eq!(graph.within_hops(&id("a"), 10).len(), 3);
+        assert!(graph.within_hops(&id("a"), 0).is_empty());
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let graph = get_graph();
+        assert_eq!(graph.shortest_path(&id("a"), &id("d")),
+                   Some(vec![&id("a"), &id("b"), &id("c"), &id("d")]));
+        assert_eq!(graph.shortest_path(&id("a"), &id("a")), Some(vec![&id("a")]));
+        assert_eq!(graph.shortest_path(&id("a"), &id("e")), None);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let graph = get_graph();
+        let components = graph.connected_components();
+
+        assert_eq!(components, vec![
+            vec![&id("a"), &id("b"), &id("c"), &id("d")],
+            vec![&id("e"), &id("f")],
+            vec![&id("g")],
+        ]);
+    }
+
+    #[test]
+    fn test_filtered() {
+        let graph    = get_graph();
+        let filtered = graph.filtered(|id| id != &self::id("b"));
+
+        assert!(!filtered.contains(&id("b")));
+        assert!(filtered.neighbors(&id("a")).is_empty());
+        assert_eq!(filtered.neighbors(&id("c")), vec![&id("d")]);
+        assert_eq!(filtered.ids().len(), 6);
+    }
+
+    #[test]
+    fn test_load_and_load_cached() {
+        use tempdir::TempDir;
+
+        let dir   = TempDir::new("imag-link-graph").unwrap();
+        ::std::fs::create_dir(dir.path().join("store")).unwrap();
+        let store = Store::new(dir.path().join("store"), None).unwrap();
+        let index = LinkGraph::index_path_for_rtp(dir.path());
+
+        {
+            let mut a = store.create(PathBuf::from("a")).unwrap();
+            let mut b = store.create(PathBuf::from("b")).unwrap();
+            let _     = store.create(PathBuf::from("c")).unwrap();
+            a.add_internal_link(&mut b).unwrap();
+        }
+
+        let graph = LinkGraph::load(&store).unwrap();
+        assert_eq!(graph.ids().len(), 3);
+        assert_eq!(graph.neighbors(&id("a")), vec![&id("b")]);
+
+        let cached = LinkGraph::load_cached(&store, &index).unwrap();
+        assert!(index.exists());
+        assert_eq!(cached.neighbors(&id("a")), vec![&id("b")]);
+
+        {
+            let mut b = store.retrieve(PathBuf::from("b")).unwrap();
+            let mut c = store.retrieve(PathBuf::from("c")).unwrap();
+            b.add_internal_link(&mut c).unwrap();
+        }
+
+        let cached = LinkGraph::load_cached(&store, &index).unwrap();
+        assert_eq!(cached.neighbors(&id("b")), vec![&id("a"), &id("c")]);
+        assert_eq!(cached.neighbors(&id("c")), vec![&id("b")]);
+    }
+
+}
@@ -18,13 +18,17 @@
 //
 
 use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::str::FromStr;
 #[cfg(test)]
 use std::path::PathBuf;
 
 use libimagstore::storeid::StoreId;
 use libimagstore::storeid::IntoStoreId;
 use libimagstore::store::Entry;
+use libimagstore::store::FileLockEntry;
 use libimagstore::store::Result as StoreResult;
+use libimagstore::store::Store;
 
 use toml_query::read::TomlValueReadExt;
 use toml_query::set::TomlValueSetExt;
@@ -42,6 +46,82 @@ use toml::Value;
 pub enum Link {
     Id          { link: StoreId },
     Annotated   { link: StoreId, annotation: String },
+    Typed       { link: StoreId, kind: LinkKind },
+}
+
+/// The relation a typed link expresses, seen from the entry which holds the link
+///
+/// Links are always bidirectional, so the linked entry holds a link of the inverse kind, for
+/// example an entry which `blocks` another entry is `blocked-by` from the view of the other one.
+#[derive(Eq, PartialEq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub enum LinkKind {
+    Blocks,
+    BlockedBy,
+    PartOf,
+    HasPart,
+    References,
+    ReferencedBy,
+    FollowUp,
+    FollowedUpBy,
+}
+
+impl LinkKind {
+
+    /// Get the kind of the link which points back from the linked entry
+    pub fn inverse(&self) -> LinkKind {
+        match *self {
+            LinkKind::Blocks       => LinkKind::BlockedBy,
+            LinkKind::BlockedBy    => LinkKind::Blocks,
+            LinkKind::PartOf       => LinkKind::HasPart,
+            LinkKind::HasPart      => LinkKind::PartOf,
+            LinkKind::References   => LinkKind::ReferencedBy,
+            LinkKind::ReferencedBy => LinkKind::References,
+            LinkKind::FollowUp     => LinkKind::FollowedUpBy,
+            LinkKind::FollowedUpBy => LinkKind::FollowUp,
+        }
+    }
+
+    /// The name of the kind, as it is stored in the header
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LinkKind::Blocks       => "blocks",
+            LinkKind::BlockedBy    => "blocked-by",
+            LinkKind::PartOf       => "part-of",
+            LinkKind::HasPart      => "has-part",
+            LinkKind::References   => "references",
+            LinkKind::ReferencedBy => "referenced-by",
+            LinkKind::FollowUp     => "follow-up",
+            LinkKind::FollowedUpBy => "followed-up-by",
+        }
+    }
+
+}
+
+impl Display for LinkKind {
+
+    fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        write!(fmt, "{}", self.as_str())
+    }
+
+}
+
+impl FromStr for LinkKind {
+    type Err = LE;
+
+    fn from_str(s: &str) -> Result<LinkKind> {
+        match s {
+            "blocks"         => Ok(LinkKind::Blocks),
+            "blocked-by"     => Ok(LinkKind::BlockedBy),
+            "part-of"        => Ok(LinkKind::PartOf),
+            "has-part"       => Ok(LinkKind::HasPart),
+            "references"     => Ok(LinkKind::References),
+            "referenced-by"  => Ok(LinkKind::ReferencedBy),
+            "follow-up"      => Ok(LinkKind::FollowUp),
+            "followed-up-by" => Ok(LinkKind::FollowedUpBy),
+            _                => Err(LE::from_kind(LEK::UnknownLinkKind(String::from(s)))),
+        }
+    }
+
 }
 
 impl Link {
@@ -50,6 +130,7 @@ impl Link {
         match *self {
             Link::Id { ref link }             => link.exists(),
             Link::Annotated { ref link, .. }  => link.exists(),
+            Link::Typed { ref link, .. }      => link.exists(),
         }
         .chain_err(|| LEK::StoreIdError)
     }
@@ -58,6 +139,7 @@ impl Link {
         match *self {
             Link::Id { ref link }             => link.to_str(),
             Link::Annotated { ref link, .. }  => link.to_str(),
+            Link::Typed { ref link, .. }      => link.to_str(),
         }
         .chain_err(|| LEK::StoreReadError)
     }
@@ -67,6 +149,7 @@ impl Link {
         match self {
             &Link::Id { link: ref s }             => s.eq(id),
             &Link::Annotated { link: ref s, .. }  => s.eq(id),
+            &Link::Typed { link: ref s, .. }      => s.eq(id),
         }
     }
 
@@ -75,6 +158,24 @@ impl Link {
         match self {
             &Link::Id { link: ref s }             => s,
             &Link::Annotated { link: ref s, .. }  => s,
+            &Link::Typed { link: ref s, .. }      => s,
+        }
+    }
+
+    /// Get the kind of the Link, if it is a typed link
+    pub fn kind(&self) -> Option<LinkKind> {
+        match *self {
+            Link::Typed { kind, .. } => Some(kind),
+            _                        => None,
+        }
+    }
+
+    /// Get a Link of the same kind to another StoreId
+    fn with_store_id(self, id: StoreId) -> Link {
+        match self {
+            Link::Id { .. } => Link::Id { link: id },
+            Link::Annotated { annotation, .. } => Link::Annotated { link: id, annotation: annotation },
+            Link::Typed { kind, .. } => Link::Typed { link: id, kind: kind },
         }
     }
 
@@ -84,6 +185,8 @@ impl Link {
             Link::Id { link: s } => Link::Id { link: s.without_base() },
             Link::Annotated { link: s, annotation: ann } =>
                 Link::Annotated { link: s.without_base(), annotation: ann },
+            Link::Typed { link: s, kind } =>
+                Link::Typed { link: s.without_base(), kind: kind },
         }
     }
 
@@ -94,6 +197,8 @@ impl Link {
             Link::Id { link: s } => Link::Id { link: s.with_base(pb) },
             Link::Annotated { link: s, annotation: ann } =>
                 Link::Annotated { link: s.with_base(pb), annotation: ann },
+            Link::Typed { link: s, kind } =>
+                Link::Typed { link: s.with_base(pb), kind: kind },
         }
     }
 
@@ -112,6 +217,18 @@ impl Link {
                         tab.insert("annotation".to_owned(), Value::String(anno.clone()));
                         Value::Table(tab)
                     })
+            },
+            &Link::Typed { ref link, ref kind } => {
+                link.to_str()
+                    .map(Value::String)
+                    .chain_err(|| LEK::InternalConversionError)
+                    .map(|link| {
+                        let mut tab = BTreeMap::new();
+
+                        tab.insert("link".to_owned(), link);
+                        tab.insert("kind".to_owned(), Value::String(String::from(kind.as_str())));
+                        Value::Table(tab)
+                    })
             }
         }
     }
@@ -125,6 +242,9 @@ impl ::std::cmp::PartialEq for Link {
             (&Link::Annotated { link: ref a, annotation: ref ann1 },
              &Link::Annotated { link: ref b, annotation: ref ann2 }) =>
                 (a, ann1).eq(&(b, ann2)),
+            (&Link::Typed { link: ref a, kind: ref k1 },
+             &Link::Typed { link: ref b, kind: ref k2 }) =>
+                (a, k1).eq(&(b, k2)),
             _ => false,
         }
     }
@@ -142,6 +262,7 @@ impl Into<StoreId> for Link {
         match self {
             Link::Id { link }            => link,
             Link::Annotated { link, .. } => link,
+            Link::Typed { link, .. }     => link,
         }
     }
 }
@@ -151,6 +272,7 @@ impl IntoStoreId for Link {
         match self {
             Link::Id { link }            => Ok(link),
             Link::Annotated { link, .. } => Ok(link),
+            Link::Typed { link, .. }     => Ok(link),
         }
     }
 }
@@ -160,31 +282,59 @@ impl AsRef<StoreId> for Link {
         match self {
             &Link::Id { ref link }            => &link,
             &Link::Annotated { ref link, .. } => &link,
+            &Link::Typed { ref link, .. }     => &link,
         }
     }
 }
 
+/// Internal links of an entry
+///
+/// Links are always written to both entries. The functions of this trait only change the headers
+/// of the entries, each entry is written when its `FileLockEntry` is dropped. If writing one of
+/// the entries fails, the link is one-sided. `add_internal_links_transactional()`,
+/// `add_internal_links_of_kind_transactional()`, `remove_internal_links_transactional()` and
+/// `set_internal_links_transactional()` write all entries in one transaction instead, so either
+/// all of them or none of them is written.
 pub trait InternalLinker {
 
     /// Get the internal links from the implementor object
     fn get_internal_links(&self) -> Result<LinkIter>;
 
     /// Set the internal links for the implementor object
+    ///
+    /// See `set_internal_links_transactional()` for writing all entries in one transaction.
     fn set_internal_links(&mut self, links: Vec<&mut Entry>) -> Result<LinkIter>;
 
     /// Add an internal link to the implementor object
+    ///
+    /// See `add_internal_links_transactional()` for writing both entries in one transaction.
     fn add_internal_link(&mut self, link: &mut Entry) -> Result<()>;
 
     /// Remove an internal link from the implementor object
+    ///
+    /// See `remove_internal_links_transactional()` for writing both entries in one transaction.
     fn remove_internal_link(&mut self, link: &mut Entry) -> Result<()>;
 
     /// Add internal annotated link
     fn add_internal_annotated_link(&mut self, link: &mut Entry, annotation: String) -> Result<()>;
+
+    /// Add an internal link of a kind to the implementor object
+    ///
+    /// The linked entry gets a link of the inverse kind. An existing untyped or typed link between
+    /// the two entries is replaced.
+    ///
+    /// See `add_internal_links_of_kind_transactional()` for writing both entries in one
+    /// transaction.
+    fn add_internal_link_of_kind(&mut self, link: &mut Entry, kind: LinkKind) -> Result<()>;
+
+    /// Get the internal links of a kind from the implementor object
+    fn get_internal_links_of_kind(&self, kind: LinkKind) -> Result<LinkIter>;
 }
 
 pub mod iter {
     use std::vec::IntoIter;
     use super::Link;
+    use super::LinkKind;
 
     use error::LinkErrorKind as LEK;
     use error::ResultExt;
@@ -279,6 +429,15 @@ pub mod iter {
             FilterLinksIter::new(self, f)
         }
 
+        /// Turn this iterator into a FilterLinksIter that removes all entries which have no link
+        /// of the kind `kind`
+        ///
+        /// This does _not_ remove the entries from the store.
+        pub fn with_links_of_kind(self, kind: LinkKind) -> FilterLinksIter<'a> {
+            let f = move |links: &[Link]| links.iter().any(|l| l.kind() == Some(kind));
+            FilterLinksIter::new(self, Box::new(f))
+        }
+
         pub fn store(&self) -> &Store {
             self.1
         }
@@ -406,7 +565,7 @@ impl InternalLinker for Entry {
         let mut new_links = vec![];
 
         for link in links {
-            if let Err(e) = add_foreign_link(link, self_location.clone()) {
+            if let Err(e) = add_foreign_link(link, self_location.clone().into()) {
                 return Err(e);
             }
             new_links.push(link.get_location().clone().into());
@@ -426,7 +585,7 @@ impl InternalLinker for Entry {
                             }));
         let res = self
             .get_header_mut()
-            .set("imag.links", Value::Array(new_links))
+            .set("imag.links", links_array(new_links))
             .chain_err(|| LEK::EntryHeaderReadError);
         process_rw_result(res)
     }
@@ -467,21 +626,62 @@ impl InternalLinker for Entry {
         add_internal_link_with_instance(self, link, new_link)
     }
 
+    fn add_internal_link_of_kind(&mut self, link: &mut Entry, kind: LinkKind) -> Result<()> {
+        let new_link = Link::Typed {
+            link: link.get_location().clone(),
+            kind: kind,
+        };
+
+        add_internal_link_with_instance(self, link, new_link)
+    }
+
+    fn get_internal_links_of_kind(&self, kind: LinkKind) -> Result<LinkIter> {
+        self.get_internal_links()
+            .map(|links| LinkIter::new(links.filter(|l| l.kind() == Some(kind)).collect()))
+    }
+
 }
 
 fn add_internal_link_with_instance(this: &mut Entry, link: &mut Entry, instance: Link) -> Result<()> {
     debug!("Adding internal link from {:?} to {:?}", this.get_location(), instance);
 
-    add_foreign_link(link, this.get_location().clone())
+    let back_link = match instance.kind() {
+        Some(kind) => Link::Typed { link: this.get_location().clone(), kind: kind.inverse() },
+        None       => this.get_location().clone().into(),
+    };
+
+    add_foreign_link(link, back_link)
         .and_then(|_| {
             this.get_internal_links()
                 .and_then(|links| {
-                    let links = links.chain(LinkIter::new(vec![instance]));
-                    rewrite_links(this.get_header_mut(), links)
+                    let links = with_link(links, instance);
+                    rewrite_links(this.get_header_mut(), links.into_iter())
                 })
         })
 }
 
+/// Add `new` to `links`
+///
+/// A typed link replaces all untyped and typed links to the same entry, an untyped link is not
+/// added if there is one of these already. Annotated links are left alone.
+fn with_link<I: Iterator<Item = Link>>(links: I, new: Link) -> Vec<Link> {
+    let new_id = new.get_store_id().clone().without_base();
+    let replaces = |l: &Link| match *l {
+        Link::Annotated { .. } => false,
+        _                      => l.eq_store_id(&new_id),
+    };
+
+    let mut links = links.collect::<Vec<_>>();
+    match new.kind() {
+        Some(_) => links.retain(|l| !replaces(l)),
+        None    => if links.iter().any(|l| replaces(l)) {
+            return links;
+        },
+    }
+    links.push(new);
+    links
+}
+
 fn rewrite_links<I: Iterator<Item = Link>>(header: &mut Value, links: I) -> Result<()> {
     let links = try!(links.into_values()
                      .into_iter()
@@ -497,19 +697,19 @@ fn rewrite_links<I: Iterator<Item = Link>>(header: &mut Value, links: I) -> Resu
 
     debug!("Setting new link array: {:?}", links);
     let process = header
-        .set("imag.links", Value::Array(links))
+        .set("imag.links", links_array(links))
         .chain_err(|| LEK::EntryHeaderReadError);
     process_rw_result(process).map(|_| ())
 }
 
 /// When Linking A -> B, the specification wants us to link back B -> A.
 /// This is a helper function which does this.
-fn add_foreign_link(target: &mut Entry, from: StoreId) -> Result<()> {
+fn add_foreign_link(target: &mut Entry, from: Link) -> Result<()> {
     debug!("Linking back from {:?} to {:?}", target.get_location(), from);
     target.get_internal_links()
         .and_then(|links| {
-            let links = try!(links
-                             .chain(LinkIter::new(vec![from.into()]))
+            let links = try!(with_link(links, from)
+                             .into_iter()
                              .into_values()
                              .into_iter()
                              .fold(Ok(vec![]), |acc, elem| {
@@ -525,13 +725,35 @@ fn add_foreign_link(target: &mut Entry, from: StoreId) -> Result<()> {
 
             let res = target
                 .get_header_mut()
-                .set("imag.links", Value::Array(links))
+                .set("imag.links", links_array(links))
                 .chain_err(|| LEK::EntryHeaderReadError);
 
             process_rw_result(res).map(|_| ())
         })
 }
 
+/// Build the array of links for the header
+///
+/// TOML arrays cannot hold strings and tables at once, so if one of the links is an annotated or
+/// typed link, all links are written as tables.
+fn links_array(links: Vec<Value>) -> Value {
+    if !links.iter().any(|l| is_match!(*l, Value::Table(_))) {
+        return Value::Array(links);
+    }
+
+    Value::Array(links
+        .into_iter()
+        .map(|link| match link {
+            Value::String(s) => {
+                let mut tab = BTreeMap::new();
+                tab.insert("link".to_owned(), Value::String(s));
+                Value::Table(tab)
+            },
+            other => other,
+        })
+        .collect())
+}
+
 fn process_rw_result(links: Result<Option<Value>>) -> Result<LinkIter> {
     use std::path::PathBuf;
 
@@ -567,28 +789,31 @@ fn process_rw_result(links: Result<Option<Value>>) -> Result<LinkIter> {
                     ,
                 Value::Table(mut tab) => {
                     debug!("Destructuring table");
-                    if !tab.contains_key("link")
-                    || !tab.contains_key("annotation") {
+                    if !tab.contains_key("link") {
                         debug!("Things missing... returning Error instance");
                         Err(LE::from_kind(LEK::LinkParserError))
                     } else {
                         let link = try!(tab.remove("link")
                             .ok_or(LE::from_kind(LEK::LinkParserFieldMissingError)));
 
-                        let anno = try!(tab.remove("annotation")
-                            .ok_or(LE::from_kind(LEK::LinkParserFieldMissingError)));
+                        let link = match link {
+                            Value::String(link) => try!(StoreId::new_baseless(PathBuf::from(link))
+                                .chain_err(|| LEK::StoreIdError)),
+                            _ => return Err(LE::from_kind(LEK::LinkParserFieldTypeError)),
+                        };
 
-                        debug!("Ok, here we go with building a Link::Annotated");
-                        match (link, anno) {
-                            (Value::String(link), Value::String(anno)) => {
-                                StoreId::new_baseless(PathBuf::from(link))
-                                    .chain_err(|| LEK::StoreIdError)
-                                    .map(|link| {
-                                        Link::Annotated {
-                                            link: link,
-                                            annotation: anno,
-                                        }
-                                    })
+                        match (tab.remove("annotation"), tab.remove("kind")) {
+                            (None, None) => Ok(Link::Id { link: link }),
+                            (Some(Value::String(anno)), None) => {
+                                debug!("Ok, here we go with building a Link::Annotated");
+                                Ok(Link::Annotated {
+                                    link: link,
+                                    annotation: anno,
+                                })
+                            },
+                            (None, Some(Value::String(kind))) => {
+                                debug!("Ok, here we go with building a Link::Typed");
+                                kind.parse().map(|kind| Link::Typed { link: link, kind: kind })
                             },
                             _ => Err(LE::from_kind(LEK::LinkParserFieldTypeError)),
                         }
@@ -603,185 +828,419 @@ fn process_rw_result(links: Result<Option<Value>>) -> Result<LinkIter> {
     Ok(LinkIter::new(links))
 }
 
+/// Link `this` with all entries in `links` and write all of them in one transaction
+///
+/// If one of the links cannot be added or one of the entries cannot be written, none of the
+/// entries is written.
+pub fn add_internal_links_transactional<'a>(store: &'a Store,
+                                            this: FileLockEntry<'a>,
+                                            links: Vec<FileLockEntry<'a>>)
+    -> Result<()>
+{
+    alter_links_transactional(store, this, links, |this, link| this.add_internal_link(link))
+}
+
+/// Link `this` with all entries in `links` with links of the kind `kind` and write all of them in
+/// one transaction
+///
+/// If one of the links cannot be added or one of the entries cannot be written, none of the
+/// entries is written.
+pub fn add_internal_links_of_kind_transactional<'a>(store: &'a Store,
+                                                    this: FileLockEntry<'a>,
+                                                    links: Vec<FileLockEntry<'a>>,
+                                                    kind: LinkKind)
+    -> Result<()>
+{
+    alter_links_transactional(store, this, links, |this, link| {
+        this.add_internal_link_of_kind(link, kind)
+    })
+}
+
+/// Remove the links between `this` and all entries in `links` and write all of them in one
+/// transaction
+///
+/// If one of the links cannot be removed or one of the entries cannot be written, none of the
+/// entries is written.
+pub fn remove_internal_links_transactional<'a>(store: &'a Store,
+                                               this: FileLockEntry<'a>,
+                                               links: Vec<FileLockEntry<'a>>)
+    -> Result<()>
+{
+    alter_links_transactional(store, this, links, |this, link| this.remove_internal_link(link))
+}
+
+/// Set the links of `this` to all entries in `links` and write all of them in one transaction
+///
+/// Returns the old links of `this`. If one of the links cannot be set or one of the entries cannot
+/// be written, none of the entries is written.
+pub fn set_internal_links_transactional<'a>(store: &'a Store,
+                                            mut this: FileLockEntry<'a>,
+                                            mut links: Vec<FileLockEntry<'a>>)
+    -> Result<LinkIter>
+{
+    let res = this.set_internal_links(links.iter_mut().map(|link| &mut **link).collect());
+    commit_links(store, this, links, res)
+}
+
+fn alter_links_transactional<'a, F>(store: &'a Store,
+                                    mut this: FileLockEntry<'a>,
+                                    mut links: Vec<FileLockEntry<'a>>,
+                                    f: F)
+    -> Result<()>
+    where F: Fn(&mut Entry, &mut Entry) -> Result<()>
+{
+    use libimagutil::iter::FoldResult;
+
+    let res = links.iter_mut().fold_result(|link| f(&mut this, link));
+    commit_links(store, this, links, res)
+}
+
+/// Write `this` and `links` in one transaction if altering their links succeeded (`res`),
+/// otherwise discard their changes
+fn commit_links<'a, T>(store: &'a Store,
+                       this: FileLockEntry<'a>,
+                       links: Vec<FileLockEntry<'a>>,
+                       res: Result<T>)
+    -> Result<T>
+{
+    let mut transaction = store.transaction();
+    transaction.add(this);
+    for link in links {
+        transaction.add(link);
+    }
+
+    match res {
+        Ok(t)  => transaction.commit().chain_err(|| LEK::StoreWriteError).map(|_| t),
+        Err(e) => {
+            debug!("Altering links failed, rolling back");
+            transaction.rollback();
+            Err(e)
+        },
+    }
+}
+
 pub mod store_check {
+    use std::collections::BTreeMap;
+    use std::fmt::{Display, Formatter, Error as FmtError};
+
     use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use error::LinkErrorKind as LEK;
     use error::Result;
     use error::ResultExt;
+    use super::Link;
+
+    /// A change to the links of an entry which makes the links in the store consistent again
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum LinkRepair {
+        /// `entry` is linked by `link`, but does not link back, so the link is added to `entry`
+        AddBackLink { entry: StoreId, link: Link },
+
+        /// `entry` links to `target`, which does not exist, so the link is removed from `entry`
+        RemoveDanglingLink { entry: StoreId, target: StoreId },
+    }
+
+    impl LinkRepair {
+
+        /// The entry which is altered by the repair
+        pub fn entry(&self) -> &StoreId {
+            match *self {
+                LinkRepair::AddBackLink { ref entry, .. }        => entry,
+                LinkRepair::RemoveDanglingLink { ref entry, .. } => entry,
+            }
+        }
+
+    }
+
+    impl Display for LinkRepair {
+
+        fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+            match *self {
+                LinkRepair::AddBackLink { ref entry, ref link } => match link.kind() {
+                    Some(kind) => write!(fmt, "Add link from '{}' to '{}' ({})",
+                                         entry, link.get_store_id(), kind),
+                    None       => write!(fmt, "Add link from '{}' to '{}'",
+                                         entry, link.get_store_id()),
+                },
+                LinkRepair::RemoveDanglingLink { ref entry, ref target } =>
+                    write!(fmt, "Remove dangling link from '{}' to '{}'", entry, target),
+            }
+        }
+
+    }
 
     pub trait StoreLinkConsistentExt {
+
+        /// Check whether the links in the store are consistent
+        ///
+        /// This is built on `link_repairs()`: every repair which would be needed is logged and an
+        /// error is returned for the first one.
         fn check_link_consistency(&self) -> Result<()>;
+
+        /// Find the changes which are needed to make the links in the store consistent
+        ///
+        /// This is a dry run, nothing is written. For each link which exists only on one of the
+        /// two linked entries, the missing link is added. Links to entries which do not exist are
+        /// removed. Hidden and trashed entries are not looked at.
+        fn link_repairs(&self) -> Result<Vec<LinkRepair>>;
+
+        /// Apply repairs as returned by `link_repairs()`
+        ///
+        /// All altered entries are written in one transaction, every change is logged.
+        fn repair_links(&self, repairs: &[LinkRepair]) -> Result<()>;
     }
 
     impl StoreLinkConsistentExt for Store {
-        fn check_link_consistency(&self) -> Result<()> {
-            use std::collections::HashMap;
 
-            use error::LinkErrorKind as LEK;
-            use error::LinkError as LE;
-            use error::Result as LResult;
+        fn link_repairs(&self) -> Result<Vec<LinkRepair>> {
+            use internal::InternalLinker;
+            use graph::stored_ids;
+
+            let mut network : BTreeMap<StoreId, Vec<Link>> = BTreeMap::new();
+            for id in try!(stored_ids(self)) {
+                let links = try!(self
+                    .retrieve_copy(id.clone())
+                    .chain_err(|| LEK::StoreReadError)
+                    .and_then(|entry| entry.get_internal_links())
+                    .chain_err(|| LEK::LinkHandlingError))
+                    .map(Link::without_base)
+                    .collect();
+
+                network.insert(id, links);
+            }
+
+            let mut repairs = vec![];
+            for (id, links) in network.iter() {
+                for link in links {
+                    let target = link.get_store_id();
+
+                    match network.get(target) {
+                        None => repairs.push(LinkRepair::RemoveDanglingLink {
+                            entry: id.clone(),
+                            target: target.clone(),
+                        }),
+
+                        Some(back_links) => if !back_links.iter().any(|l| l.eq_store_id(id)) {
+                            let back_link = match link.kind() {
+                                Some(kind) => Link::Typed { link: id.clone(), kind: kind.inverse() },
+                                None       => Link::Id { link: id.clone() },
+                            };
+
+                            repairs.push(LinkRepair::AddBackLink {
+                                entry: target.clone(),
+                                link: back_link,
+                            })
+                        },
+                    }
+                }
+            }
+
+            Ok(repairs)
+        }
+
+        fn repair_links(&self, repairs: &[LinkRepair]) -> Result<()> {
             use internal::InternalLinker;
+            use super::rewrite_links;
+            use super::with_link;
+
+            let mut by_entry : BTreeMap<&StoreId, Vec<&LinkRepair>> = BTreeMap::new();
+            for repair in repairs {
+                by_entry.entry(repair.entry()).or_insert_with(Vec::new).push(repair);
+            }
+
+            // Entries are added to the transaction even if altering them failed, so their changes
+            // are discarded together with the transaction.
+            let mut transaction = self.transaction();
+            for (id, repairs) in by_entry {
+                let mut entry = match try!(self.get(id.clone()).chain_err(|| LEK::StoreReadError)) {
+                    Some(entry) => entry,
+                    None        => return Err(LEK::LinkTargetDoesNotExist.into()),
+                };
 
-            use libimagstore::store::StoreObject;
-            use libimagstore::storeid::StoreId;
-            use libimagerror::iter::TraceIterator;
-            use libimagutil::iter::FoldResult;
+                let res = entry.get_internal_links().and_then(|links| {
+                    let mut links = links.collect::<Vec<_>>();
+                    for repair in repairs.iter() {
+                        info!("{}", repair);
+                        links = match **repair {
+                            LinkRepair::AddBackLink { ref link, .. } =>
+                                with_link(links.into_iter(), link.clone()),
+                            LinkRepair::RemoveDanglingLink { ref target, .. } =>
+                                links.into_iter().filter(|l| !l.eq_store_id(target)).collect(),
+                        };
+                    }
 
-            // Helper data structure to collect incoming and outgoing links for each StoreId
-            #[derive(Debug, Default)]
-            struct Linking {
-                outgoing: Vec<StoreId>,
-                incoming: Vec<StoreId>,
+                    rewrite_links(entry.get_header_mut(), links.into_iter())
+                });
+
+                transaction.add(entry);
+                try!(res);
             }
 
-            /// Helper function to aggregate the Link network
-            ///
-            /// This function aggregates a HashMap which maps each StoreId object in the store onto
-            /// a Linking object, which contains a list of StoreIds which this entry links to and a
-            /// list of StoreIds which link to the current one.
-            ///
-            /// The lambda returns an error if something fails
-            let aggregate_link_network = |store: &Store| -> Result<HashMap<StoreId, Linking>> {
-                store
-                    .walk("") // this is a hack... I know...
-                    .filter_map(|obj: StoreObject| match obj {
-                        StoreObject::Id(id) => Some(id),
-                        _ => None
-                    }) //  Only ids are interesting
-                    .fold(Ok(HashMap::new()), |acc, sid| {
-                        acc.and_then(|mut state| {
-                            debug!("Checking entry: '{}'", sid);
-
-                            match try!(self.get(sid).chain_err(|| LEK::StoreError)) {
-                                Some(fle) => {
-                                    debug!("Found FileLockEntry");
-
-                                    let fle_loc = fle.get_location();
-
-                                    let internal_links = fle
-                                        .get_internal_links()
-                                        .chain_err(|| LEK::StoreError)?
-                                        .into_getter(self) // get the FLEs from the Store
-                                        .trace_unwrap(); // trace all Err(e)s and get the Ok(fle)s
-
-                                    for internal_link in internal_links {
-                                        let il_loc = internal_link.get_location();
-
-                                        state
-                                            .entry(il_loc.clone())
-                                            .or_insert(Linking::default())
-                                            .incoming
-                                            .push(fle_loc.clone());
-
-                                        // Make sure an empty linking object is present for the
-                                        // current StoreId object
-                                        state
-                                            .entry(fle_loc.clone())
-                                            .or_insert(Linking::default())
-                                            .outgoing
-                                            .push(il_loc.clone());
-                                    }
-
-                                    Ok(state)
-                                },
-                                None => {
-                                    debug!("No entry");
-                                    Ok(state)
-                                }
-                            }
-                        })
-                    })
-            };
+            transaction.commit().chain_err(|| LEK::StoreWriteError)
+        }
 
-            /// Helper to check whethre all StoreIds in the network actually exists
-            ///
-            /// Because why not?
-            let all_collected_storeids_exist = |network: &HashMap<StoreId, Linking>| -> LResult<()> {
-                network
-                    .iter()
-                    .fold_result(|(id, _)| {
-                        if is_match!(self.get(id.clone()), Ok(Some(_))) {
-                            debug!("Exists in store: {:?}", id);
-
-                            if !try!(id.exists().chain_err(|| LEK::StoreReadError)) {
-                                warn!("Does exist in store but not on FS: {:?}", id);
-                                Err(LE::from_kind(LEK::LinkTargetDoesNotExist))
-                            } else {
-                                Ok(())
-                            }
-                        } else {
-                            warn!("Does not exist in store: {:?}", id);
-                            Err(LE::from_kind(LEK::LinkTargetDoesNotExist))
-                        }
-                    })
+        fn check_link_consistency(&self) -> Result<()> {
+            use error::LinkError as LE;
+
+            let repairs = try!(self.link_repairs());
+            for repair in repairs.iter() {
+                warn!("Inconsistent link, needed repair: {}", repair);
+            }
+
+            match repairs.into_iter().next() {
+                None => Ok(()),
+                Some(LinkRepair::AddBackLink { entry, link }) =>
+                    Err(LE::from_kind(LEK::DeadLink(link.get_store_id().clone(), entry))),
+                Some(LinkRepair::RemoveDanglingLink { .. }) =>
+                    Err(LE::from_kind(LEK::LinkTargetDoesNotExist)),
+            }
+        }
+    }
+
+}
+
+pub mod store_move {
+    use std::collections::BTreeSet;
+
+    use libimagstore::store::Entry;
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagentrymarkdown::link::extract_links;
+
+    use error::LinkErrorKind as LEK;
+    use error::Result;
+    use error::ResultExt;
+
+    pub trait StoreLinkMoveExt {
+
+        /// Move an entry like `Store::move_by_id()` and rewrite the links which point to it
+        ///
+        /// The internal links of the entries which are linked with the moved entry are changed to
+        /// point to the new id. If `markdown` is true, the markdown links to the old id in the
+        /// content of all entries are changed as well.
+        ///
+        /// All altered entries are written in one transaction. If this fails, the entry is moved
+        /// back.
+        fn move_by_id_with_links(&self, old_id: StoreId, new_id: StoreId, markdown: bool)
+            -> Result<()>;
+
+    }
+
+    impl StoreLinkMoveExt for Store {
+
+        fn move_by_id_with_links(&self, old_id: StoreId, new_id: StoreId, markdown: bool)
+            -> Result<()>
+        {
+            let old_id = old_id.without_base();
+            let new_id = new_id.without_base();
+
+            try!(self.move_by_id(old_id.clone(), new_id.clone()).chain_err(|| LEK::StoreWriteError));
+
+            let res = rewrite_links_to(self, &old_id, &new_id, markdown);
+            if res.is_err() {
+                debug!("Rewriting the links failed, moving '{}' back", new_id);
+                if let Err(e) = self.move_by_id(new_id.clone(), old_id.clone()) {
+                    warn!("Could not move '{}' back to '{}': {:?}", new_id, old_id, e);
+                }
+            }
+            res
+        }
+
+    }
+
+    /// Rewrite the links to `old_id` in the entries which could link to it
+    fn rewrite_links_to(store: &Store, old_id: &StoreId, new_id: &StoreId, markdown: bool)
+        -> Result<()>
+    {
+        use internal::InternalLinker;
+        use graph::stored_ids;
+
+        let mut candidates = try!(store
+            .retrieve_copy(new_id.clone())
+            .chain_err(|| LEK::StoreReadError)
+            .and_then(|entry| entry.get_internal_links()))
+            .map(|link| link.get_store_id().clone().without_base())
+            .collect::<BTreeSet<_>>();
+
+        if markdown {
+            candidates.extend(try!(stored_ids(store)));
+        }
+
+        // Entries are added to the transaction even if altering them failed, so their changes
+        // are discarded together with the transaction.
+        let mut transaction = store.transaction();
+        for id in candidates {
+            let mut copy = match store.retrieve_copy(id.clone()) {
+                Ok(copy) => copy,
+                Err(_)   => {
+                    debug!("Cannot read '{}', not rewriting links in it", id);
+                    continue
+                },
             };
 
-            /// Helper function to create a SLCECD::OneDirectionalLink error object
-            #[inline]
-            let mk_one_directional_link_err = |src: StoreId, target: StoreId| -> LE {
-                LE::from_kind(LEK::DeadLink(src, target))
+            // Find out whether the entry links to the old id before getting it, as the store
+            // writes all entries it hands out
+            if !try!(rewrite_entry(&mut copy, old_id, new_id, markdown)) {
+                continue;
+            }
+
+            let mut entry = match try!(store.get(id.clone()).chain_err(|| LEK::StoreReadError)) {
+                Some(entry) => entry,
+                None        => continue,
             };
 
-            /// Helper lambda to check whether the _incoming_ links of each entry actually also
-            /// appear in the _outgoing_ list of the linked entry
-            let incoming_links_exists_as_outgoing_links =
-                |src: &StoreId, linking: &Linking, network: &HashMap<StoreId, Linking>| -> Result<()> {
-                    linking
-                        .incoming
-                        .iter()
-                        .fold_result(|link| {
-
-                            // Check whether the links which are _incoming_ on _src_ are outgoing
-                            // in each of the links in the incoming list.
-                            let incoming_consistent = network.get(link)
-                                .map(|l| l.outgoing.contains(src))
-                                .unwrap_or(false);
-
-                            if !incoming_consistent {
-                                Err(mk_one_directional_link_err(src.clone(), link.clone()))
-                            } else {
-                                Ok(())
-                            }
-                        })
-                };
+            info!("Rewriting links to '{}' in '{}'", old_id, id);
+            let res = rewrite_entry(&mut entry, old_id, new_id, markdown);
+            transaction.add(entry);
+            try!(res);
+        }
 
-            /// Helper lambda to check whether the _outgoing links of each entry actually also
-            /// appear in the _incoming_ list of the linked entry
-            let outgoing_links_exist_as_incoming_links =
-                |src: &StoreId, linking: &Linking, network: &HashMap<StoreId, Linking>| -> Result<()> {
-                    linking
-                        .outgoing
-                        .iter()
-                        .fold_result(|link| {
-
-                            // Check whether the links which are _outgoing_ on _src_ are incoming
-                            // in each of the links in the outgoing list.
-                            let outgoing_consistent = network.get(link)
-                                .map(|l| l.incoming.contains(src))
-                                .unwrap_or(false);
-
-                            if !outgoing_consistent {
-                                Err(mk_one_directional_link_err(link.clone(), src.clone()))
-                            } else {
-                                Ok(())
-                            }
-                        })
-                };
+        transaction.commit().chain_err(|| LEK::StoreWriteError)
+    }
 
-            aggregate_link_network(&self)
-                .and_then(|nw| {
-                    all_collected_storeids_exist(&nw)
-                        .map(|_| nw)
-                        .chain_err(|| LEK::LinkHandlingError)
-                })
-                .and_then(|nw| {
-                    nw.iter().fold_result(|(id, linking)| {
-                        try!(incoming_links_exists_as_outgoing_links(id, linking, &nw));
-                        try!(outgoing_links_exist_as_incoming_links(id, linking, &nw));
-                        Ok(())
-                    })
-                })
-                .map(|_| ())
+    /// Change the links to `old_id` in `entry` to links to `new_id`
+    ///
+    /// Returns whether the entry was changed.
+    fn rewrite_entry(entry: &mut Entry, old_id: &StoreId, new_id: &StoreId, markdown: bool)
+        -> Result<bool>
+    {
+        use internal::InternalLinker;
+        use super::rewrite_links;
+
+        let mut changed = false;
+
+        let links = try!(entry.get_internal_links()).collect::<Vec<_>>();
+        if links.iter().any(|l| l.eq_store_id(old_id)) {
+            let links = links.into_iter().map(|l| if l.eq_store_id(old_id) {
+                l.with_store_id(new_id.clone())
+            } else {
+                l
+            });
+
+            try!(rewrite_links(entry.get_header_mut(), links));
+            changed = true;
+        }
+
+        if markdown {
+            let old = try!(old_id.to_str().chain_err(|| LEK::StoreIdError));
+            let new = try!(new_id.to_str().chain_err(|| LEK::StoreIdError));
+
+            // Only parse the content if it can contain a link to the old id at all
+            let links_to_old = entry.get_content().contains(&format!("]({}", old)) &&
+                extract_links(entry.get_content()).iter().any(|l| l.link == old);
+
+            if links_to_old {
+                let content = entry
+                    .get_content()
+                    .replace(&format!("]({})", old), &format!("]({})", new))
+                    .replace(&format!("]({} ", old), &format!("]({} ", new));
+
+                *entry.get_content_mut() = content;
+                changed = true;
+            }
         }
+
+        Ok(changed)
     }
 
 }
@@ -1000,5 +1459,325 @@ mod test {
         assert_eq!(e3.get_internal_links().unwrap().collect::<Vec<_>>().len(), 0);
     }
 
-}
+    #[test]
+    fn test_add_and_remove_links_transactional() {
+        use super::add_internal_links_transactional;
+        use super::remove_internal_links_transactional;
+
+        setup_logging();
+        let store = get_store();
+
+        {
+            let e1 = store.create(PathBuf::from("1")).unwrap();
+            let e2 = store.create(PathBuf::from("2")).unwrap();
+            let e3 = store.create(PathBuf::from("3")).unwrap();
+            assert!(add_internal_links_transactional(&store, e1, vec![e2, e3]).is_ok());
+        }
+
+        let links_of = |id: &str| store.retrieve_copy(PathBuf::from(id))
+            .unwrap()
+            .get_internal_links()
+            .unwrap()
+            .collect::<Vec<_>>()
+            .len();
+
+        assert_eq!(links_of("1"), 2);
+        assert_eq!(links_of("2"), 1);
+        assert_eq!(links_of("3"), 1);
+
+        {
+            let e1 = store.retrieve(PathBuf::from("1")).unwrap();
+            let e3 = store.retrieve(PathBuf::from("3")).unwrap();
+            assert!(remove_internal_links_transactional(&store, e1, vec![e3]).is_ok());
+        }
+
+        assert_eq!(links_of("1"), 1);
+        assert_eq!(links_of("2"), 1);
+        assert_eq!(links_of("3"), 0);
+    }
+
+    #[test]
+    fn test_set_links_transactional() {
+        use super::set_internal_links_transactional;
+
+        setup_logging();
+        let store = get_store();
 
+        {
+            let e1 = store.create(PathBuf::from("1")).unwrap();
+            let e2 = store.create(PathBuf::from("2")).unwrap();
+            let e3 = store.create(PathBuf::from("3")).unwrap();
+            let old = set_internal_links_transactional(&store, e1, vec![e2, e3]).unwrap();
+            assert_eq!(old.count(), 0);
+        }
+
+        let links_of = |id: &str| store.retrieve_copy(PathBuf::from(id))
+            .unwrap()
+            .get_internal_links()
+            .unwrap()
+            .collect::<Vec<_>>()
+            .len();
+
+        assert_eq!(links_of("1"), 2);
+        assert_eq!(links_of("2"), 1);
+        assert_eq!(links_of("3"), 1);
+    }
+
+    #[test]
+    fn test_add_links_transactional_writes_nothing_on_error() {
+        use toml::Value;
+        use toml_query::set::TomlValueSetExt;
+        use super::add_internal_links_transactional;
+
+        setup_logging();
+        let store = get_store();
+
+        {
+            let _ = store.create(PathBuf::from("1")).unwrap();
+            let _ = store.create(PathBuf::from("2")).unwrap();
+        }
+
+        {
+            let e1     = store.retrieve(PathBuf::from("1")).unwrap();
+            let e2     = store.retrieve(PathBuf::from("2")).unwrap();
+            let mut e3 = store.create(PathBuf::from("3")).unwrap();
+            let _      = e3.get_header_mut().set("imag.links", Value::Integer(1)).unwrap();
+
+            assert!(add_internal_links_transactional(&store, e1, vec![e2, e3]).is_err());
+        }
+
+        for id in &["1", "2"] {
+            let entry = store.retrieve_copy(PathBuf::from(id)).unwrap();
+            assert_eq!(entry.get_internal_links().unwrap().collect::<Vec<_>>().len(), 0);
+        }
+        assert!(store.get(PathBuf::from("3")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_link_kinds() {
+        use libimagstore::store::Entry;
+        use super::LinkKind;
+
+        setup_logging();
+        let store = get_store();
+
+        let mut e1 = store.create(PathBuf::from("test_link_kinds1")).unwrap();
+        let mut e2 = store.create(PathBuf::from("test_link_kinds2")).unwrap();
+        let mut e3 = store.create(PathBuf::from("test_link_kinds3")).unwrap();
+
+        assert!(e1.add_internal_link(&mut e2).is_ok());
+        assert!(e1.add_internal_link_of_kind(&mut e2, LinkKind::Blocks).is_ok());
+        assert!(e1.add_internal_link_of_kind(&mut e3, LinkKind::PartOf).is_ok());
+
+        // The untyped link was replaced by the typed one
+        let e1_links = e1.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(e1_links.len(), 2);
+        assert!(e1_links.iter().all(|l| l.kind().is_some()));
+
+        let blocks = e1.get_internal_links_of_kind(LinkKind::Blocks).unwrap().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].clone().with_base(store.path().clone()).eq_store_id(e2.get_location()));
+
+        let e2_links = e2.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(e2_links.len(), 1);
+        assert_eq!(e2_links[0].kind(), Some(LinkKind::BlockedBy));
+
+        let e3_links = e3.get_internal_links_of_kind(LinkKind::HasPart).unwrap();
+        assert_eq!(e3_links.count(), 1);
+
+        // Typed and untyped links can be mixed
+        let mut e4 = store.create(PathBuf::from("test_link_kinds4")).unwrap();
+        assert!(e1.add_internal_link(&mut e4).is_ok());
+        assert_eq!(e1.get_internal_links().unwrap().count(), 3);
+
+        // ...but are written as tables, as TOML arrays cannot be mixed
+        let serialized = e1.to_str();
+        assert!(serialized.contains("[[imag.links]]"), "Expected array of tables, got: {}", serialized);
+        assert!(serialized.contains("link = \"test_link_kinds4\""), "Untyped link missing: {}", serialized);
+        let reread = Entry::from_str(e1.get_location().clone(), &serialized).unwrap();
+        assert_eq!(reread.get_internal_links().unwrap().count(), 3);
+
+        // Adding an untyped link does not remove the kind
+        assert!(e2.add_internal_link(&mut e1).is_ok());
+        assert_eq!(e2.get_internal_links().unwrap().next().unwrap().kind(), Some(LinkKind::BlockedBy));
+        assert_eq!(e1.get_internal_links_of_kind(LinkKind::Blocks).unwrap().count(), 1);
+
+        assert!(e1.remove_internal_link(&mut e2).is_ok());
+        assert_eq!(e1.get_internal_links().unwrap().count(), 2);
+        assert_eq!(e2.get_internal_links().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_link_kind_header_roundtrip() {
+        use toml::Value;
+        use toml_query::read::TomlValueReadExt;
+        use toml_query::set::TomlValueSetExt;
+        use super::LinkKind;
+
+        setup_logging();
+        let store = get_store();
+
+        let mut e1 = store.create(PathBuf::from("test_link_kind_header1")).unwrap();
+        let mut e2 = store.create(PathBuf::from("test_link_kind_header2")).unwrap();
+        assert!(e1.add_internal_link_of_kind(&mut e2, LinkKind::FollowUp).is_ok());
+
+        match e1.get_header().read("imag.links.[0].kind") {
+            Ok(Some(&Value::String(ref s))) => assert_eq!(s, "follow-up"),
+            other => panic!("Unexpected header value: {:?}", other),
+        }
+
+        let _ = e1.get_header_mut()
+            .set("imag.links.[0].kind", Value::String(String::from("unknown")))
+            .unwrap();
+        assert!(e1.get_internal_links().is_err());
+
+        assert_eq!("part-of".parse::<LinkKind>().unwrap(), LinkKind::PartOf);
+        assert_eq!(LinkKind::PartOf.inverse().to_string(), "has-part");
+    }
+
+    #[test]
+    fn test_filter_for_link_kind() {
+        use libimagstore::storeid::StoreId;
+        use super::Link;
+        use super::LinkIter;
+        use super::LinkKind;
+        use super::add_internal_links_of_kind_transactional;
+
+        setup_logging();
+        let store = get_store();
+
+        {
+            let e1 = store.create(PathBuf::from("1")).unwrap();
+            let e2 = store.create(PathBuf::from("2")).unwrap();
+            let e3 = store.create(PathBuf::from("3")).unwrap();
+            let res = add_internal_links_of_kind_transactional(&store, e1, vec![e2, e3],
+                                                               LinkKind::Blocks);
+            assert!(res.is_ok());
+
+            let mut e4 = store.create(PathBuf::from("4")).unwrap();
+            let mut e5 = store.create(PathBuf::from("5")).unwrap();
+            assert!(e4.add_internal_link(&mut e5).is_ok());
+        }
+
+        let ids = vec!["1", "2", "3", "4", "5"]
+            .into_iter()
+            .map(|id| Link::from(StoreId::new(Some(store.path().clone()), PathBuf::from(id)).unwrap()))
+            .collect::<Vec<_>>();
+
+        let blocked = LinkIter::new(ids)
+            .into_getter(&store)
+            .with_links_of_kind(LinkKind::BlockedBy)
+            .map(|fle| fle.unwrap().get_location().clone().without_base().to_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(blocked, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn test_link_repairs() {
+        use std::collections::BTreeMap;
+        use toml::Value;
+        use toml_query::set::TomlValueSetExt;
+        use libimagstore::storeid::StoreId;
+        use super::LinkKind;
+        use super::store_check::LinkRepair;
+        use super::store_check::StoreLinkConsistentExt;
+
+        setup_logging();
+        let store = get_store();
+
+        let plain = |id: &str| {
+            let mut tab = BTreeMap::new();
+            tab.insert(String::from("link"), Value::String(String::from(id)));
+            Value::Table(tab)
+        };
+
+        let typed = |id: &str, kind: &str| {
+            let mut tab = BTreeMap::new();
+            tab.insert(String::from("link"), Value::String(String::from(id)));
+            tab.insert(String::from("kind"), Value::String(String::from(kind)));
+            Value::Table(tab)
+        };
+
+        {
+            let mut a = store.create(PathBuf::from("a")).unwrap();
+            let links = vec![
+                plain("b"),
+                plain("missing"),
+                typed("c", "blocks"),
+            ];
+            let _ = a.get_header_mut().set("imag.links", Value::Array(links)).unwrap();
+
+            let _ = store.create(PathBuf::from("b")).unwrap();
+            let _ = store.create(PathBuf::from("c")).unwrap();
+        }
+
+        let id = |s: &str| StoreId::new_baseless(PathBuf::from(s)).unwrap();
+
+        let repairs = store.link_repairs().unwrap();
+        assert_eq!(repairs.len(), 3);
+        assert!(repairs.contains(&LinkRepair::AddBackLink { entry: id("b"), link: id("a").into() }));
+        assert!(repairs.contains(&LinkRepair::RemoveDanglingLink { entry: id("a"), target: id("missing") }));
+
+        // link_repairs() is a dry run
+        assert_eq!(store.link_repairs().unwrap(), repairs);
+        assert!(store.check_link_consistency().is_err());
+
+        assert!(store.repair_links(&repairs).is_ok());
+        assert!(store.link_repairs().unwrap().is_empty());
+        assert!(store.check_link_consistency().is_ok());
+
+        let a = store.retrieve_copy(PathBuf::from("a")).unwrap();
+        assert_eq!(a.get_internal_links().unwrap().count(), 2);
+
+        let c = store.retrieve_copy(PathBuf::from("c")).unwrap();
+        let c_links = c.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(c_links.len(), 1);
+        assert_eq!(c_links[0].kind(), Some(LinkKind::BlockedBy));
+    }
+
+    #[test]
+    fn test_move_by_id_with_links() {
+        use libimagstore::storeid::StoreId;
+        use super::LinkKind;
+        use super::store_move::StoreLinkMoveExt;
+
+        setup_logging();
+        let store = get_store();
+
+        {
+            let mut a = store.create(PathBuf::from("test_move_a")).unwrap();
+            let mut b = store.create(PathBuf::from("test_move_b")).unwrap();
+            assert!(a.add_internal_link_of_kind(&mut b, LinkKind::PartOf).is_ok());
+
+            let mut c = store.create(PathBuf::from("test_move_c")).unwrap();
+            *c.get_content_mut() = String::from("See [b](test_move_b) and [a](test_move_a).\n");
+        }
+
+        let id = |s: &str| StoreId::new_baseless(PathBuf::from(s)).unwrap();
+        assert!(store.move_by_id_with_links(id("test_move_b"), id("test_move_d"), true).is_ok());
+
+        assert!(store.get(PathBuf::from("test_move_b")).unwrap().is_none());
+
+        let a = store.retrieve_copy(PathBuf::from("test_move_a")).unwrap();
+        let a_links = a.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(a_links.len(), 1);
+        assert!(a_links[0].eq_store_id(&id("test_move_d")));
+        assert_eq!(a_links[0].kind(), Some(LinkKind::PartOf));
+
+        let d = store.retrieve_copy(PathBuf::from("test_move_d")).unwrap();
+        let d_links = d.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert!(d_links[0].eq_store_id(&id("test_move_a")));
+
+        let c = store.retrieve_copy(PathBuf::from("test_move_c")).unwrap();
+        assert_eq!(c.get_content(), "See [b](test_move_d) and [a](test_move_a).\n");
+
+        // Without rewriting markdown links, the content is left alone
+        assert!(store.move_by_id_with_links(id("test_move_d"), id("test_move_e"), false).is_ok());
+        let c = store.retrieve_copy(PathBuf::from("test_move_c")).unwrap();
+        assert_eq!(c.get_content(), "See [b](test_move_d) and [a](test_move_a).\n");
+        let a = store.retrieve_copy(PathBuf::from("test_move_a")).unwrap();
+        assert!(a.get_internal_links().unwrap().next().unwrap().eq_store_id(&id("test_move_e")));
+    }
+
+}
@@ -41,19 +41,26 @@ extern crate toml;
 extern crate toml_query;
 extern crate url;
 extern crate crypto;
+extern crate serde;
+extern crate serde_json;
+#[macro_use] extern crate serde_derive;
 #[macro_use] extern crate is_match;
 #[macro_use] extern crate error_chain;
 
 #[cfg(test)]
 extern crate env_logger;
+#[cfg(test)]
+extern crate tempdir;
 
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
 extern crate libimagutil;
+extern crate libimagentrymarkdown;
 
 module_entry_path_mod!("links");
 
 pub mod error;
 pub mod external;
+pub mod graph;
 pub mod internal;
 
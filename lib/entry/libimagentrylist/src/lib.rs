@@ -39,8 +39,12 @@ extern crate clap;
 #[macro_use] extern crate log;
 extern crate prettytable;
 #[macro_use] extern crate error_chain;
+extern crate serde;
+extern crate serde_json;
+extern crate toml;
 
 extern crate libimagstore;
+extern crate libimagrt;
 extern crate libimagutil;
 extern crate libimagerror;
 
@@ -48,4 +52,5 @@ pub mod cli;
 pub mod error;
 pub mod lister;
 pub mod listers;
+pub mod serializer;
 
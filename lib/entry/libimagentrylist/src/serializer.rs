@@ -0,0 +1,150 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! Printing the results of listing and query commands in the output format of the runtime
+//!
+//! Binaries collect the items they list (usually structs which derive `Serialize`) and print
+//! them with `print_items()` in the format from `Runtime::output_format()`. In the JSON and TOML
+//! formats, the items are written as array in a document, where the name of the array is passed
+//! by the binary:
+//!
+//! ```text
+//! $ imag counter --output json list
+//! {"counters":[{"name":"coffee","unit":null,"value":3}]}
+//! ```
+//!
+//! `None` values are written as `null` in JSON and left out in TOML, which does not know a null
+//! value. As the structure of the output is part of the interface of a binary, fields should only
+//! be added, not renamed or removed.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::io::stdout;
+
+use serde::Serialize;
+use serde_json;
+use toml;
+
+pub use libimagrt::output::OutputFormat;
+
+use error::Result;
+use error::ResultExt;
+use error::ListErrorKind as LEK;
+
+/// Write the items to `out`
+///
+/// In the text format, each item is written with `text`, followed by a newline. Otherwise the
+/// items are written as the array `name` in a JSON or TOML document.
+pub fn write_items<W, T, F>(out: &mut W, format: OutputFormat, name: &str, items: &[T], text: F)
+    -> Result<()>
+    where W: Write,
+          T: Serialize,
+          F: Fn(&T) -> String
+{
+    let mut document = BTreeMap::new();
+    document.insert(name, items);
+
+    match format {
+        OutputFormat::Text => {
+            for item in items {
+                try!(write!(out, "{}\n", text(item)).chain_err(|| LEK::IOError));
+            }
+            Ok(())
+        },
+
+        OutputFormat::Json => {
+            let json = try!(serde_json::to_string(&document).chain_err(|| LEK::FormatError));
+            write!(out, "{}\n", json).chain_err(|| LEK::IOError)
+        },
+
+        OutputFormat::Toml => {
+            // Going through toml::Value orders the tables after the plain values, which the
+            // serializer requires
+            let toml = try!(toml::Value::try_from(&document)
+                            .and_then(|value| toml::to_string(&value))
+                            .chain_err(|| LEK::FormatError));
+            write!(out, "{}", toml).chain_err(|| LEK::IOError)
+        },
+    }
+}
+
+/// Write the items to stdout, see `write_items()`
+pub fn print_items<T, F>(format: OutputFormat, name: &str, items: &[T], text: F) -> Result<()>
+    where T: Serialize,
+          F: Fn(&T) -> String
+{
+    let out = stdout();
+    let mut lock = out.lock();
+    write_items(&mut lock, format, name, items, text)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use libimagrt::output::OutputFormat;
+
+    use super::write_items;
+
+    fn items() -> Vec<BTreeMap<&'static str, &'static str>> {
+        let mut a = BTreeMap::new();
+        a.insert("name", "a");
+        a.insert("value", "1");
+
+        let mut b = BTreeMap::new();
+        b.insert("name", "b");
+        b.insert("value", "2");
+
+        vec![a, b]
+    }
+
+    fn write(format: OutputFormat) -> String {
+        let mut out = vec![];
+        write_items(&mut out, format, "things", &items(), |item| format!("{} = {}", item["name"], item["value"]))
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!("a = 1\nb = 2\n", write(OutputFormat::Text));
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!("{\"things\":[{\"name\":\"a\",\"value\":\"1\"},{\"name\":\"b\",\"value\":\"2\"}]}\n",
+                   write(OutputFormat::Json));
+    }
+
+    #[test]
+    fn test_toml() {
+        assert_eq!("[[things]]\nname = \"a\"\nvalue = \"1\"\n\n[[things]]\nname = \"b\"\nvalue = \"2\"\n",
+                   write(OutputFormat::Toml));
+    }
+
+    #[test]
+    fn test_no_items() {
+        let none : Vec<String> = vec![];
+        let mut out = vec![];
+        write_items(&mut out, OutputFormat::Json, "things", &none, String::clone).unwrap();
+        assert_eq!("{\"things\":[]}\n", String::from_utf8(out).unwrap());
+    }
+
+}
@@ -41,6 +41,8 @@ extern crate itertools;
 extern crate toml;
 extern crate toml_query;
 extern crate walkdir;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
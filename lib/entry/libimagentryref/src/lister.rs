@@ -18,12 +18,11 @@
 //
 
 use std::default::Default;
-use std::io::stdout;
-use std::io::Write;
 use std::ops::Deref;
 
 use libimagentrylist::lister::Lister;
 use libimagentrylist::error::Result;
+use libimagentrylist::serializer::{OutputFormat, print_items};
 use libimagerror::trace::trace_error;
 use libimagstore::store::FileLockEntry;
 use libimagentrylist::error::ListErrorKind as LEK;
@@ -36,6 +35,18 @@ pub struct RefLister {
     check_changed: bool,
     check_changed_content: bool,
     check_changed_permiss: bool,
+    format: OutputFormat,
+}
+
+/// A reference as it is listed. The checks which were not requested are `None`.
+#[derive(Serialize)]
+struct RefListing {
+    id: String,
+    hash: Option<String>,
+    dead: Option<bool>,
+    changed: Option<bool>,
+    changed_content: Option<bool>,
+    changed_permissions: Option<bool>,
 }
 
 impl RefLister {
@@ -64,6 +75,11 @@ impl RefLister {
         self
     }
 
+    pub fn output_format(mut self, format: OutputFormat) -> RefLister {
+        self.format = format;
+        self
+    }
+
 }
 
 impl Default for RefLister {
@@ -74,6 +90,7 @@ impl Default for RefLister {
             check_changed: false,
             check_changed_content: false,
             check_changed_permiss: false,
+            format: OutputFormat::Text,
         }
     }
 }
@@ -83,56 +100,62 @@ impl Lister for RefLister {
     fn list<'b, I: Iterator<Item = FileLockEntry<'b>>>(&self, entries: I) -> Result<()> {
 
         debug!("Called list()");
-        let (r, n) = entries.fold((Ok(()), 0), |(accu, i), entry| {
-            debug!("fold({:?}, {:?})", accu, entry);
-            let r = accu.and_then(|_| {
-                    debug!("Listing Entry: {:?}", entry);
-                    {
-                        let is_dead = if self.check_dead {
-                            if try!(lerror::ResultExt::chain_err(entry.fs_link_exists(), || LEK::FormatError)) {
-                                "dead"
-                            } else {
-                                "alive"
-                            }
-                        } else {
-                            "not checked"
-                        };
-
-                        let is_changed = if self.check_changed {
-                            if check_changed(entry.deref()) { "changed" } else { "unchanged" }
-                        } else {
-                            "not checked"
-                        };
-
-                        let is_changed_content = if self.check_changed_content {
-                            if check_changed_content(entry.deref()) { "changed" } else { "unchanged" }
-                        } else {
-                            "not checked"
-                        };
-
-                        let is_changed_permiss = if self.check_changed_permiss {
-                            if check_changed_permiss(entry.deref()) { "changed" } else { "unchanged" }
-                        } else {
-                            "not checked"
-                        };
-
-                        Ok(format!("{} | {} | {} | {} | {} | {}",
-                                is_dead,
-                                is_changed,
-                                is_changed_content,
-                                is_changed_permiss,
-                                entry.get_path_hash().unwrap_or_else(|_| String::from("Cannot get hash")),
-                                entry.get_location()))
-                    }
-                        .and_then(|s| {
-                            lerror::ResultExt::chain_err(write!(stdout(), "{}\n", s), || LEK::FormatError)
-                        })
-                })
-                .map(|_| ());
-            (r, i + 1)
-        });
-        debug!("Iterated over {} entries", n);
-        r
+        let mut refs = vec![];
+        for entry in entries {
+            debug!("Listing Entry: {:?}", entry);
+
+            let is_dead = if self.check_dead {
+                Some(!try!(lerror::ResultExt::chain_err(entry.fs_link_exists(), || LEK::FormatError)))
+            } else {
+                None
+            };
+
+            let is_changed = if self.check_changed {
+                Some(check_changed(entry.deref()))
+            } else {
+                None
+            };
+
+            let is_changed_content = if self.check_changed_content {
+                Some(check_changed_content(entry.deref()))
+            } else {
+                None
+            };
+
+            let is_changed_permiss = if self.check_changed_permiss {
+                Some(check_changed_permiss(entry.deref()))
+            } else {
+                None
+            };
+
+            refs.push(RefListing {
+                id: entry.get_location().to_string(),
+                hash: entry.get_path_hash().ok(),
+                dead: is_dead,
+                changed: is_changed,
+                changed_content: is_changed_content,
+                changed_permissions: is_changed_permiss,
+            });
+        }
+        debug!("Iterated over {} entries", refs.len());
+
+        print_items(self.format, "refs", &refs, |r| {
+            fn state(checked: Option<bool>, yes: &'static str, no: &'static str) -> &'static str {
+                match checked {
+                    Some(true)  => yes,
+                    Some(false) => no,
+                    None        => "not checked",
+                }
+            }
+
+            format!("{} | {} | {} | {} | {} | {}",
+                    state(r.dead, "dead", "alive"),
+                    state(r.changed, "changed", "unchanged"),
+                    state(r.changed_content, "changed", "unchanged"),
+                    state(r.changed_permissions, "changed", "unchanged"),
+                    r.hash.as_ref().map(String::as_str).unwrap_or("Cannot get hash"),
+                    r.id)
+        })
     }
 
 }
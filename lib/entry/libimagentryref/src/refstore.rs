@@ -61,6 +61,12 @@ pub trait RefStore {
     fn create_with_hasher<'a, H: Hasher>(&'a self, pb: PathBuf, flags: RefFlags, h: H)
         -> Result<FileLockEntry<'a>>;
 
+    /// Find references which refer to the same file
+    ///
+    /// Returns the referenced path and the ids of the references for each file which is
+    /// referenced more than once. References which cannot be read are ignored.
+    fn duplicate_refs(&self) -> Result<Vec<(PathBuf, Vec<StoreId>)>>;
+
 }
 
 impl RefStore for Store {
@@ -264,5 +270,28 @@ impl RefStore for Store {
         Ok(fle)
     }
 
-}
+    fn duplicate_refs(&self) -> Result<Vec<(PathBuf, Vec<StoreId>)>> {
+        let mut refs : BTreeMap<PathBuf, Vec<StoreId>> = BTreeMap::new();
 
+        for id in try!(self.retrieve_for_module("ref").chain_err(|| REK::StoreReadError)) {
+            let path = match self.retrieve_copy(id.clone()) {
+                Ok(entry) => match read_reference(&entry) {
+                    Ok(path) => path,
+                    Err(_)   => {
+                        debug!("Ignoring ref without path: {:?}", id);
+                        continue;
+                    },
+                },
+                Err(_) => {
+                    debug!("Ignoring ref which cannot be read: {:?}", id);
+                    continue;
+                },
+            };
+
+            refs.entry(path).or_insert_with(Vec::new).push(id);
+        }
+
+        Ok(refs.into_iter().filter(|&(_, ref ids)| ids.len() > 1).collect())
+    }
+
+}
@@ -121,18 +121,24 @@ impl Tagable for Value {
     fn has_tag(&self, t: TagSlice) -> Result<bool> {
         let tags = try!(self.read("imag.tags").chain_err(|| TagErrorKind::HeaderReadError));
 
-        if !tags.iter().all(|t| is_match!(*t, &Value::String(_))) {
-            return Err(TagErrorKind::TagTypeError.into());
-        }
+        match tags {
+            Some(&Value::Array(ref tags)) => {
+                if !tags.iter().all(|t| is_match!(*t, Value::String(_))) {
+                    return Err(TagErrorKind::TagTypeError.into());
+                }
 
-        Ok(tags
-           .iter()
-           .any(|tag| {
-               match *tag {
-                   &Value::String(ref s) => { s == t },
-                   _ => unreachable!()
-               }
-           }))
+                Ok(tags
+                   .iter()
+                   .any(|tag| {
+                       match *tag {
+                           Value::String(ref s) => { s == t },
+                           _ => unreachable!()
+                       }
+                   }))
+            },
+            None => Ok(false),
+            _ => Err(TagErrorKind::TagTypeError.into()),
+        }
     }
 
     fn has_tags(&self, tags: &[Tag]) -> Result<bool> {
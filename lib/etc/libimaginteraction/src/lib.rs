@@ -45,8 +45,10 @@ extern crate toml;
 
 extern crate libimagstore;
 extern crate libimagerror;
+extern crate libimagutil;
 
 pub mod ask;
+pub mod config;
 pub mod error;
 pub mod filter;
 pub mod ui;
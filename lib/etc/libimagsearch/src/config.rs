@@ -0,0 +1,81 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use toml::Value;
+
+use libimagutil::schema::{ConfigSchema, ValueType};
+
+/// Get the header fields which should be indexed
+///
+/// `config` is the configuration of the `search-index` hook (`[store.hooks.search-index]`), the
+/// fields are read from its `header-fields` array. Values which are not strings are ignored.
+pub fn header_fields(config: Option<&Value>) -> Vec<String> {
+    match config.and_then(|c| c.get("header-fields")) {
+        Some(&Value::Array(ref a)) => a
+            .iter()
+            .filter_map(|v| match *v {
+                Value::String(ref s) => Some(s.clone()),
+                _ => {
+                    warn!("Ignoring non-string value in 'header-fields': {:?}", v);
+                    None
+                },
+            })
+            .collect(),
+        Some(_) => {
+            warn!("'header-fields' is not an array, not indexing any header fields");
+            vec![]
+        },
+        None => vec![],
+    }
+}
+
+/// The configuration keys of the `search-index` hooks
+pub fn config_schema() -> ConfigSchema {
+    ConfigSchema::new()
+        .key("store.hooks.search-index.aspect",        ValueType::String)
+        .key("store.hooks.search-index.header-fields", ValueType::Array)
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use super::header_fields;
+
+    #[test]
+    fn test_header_fields() {
+        let cfg : Value = toml_from_str(r#"header-fields = [ "note.name", "imag.tags" ]"#)
+            .unwrap();
+        assert_eq!(header_fields(Some(&cfg)), vec!["note.name", "imag.tags"]);
+    }
+
+    #[test]
+    fn test_header_fields_not_an_array() {
+        let cfg : Value = toml_from_str(r#"header-fields = "note.name""#).unwrap();
+        assert!(header_fields(Some(&cfg)).is_empty());
+    }
+
+    #[test]
+    fn test_header_fields_missing() {
+        assert!(header_fields(None).is_empty());
+    }
+
+}
+
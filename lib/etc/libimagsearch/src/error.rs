@@ -0,0 +1,57 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+error_chain! {
+    types {
+        SearchError, SearchErrorKind, ResultExt, Result;
+    }
+
+    foreign_links {
+        Io(::std::io::Error);
+        Json(::serde_json::Error);
+    }
+
+    errors {
+        IndexReadError {
+            description("Error while reading the search index")
+            display("Error while reading the search index")
+        }
+
+        IndexWriteError {
+            description("Error while writing the search index")
+            display("Error while writing the search index")
+        }
+
+        IndexBuildError {
+            description("Error while building the search index")
+            display("Error while building the search index")
+        }
+
+        EmptyQuery {
+            description("The query is empty")
+            display("The query is empty")
+        }
+
+        UnterminatedPhrase {
+            description("Phrase in query is not terminated")
+            display("Phrase in query is not terminated")
+        }
+    }
+}
+
@@ -0,0 +1,772 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::fs::create_dir_all;
+use std::fs::rename;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use fs2::FileExt;
+use fs2::lock_contended_error;
+use serde_json;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use error::SearchError as SE;
+use error::SearchErrorKind as SEK;
+use error::ResultExt;
+use error::Result;
+use query::Query;
+use query::QueryMode;
+use query::QueryPart;
+use tokenize::tokenize;
+
+/// The name of the field the content of an entry is indexed as
+pub const CONTENT_FIELD : &'static str = "content";
+
+const SNAPSHOT_FILE : &'static str = "index.json";
+const JOURNAL_FILE  : &'static str = "journal";
+const LOCK_FILE     : &'static str = "lock";
+
+/// An indexed entry: field name -> term -> positions of the term in the field
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Document {
+    fields: BTreeMap<String, BTreeMap<String, Vec<usize>>>,
+    length: usize,
+}
+
+impl Document {
+
+    fn new(entry: &Entry, header_fields: &[String]) -> Document {
+        let mut doc = Document::default();
+        doc.add_field(CONTENT_FIELD, entry.get_content());
+
+        for field in header_fields {
+            match entry.get_header().read(field) {
+                Ok(Some(value)) => {
+                    let text = value_to_text(value);
+                    doc.add_field(field, &text);
+                },
+                Ok(None) => {},
+                Err(e) => debug!("Cannot read header field '{}': {:?}", field, e),
+            }
+        }
+
+        doc
+    }
+
+    fn add_field(&mut self, name: &str, text: &str) {
+        let terms = tokenize(text);
+        self.length += terms.len();
+
+        let field = self.fields.entry(String::from(name)).or_insert_with(BTreeMap::new);
+        for (pos, term) in terms.into_iter().enumerate() {
+            field.entry(term).or_insert_with(Vec::new).push(pos);
+        }
+    }
+
+    fn terms(&self) -> BTreeSet<&String> {
+        self.fields.values().flat_map(|f| f.keys()).collect()
+    }
+
+    /// How often `term` appears in this document
+    fn term_count(&self, term: &str) -> usize {
+        self.fields
+            .values()
+            .filter_map(|f| f.get(term))
+            .map(|positions| positions.len())
+            .sum()
+    }
+
+    /// How often `phrase` appears in this document
+    ///
+    /// A phrase never spans several fields.
+    fn phrase_count(&self, phrase: &[String]) -> usize {
+        self.fields
+            .values()
+            .map(|field| {
+                let first = match phrase.first().and_then(|t| field.get(t)) {
+                    Some(positions) => positions,
+                    None => return 0,
+                };
+
+                first
+                    .iter()
+                    .filter(|&&start| {
+                        phrase.iter().enumerate().skip(1).all(|(offset, term)| {
+                            field.get(term)
+                                .map(|positions| positions.contains(&(start + offset)))
+                                .unwrap_or(false)
+                        })
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+}
+
+fn value_to_text(value: &Value) -> String {
+    match *value {
+        Value::String(ref s)   => s.clone(),
+        Value::Integer(i)      => format!("{}", i),
+        Value::Float(f)        => format!("{}", f),
+        Value::Boolean(b)      => format!("{}", b),
+        Value::Datetime(ref d) => format!("{}", d),
+        Value::Array(ref a)    => a.iter().map(value_to_text).collect::<Vec<_>>().join(" "),
+        Value::Table(_)        => String::new(),
+    }
+}
+
+/// A search result
+#[derive(Debug, Clone)]
+pub struct Hit {
+    id: StoreId,
+    score: f64,
+}
+
+impl Hit {
+
+    pub fn id(&self) -> &StoreId {
+        &self.id
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+}
+
+/// The search index
+///
+/// The index lives in a directory, where a snapshot of the index and a journal of the changes
+/// since the snapshot was written are stored. Only the documents are persisted, the mapping from
+/// terms to documents is built when the index is loaded.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    documents: BTreeMap<String, Document>,
+
+    #[serde(skip)]
+    postings: BTreeMap<String, BTreeSet<String>>,
+
+    #[serde(skip)]
+    journal_len: usize,
+}
+
+impl Index {
+
+    pub fn new() -> Index {
+        Index::default()
+    }
+
+    /// Get the path of the index directory for the runtime path `rtp`
+    pub fn path_for_rtp(rtp: &Path) -> PathBuf {
+        rtp.join("search-index")
+    }
+
+    /// Load the index from the index directory `dir`
+    ///
+    /// The snapshot of the index is loaded and the changes from the journal are applied to it. If
+    /// there is neither a snapshot nor a journal, an empty index is returned.
+    pub fn load(dir: &Path) -> Result<Index> {
+        let snapshot = dir.join(SNAPSHOT_FILE);
+        let mut index = if snapshot.exists() {
+            try!(File::open(&snapshot)
+                .map_err(SE::from)
+                .and_then(|file| serde_json::from_reader(file).map_err(SE::from))
+                .chain_err(|| SEK::IndexReadError))
+        } else {
+            debug!("No search index snapshot at {:?}", snapshot);
+            Index::new()
+        };
+
+        let journal = dir.join(JOURNAL_FILE);
+        if journal.exists() {
+            let file = try!(File::open(&journal).chain_err(|| SEK::IndexReadError));
+            for line in BufReader::new(file).lines() {
+                let line = try!(line.chain_err(|| SEK::IndexReadError));
+                match serde_json::from_str(&line) {
+                    Ok(record) => index.apply(record),
+                    Err(e) => warn!("Ignoring broken record in search index journal: {}", e),
+                }
+            }
+        }
+
+        index.build_postings();
+        Ok(index)
+    }
+
+    /// Write the index to the index directory `dir`
+    ///
+    /// The snapshot is written to a temporary file first, which is then moved in place, so the
+    /// snapshot is never left half-written. The journal is emptied afterwards, so the index must
+    /// contain all changes from the journal. This is the case if it was loaded or built while
+    /// `lock` was held, as no records can be appended to the journal meanwhile.
+    pub fn save(&self, dir: &Path, lock: &IndexLock) -> Result<()> {
+        debug_assert!(lock.dir.as_path() == dir);
+        let snapshot = dir.join(SNAPSHOT_FILE);
+        let tmp      = dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        debug!("Writing search index to {:?}", dir);
+
+        let write_snapshot = || -> Result<()> {
+            try!(create_dir_all(dir));
+            let file       = try!(OpenOptions::new().write(true).create(true).truncate(true).open(&tmp));
+            let mut writer = BufWriter::new(file);
+            try!(serde_json::to_writer(&mut writer, self));
+
+            // The snapshot must be completely on disk before it replaces the old one
+            try!(writer.flush());
+            try!(writer.get_ref().sync_all());
+            try!(rename(&tmp, &snapshot));
+
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(dir.join(JOURNAL_FILE))
+                .map(|_| ())
+                .map_err(SE::from)
+        };
+
+        write_snapshot().chain_err(|| SEK::IndexWriteError)
+    }
+
+    /// Compact the index in the index directory `dir`
+    ///
+    /// The index is loaded and written again, so the journal is emptied. Other processes are not
+    /// waited for: if the index is locked, nothing is done and `false` is returned.
+    pub fn compact(dir: &Path) -> Result<bool> {
+        match try!(IndexLock::try_new(dir)) {
+            Some(lock) => Index::load(dir).and_then(|index| index.save(dir, &lock)).map(|_| true),
+            None => {
+                debug!("Search index in {:?} is locked, not compacting it", dir);
+                Ok(false)
+            },
+        }
+    }
+
+    /// The number of journal records which were applied when loading the index
+    pub fn journal_len(&self) -> usize {
+        self.journal_len
+    }
+
+    fn apply(&mut self, record: JournalRecord) {
+        self.journal_len += 1;
+        match record {
+            JournalRecord::Add { id, document } => {
+                self.documents.insert(id, document);
+            },
+            JournalRecord::Remove { id } => {
+                self.documents.remove(&id);
+            },
+            JournalRecord::Rename { from, to } => {
+                if let Some(doc) = self.documents.remove(&from) {
+                    self.documents.insert(to, doc);
+                }
+            },
+        }
+    }
+
+    /// Build an index over all entries in `store`
+    ///
+    /// Directories and files which are not entries (like the `.git` directory) are skipped.
+    pub fn build_from_store(store: &Store, header_fields: &[String]) -> Result<Index> {
+        let mut index = Index::new();
+        let ids       = try!(store.entries().chain_err(|| SEK::IndexBuildError));
+
+        for id in ids.filter(StoreId::is_entry_file) {
+            let entry = try!(store.retrieve_copy(id).chain_err(|| SEK::IndexBuildError));
+            index.add(&entry, header_fields);
+        }
+
+        Ok(index)
+    }
+
+    fn build_postings(&mut self) {
+        let mut postings = BTreeMap::new();
+        for (key, doc) in &self.documents {
+            for term in doc.terms() {
+                postings.entry(term.clone()).or_insert_with(BTreeSet::new).insert(key.clone());
+            }
+        }
+        self.postings = postings;
+    }
+
+    /// Add `entry` to the index, replacing the indexed version of it if there is one
+    pub fn add(&mut self, entry: &Entry, header_fields: &[String]) {
+        let key = key(entry.get_location());
+        let _   = self.remove_key(&key);
+        let doc = Document::new(entry, header_fields);
+
+        for term in doc.terms() {
+            self.postings.entry(term.clone()).or_insert_with(BTreeSet::new).insert(key.clone());
+        }
+
+        debug!("Indexed '{}'", key);
+        self.documents.insert(key, doc);
+    }
+
+    /// Remove the entry `id` from the index
+    ///
+    /// Returns whether the entry was in the index.
+    pub fn remove(&mut self, id: &StoreId) -> bool {
+        self.remove_key(&key(id)).is_some()
+    }
+
+    fn remove_key(&mut self, key: &str) -> Option<Document> {
+        let doc = match self.documents.remove(key) {
+            Some(doc) => doc,
+            None => return None,
+        };
+
+        for term in doc.terms() {
+            let now_unused = match self.postings.get_mut(term) {
+                Some(ids) => {
+                    ids.remove(key);
+                    ids.is_empty()
+                },
+                None => false,
+            };
+
+            if now_unused {
+                self.postings.remove(term);
+            }
+        }
+
+        Some(doc)
+    }
+
+    /// Move the indexed entry `from` to `to`
+    ///
+    /// Returns whether `from` was in the index.
+    pub fn rename(&mut self, from: &StoreId, to: &StoreId) -> bool {
+        match self.remove_key(&key(from)) {
+            Some(doc) => {
+                let key = key(to);
+                let _   = self.remove_key(&key);
+                for term in doc.terms() {
+                    self.postings
+                        .entry(term.clone())
+                        .or_insert_with(BTreeSet::new)
+                        .insert(key.clone());
+                }
+                self.documents.insert(key, doc);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// The number of indexed entries
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Search the index
+    ///
+    /// Each part of the query is weighted by how rare it is in the index (inverse document
+    /// frequency) and by how often it appears in the entry. The score is normalized by the length
+    /// of the entry. The hits are sorted by score, the best hit first.
+    pub fn search(&self, query: &Query) -> Vec<Hit> {
+        let n = self.documents.len() as f64;
+        let mut scores : HashMap<&String, (usize, f64)> = HashMap::new();
+
+        for part in query.parts() {
+            let matches = self.matches(part);
+            if matches.is_empty() {
+                continue;
+            }
+
+            let idf = (1.0 + n / matches.len() as f64).ln();
+            for (key, count) in matches {
+                let score = scores.entry(key).or_insert((0, 0.0));
+                score.0 += 1;
+                score.1 += (count as f64).sqrt() * idf;
+            }
+        }
+
+        let needed = match query.mode() {
+            QueryMode::All => query.parts().len(),
+            QueryMode::Any => 1,
+        };
+
+        let mut hits : Vec<Hit> = scores
+            .into_iter()
+            .filter(|&(_, (matched, _))| matched >= needed)
+            .filter_map(|(key, (_, score))| {
+                let length = self.documents.get(key).map(|d| d.length).unwrap_or(1).max(1);
+                match StoreId::new_baseless(PathBuf::from(key)) {
+                    Ok(id) => Some(Hit { id: id, score: score / (length as f64).sqrt() }),
+                    Err(e) => {
+                        warn!("Invalid id in search index '{}': {:?}", key, e);
+                        None
+                    },
+                }
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(::std::cmp::Ordering::Equal)
+                .then_with(|| a.id.local().cmp(b.id.local()))
+        });
+        hits
+    }
+
+    /// Get the keys of all documents which match `part` and how often it matches each of them
+    fn matches(&self, part: &QueryPart) -> Vec<(&String, usize)> {
+        match *part {
+            QueryPart::Term(ref term) => self.postings
+                .get(term)
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|k| self.documents.get(k).map(|doc| (k, doc.term_count(term))))
+                        .collect()
+                })
+                .unwrap_or_else(Vec::new),
+
+            QueryPart::Phrase(ref terms) => {
+                let mut candidates : Option<BTreeSet<&String>> = None;
+                for term in terms {
+                    let keys : BTreeSet<&String> = self.postings
+                        .get(term)
+                        .map(|keys| keys.iter().collect())
+                        .unwrap_or_else(BTreeSet::new);
+
+                    candidates = Some(match candidates {
+                        None => keys,
+                        Some(c) => c.intersection(&keys).cloned().collect(),
+                    });
+                }
+
+                candidates
+                    .unwrap_or_else(BTreeSet::new)
+                    .into_iter()
+                    .filter_map(|k| self.documents.get(k).map(|doc| (k, doc.phrase_count(terms))))
+                    .filter(|&(_, count)| count > 0)
+                    .collect()
+            },
+        }
+    }
+
+}
+
+fn key(id: &StoreId) -> String {
+    format!("{}", id)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum JournalRecord {
+    Add { id: String, document: Document },
+    Remove { id: String },
+    Rename { from: String, to: String },
+}
+
+/// The journal of an index directory
+///
+/// Changes to entries are appended to the journal, so the index does not have to be loaded and
+/// written for each change. The changes are applied when the index is loaded.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+
+    /// Get the journal of the index directory `dir`
+    pub fn new(dir: PathBuf) -> Journal {
+        Journal {
+            dir: dir,
+        }
+    }
+
+    /// Record that `entry` was added or changed
+    pub fn add(&self, entry: &Entry, header_fields: &[String]) -> Result<()> {
+        self.append(JournalRecord::Add {
+            id: key(entry.get_location()),
+            document: Document::new(entry, header_fields),
+        })
+    }
+
+    /// Record that the entry `id` was removed
+    pub fn remove(&self, id: &StoreId) -> Result<()> {
+        self.append(JournalRecord::Remove { id: key(id) })
+    }
+
+    /// Record that the entry `from` was moved to `to`
+    pub fn rename(&self, from: &StoreId, to: &StoreId) -> Result<()> {
+        self.append(JournalRecord::Rename { from: key(from), to: key(to) })
+    }
+
+    fn append(&self, record: JournalRecord) -> Result<()> {
+        let mut line = try!(serde_json::to_string(&record).chain_err(|| SEK::IndexWriteError));
+        line.push('\n');
+
+        // The lock keeps the record from being lost when the index is saved meanwhile
+        IndexLock::new(&self.dir)
+            .and_then(|lock| {
+                OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(lock.dir.join(JOURNAL_FILE))
+                    .and_then(|mut file| file.write_all(line.as_bytes()))
+                    .map_err(SE::from)
+            })
+            .chain_err(|| SEK::IndexWriteError)
+    }
+
+}
+
+/// An advisory lock (flock()) on an index directory
+///
+/// Records are only appended to the journal and the index is only saved while the lock is held.
+/// The lock is released when this object is dropped.
+#[derive(Debug)]
+pub struct IndexLock {
+    dir: PathBuf,
+    file: File,
+}
+
+impl IndexLock {
+
+    /// Lock the index directory `dir`, waiting for other processes which hold the lock
+    pub fn new(dir: &Path) -> Result<IndexLock> {
+        let lock = try!(IndexLock::open(dir));
+        try!(lock.file.lock_exclusive());
+        debug!("Locked search index in {:?}", dir);
+        Ok(lock)
+    }
+
+    /// Lock the index directory `dir`, or return `None` if another process holds the lock
+    pub fn try_new(dir: &Path) -> Result<Option<IndexLock>> {
+        let lock = try!(IndexLock::open(dir));
+        match lock.file.try_lock_exclusive() {
+            Ok(()) => {
+                debug!("Locked search index in {:?}", dir);
+                Ok(Some(lock))
+            },
+            Err(ref e) if e.kind() == lock_contended_error().kind() => Ok(None),
+            Err(e) => Err(SE::from(e)),
+        }
+    }
+
+    fn open(dir: &Path) -> Result<IndexLock> {
+        try!(create_dir_all(dir));
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(dir.join(LOCK_FILE))
+            .map(|file| IndexLock { dir: PathBuf::from(dir), file: file })
+            .map_err(SE::from)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+    use toml::Value;
+    use toml::de::from_str as toml_from_str;
+
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::StoreId;
+
+    use query::Query;
+    use query::QueryMode;
+    use super::Index;
+    use super::IndexLock;
+    use super::Journal;
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    fn entry(name: &str, header: &str, content: &str) -> Entry {
+        let mut entry = Entry::new(id(name));
+        {
+            let h : Value = toml_from_str(header).unwrap();
+            let hdr = entry.get_header_mut();
+            if let (&mut Value::Table(ref mut t), Value::Table(h)) = (hdr, h) {
+                t.extend(h);
+            }
+        }
+        *entry.get_content_mut() = String::from(content);
+        entry
+    }
+
+    fn setup_index() -> Index {
+        let fields = vec![String::from("note.name")];
+        let mut index = Index::new();
+        index.add(&entry("notes/shopping", "[note]\nname = \"Shopping list\"",
+                         "Buy milk and bread. Milk is important."), &fields);
+        index.add(&entry("notes/recipes", "[note]\nname = \"Recipes\"",
+                         "Bread recipe: flour, water, salt. Serve with milk tea."), &fields);
+        index.add(&entry("notes/todo", "", "Clean the kitchen"), &fields);
+        index
+    }
+
+    fn ids(index: &Index, query: Query) -> Vec<String> {
+        index.search(&query).into_iter().map(|h| format!("{}", h.id())).collect()
+    }
+
+    #[test]
+    fn test_term_search_is_ranked() {
+        let index = setup_index();
+        let hits  = ids(&index, Query::parse("milk").unwrap());
+        assert_eq!(hits, vec!["notes/shopping", "notes/recipes"]);
+    }
+
+    #[test]
+    fn test_all_terms_have_to_match() {
+        let index = setup_index();
+        assert_eq!(ids(&index, Query::parse("bread flour").unwrap()), vec!["notes/recipes"]);
+        assert!(ids(&index, Query::parse("kitchen milk").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_any_term_matches() {
+        let index = setup_index();
+        let query = Query::parse("kitchen flour").unwrap().with_mode(QueryMode::Any);
+        let mut hits = ids(&index, query);
+        hits.sort();
+        assert_eq!(hits, vec!["notes/recipes", "notes/todo"]);
+    }
+
+    #[test]
+    fn test_phrase_search() {
+        let index = setup_index();
+        assert_eq!(ids(&index, Query::parse("\"milk tea\"").unwrap()), vec!["notes/recipes"]);
+        assert!(ids(&index, Query::parse("\"tea milk\"").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_header_field_search() {
+        let index = setup_index();
+        assert_eq!(ids(&index, Query::parse("list").unwrap()), vec!["notes/shopping"]);
+    }
+
+    #[test]
+    fn test_remove_and_rename() {
+        let mut index = setup_index();
+        assert!(index.remove(&id("notes/todo")));
+        assert!(!index.remove(&id("notes/todo")));
+        assert!(ids(&index, Query::parse("kitchen").unwrap()).is_empty());
+
+        assert!(index.rename(&id("notes/recipes"), &id("cooking/recipes")));
+        assert_eq!(ids(&index, Query::parse("flour").unwrap()), vec!["cooking/recipes"]);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir   = TempDir::new("imag-search-index-test").unwrap();
+        let path  = Index::path_for_rtp(dir.path());
+        let index = setup_index();
+        index.save(&path, &IndexLock::new(&path).unwrap()).unwrap();
+
+        let loaded = Index::load(&path).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.journal_len(), 0);
+        assert_eq!(ids(&loaded, Query::parse("milk").unwrap()),
+                   vec!["notes/shopping", "notes/recipes"]);
+    }
+
+    #[test]
+    fn test_journal_is_applied_on_load() {
+        let dir  = TempDir::new("imag-search-index-test").unwrap();
+        let path = Index::path_for_rtp(dir.path());
+        setup_index().save(&path, &IndexLock::new(&path).unwrap()).unwrap();
+
+        let journal = Journal::new(path.clone());
+        journal.add(&entry("notes/garden", "", "Water the tomatoes"), &[]).unwrap();
+        journal.remove(&id("notes/todo")).unwrap();
+        journal.rename(&id("notes/recipes"), &id("cooking/recipes")).unwrap();
+
+        let loaded = Index::load(&path).unwrap();
+        assert_eq!(loaded.journal_len(), 3);
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(ids(&loaded, Query::parse("tomatoes").unwrap()), vec!["notes/garden"]);
+        assert!(ids(&loaded, Query::parse("kitchen").unwrap()).is_empty());
+        assert_eq!(ids(&loaded, Query::parse("flour").unwrap()), vec!["cooking/recipes"]);
+
+        assert!(Index::compact(&path).unwrap());
+        let compacted = Index::load(&path).unwrap();
+        assert_eq!(compacted.journal_len(), 0);
+        assert_eq!(compacted.len(), 3);
+    }
+
+    #[test]
+    fn test_compact_skips_locked_index() {
+        let dir  = TempDir::new("imag-search-index-test").unwrap();
+        let path = Index::path_for_rtp(dir.path());
+        setup_index().save(&path, &IndexLock::new(&path).unwrap()).unwrap();
+        Journal::new(path.clone()).remove(&id("notes/todo")).unwrap();
+
+        {
+            let _lock = IndexLock::new(&path).unwrap();
+            assert!(IndexLock::try_new(&path).unwrap().is_none());
+            assert!(!Index::compact(&path).unwrap());
+        }
+
+        assert_eq!(Index::load(&path).unwrap().journal_len(), 1);
+        assert!(Index::compact(&path).unwrap());
+        assert_eq!(Index::load(&path).unwrap().journal_len(), 0);
+    }
+
+    #[test]
+    fn test_build_from_store_skips_non_entries() {
+        use std::fs::{create_dir_all, File};
+        use libimagstore::store::Store;
+
+        let dir   = TempDir::new("imag-search-index-test").unwrap();
+        let path  = dir.path().join("store");
+        create_dir_all(path.join(".git")).unwrap();
+        File::create(path.join(".git").join("HEAD")).unwrap();
+        let store = Store::new(path, None).unwrap();
+
+        {
+            let _ = store.create(PathBuf::from("notes/shopping")).unwrap();
+            let _ = store.create(PathBuf::from("todo")).unwrap();
+        }
+
+        let index = Index::build_from_store(&store, &[]).unwrap();
+        assert_eq!(index.len(), 2);
+    }
+
+}
@@ -0,0 +1,63 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![recursion_limit="256"]
+
+#![deny(
+    dead_code,
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+//! Full-text search over the entries of the store
+//!
+//! The index is an inverted index over the content and selected header fields of the entries. It
+//! is persisted in the runtime path and kept up to date by the `search-index` hooks from
+//! `libimagstorestdhook`. It can be queried for terms and phrases, the results are ranked.
+//!
+
+#[macro_use] extern crate log;
+extern crate toml;
+extern crate toml_query;
+extern crate serde;
+extern crate serde_json;
+extern crate fs2;
+#[macro_use] extern crate serde_derive;
+#[macro_use] extern crate error_chain;
+#[cfg(test)] extern crate tempdir;
+
+extern crate libimagstore;
+extern crate libimagutil;
+
+pub mod config;
+pub mod error;
+pub mod index;
+pub mod query;
+pub mod tokenize;
+
@@ -0,0 +1,147 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use tokenize::tokenize;
+use error::SearchError as SE;
+use error::SearchErrorKind as SEK;
+use error::Result;
+
+/// A part of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryPart {
+    /// A single term
+    Term(String),
+
+    /// Several terms which have to appear in this order, one after another
+    Phrase(Vec<String>),
+}
+
+/// How the parts of a query are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryMode {
+    /// All parts of the query have to match
+    All,
+
+    /// At least one part of the query has to match
+    Any,
+}
+
+/// A search query
+#[derive(Debug, Clone)]
+pub struct Query {
+    parts: Vec<QueryPart>,
+    mode: QueryMode,
+}
+
+impl Query {
+
+    /// Parse a query string
+    ///
+    /// Words are terms, text in double quotes is a phrase. Words which contain separators (like
+    /// "foo-bar") are phrases as well.
+    pub fn parse(s: &str) -> Result<Query> {
+        let mut parts = vec![];
+
+        for (i, segment) in s.split('"').enumerate() {
+            if i % 2 == 1 {
+                parts.push(make_part(tokenize(segment)));
+            } else {
+                for word in segment.split_whitespace() {
+                    parts.push(make_part(tokenize(word)));
+                }
+            }
+        }
+
+        if s.matches('"').count() % 2 == 1 {
+            return Err(SE::from_kind(SEK::UnterminatedPhrase));
+        }
+
+        let parts : Vec<QueryPart> = parts.into_iter().filter_map(|p| p).collect();
+        if parts.is_empty() {
+            return Err(SE::from_kind(SEK::EmptyQuery));
+        }
+
+        Ok(Query {
+            parts: parts,
+            mode: QueryMode::All,
+        })
+    }
+
+    pub fn with_mode(mut self, mode: QueryMode) -> Query {
+        self.mode = mode;
+        self
+    }
+
+    pub fn parts(&self) -> &Vec<QueryPart> {
+        &self.parts
+    }
+
+    pub fn mode(&self) -> QueryMode {
+        self.mode
+    }
+
+}
+
+fn make_part(mut terms: Vec<String>) -> Option<QueryPart> {
+    match terms.len() {
+        0 => None,
+        1 => terms.pop().map(QueryPart::Term),
+        _ => Some(QueryPart::Phrase(terms)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn term(s: &str) -> QueryPart {
+        QueryPart::Term(String::from(s))
+    }
+
+    fn phrase(v: &[&str]) -> QueryPart {
+        QueryPart::Phrase(v.iter().map(|s| String::from(*s)).collect())
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        let q = Query::parse("Foo bar").unwrap();
+        assert_eq!(q.parts(), &vec![term("foo"), term("bar")]);
+        assert_eq!(q.mode(), QueryMode::All);
+    }
+
+    #[test]
+    fn test_parse_phrases() {
+        let q = Query::parse(r#"foo "bar baz" foo-bar "single""#).unwrap();
+        assert_eq!(q.parts(), &vec![
+                   term("foo"),
+                   phrase(&["bar", "baz"]),
+                   phrase(&["foo", "bar"]),
+                   term("single"),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse(" -- ").is_err());
+        assert!(Query::parse(r#"foo "bar"#).is_err());
+    }
+
+}
+
@@ -0,0 +1,46 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+/// Split `text` into terms
+///
+/// Terms are separated by all characters which are not alphanumeric and get lowercased.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::tokenize;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("Hello, World! foo-bar  baz42"),
+                   vec!["hello", "world", "foo", "bar", "baz42"]);
+    }
+
+    #[test]
+    fn test_tokenize_empty() {
+        assert!(tokenize("  ,.-  ").is_empty());
+    }
+
+}
+
@@ -39,6 +39,7 @@ extern crate regex;
 extern crate url;
 extern crate boolinator;
 extern crate tempfile;
+extern crate toml;
 
 #[macro_use] mod log_result;
 pub mod cli_validators;
@@ -47,6 +48,7 @@ pub mod edit;
 pub mod info_result;
 pub mod iter;
 pub mod key_value_split;
+pub mod schema;
 pub mod variants;
 pub mod warn_exit;
 pub mod warn_result;
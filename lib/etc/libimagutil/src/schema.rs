@@ -0,0 +1,399 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015, 2016 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Schema of the configuration
+//!
+//! Each library which reads the configuration describes the keys it reads and the types of their
+//! values in a `ConfigSchema`, which it exposes with a `config_schema()` function.
+//! `imag-config check` merges the schemas and checks the configuration files against them,
+//! reporting unknown keys, values of the wrong type and deprecated keys.
+//!
+//! A part of a key can be `*`, which matches any name, for example `store.hooks.*` for the
+//! configuration of all hooks. A key of type `ValueType::Table` is a table with any content,
+//! unless the schema has keys in it, for example `store.hooks.search-index.aspect`. Then the keys
+//! of the table are checked.
+//!
+
+use std::fmt::{Display, Formatter};
+use std::fmt::Error as FmtError;
+
+use toml::Value;
+use toml::value::Table;
+
+/// The type of a configuration value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Datetime,
+    Array,
+    Table,
+}
+
+impl ValueType {
+
+    /// Get the type of `value`
+    pub fn of(value: &Value) -> ValueType {
+        match *value {
+            Value::String(_)   => ValueType::String,
+            Value::Integer(_)  => ValueType::Integer,
+            Value::Float(_)    => ValueType::Float,
+            Value::Boolean(_)  => ValueType::Boolean,
+            Value::Datetime(_) => ValueType::Datetime,
+            Value::Array(_)    => ValueType::Array,
+            Value::Table(_)    => ValueType::Table,
+        }
+    }
+
+    /// Whether `value` is of this type. Integers are accepted as floats.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (*self, ValueType::of(value)) {
+            (ValueType::Float, ValueType::Integer) => true,
+            (expected, found)                      => expected == found,
+        }
+    }
+
+}
+
+impl Display for ValueType {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        let s = match *self {
+            ValueType::String   => "a string",
+            ValueType::Integer  => "an integer",
+            ValueType::Float    => "a float",
+            ValueType::Boolean  => "a boolean",
+            ValueType::Datetime => "a datetime",
+            ValueType::Array    => "an array",
+            ValueType::Table    => "a table",
+        };
+        write!(fmt, "{}", s)
+    }
+
+}
+
+/// A problem found by `ConfigSchema::check()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaViolation {
+    /// The key is not in the schema
+    UnknownKey(String),
+
+    /// The value has the wrong type
+    WrongType {
+        key: String,
+        expected: ValueType,
+        found: ValueType,
+    },
+
+    /// The key is deprecated, `replacement` should be used instead
+    Deprecated {
+        key: String,
+        replacement: String,
+    },
+}
+
+impl SchemaViolation {
+
+    /// The key the violation is about
+    pub fn key(&self) -> &str {
+        match *self {
+            SchemaViolation::UnknownKey(ref key)         => key,
+            SchemaViolation::WrongType { ref key, .. }   => key,
+            SchemaViolation::Deprecated { ref key, .. }  => key,
+        }
+    }
+
+}
+
+impl Display for SchemaViolation {
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            SchemaViolation::UnknownKey(ref key) => write!(fmt, "Unknown key '{}'", key),
+            SchemaViolation::WrongType { ref key, ref expected, ref found } => {
+                write!(fmt, "'{}' should be {}, but is {}", key, expected, found)
+            },
+            SchemaViolation::Deprecated { ref key, ref replacement } => {
+                write!(fmt, "'{}' is deprecated, use '{}' instead", key, replacement)
+            },
+        }
+    }
+
+}
+
+/// The keys a library reads from the configuration
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSchema {
+    keys: Vec<(String, ValueType)>,
+    deprecated: Vec<(String, String)>,
+}
+
+impl ConfigSchema {
+
+    pub fn new() -> ConfigSchema {
+        ConfigSchema::default()
+    }
+
+    /// Add the key `key` with values of type `ty`
+    pub fn key(mut self, key: &str, ty: ValueType) -> ConfigSchema {
+        self.keys.push((String::from(key), ty));
+        self
+    }
+
+    /// Add the deprecated key `key`, which was replaced by `replacement`
+    pub fn deprecated(mut self, key: &str, replacement: &str) -> ConfigSchema {
+        self.deprecated.push((String::from(key), String::from(replacement)));
+        self
+    }
+
+    /// Add the keys of `other` to this schema
+    pub fn merge(mut self, other: ConfigSchema) -> ConfigSchema {
+        self.keys.extend(other.keys);
+        self.deprecated.extend(other.deprecated);
+        self
+    }
+
+    /// Check `config` against the schema
+    pub fn check(&self, config: &Value) -> Vec<SchemaViolation> {
+        let mut violations = vec![];
+        match *config {
+            Value::Table(ref t) => self.check_table("", t, &mut violations),
+            ref other => violations.push(SchemaViolation::WrongType {
+                key: String::new(),
+                expected: ValueType::Table,
+                found: ValueType::of(other),
+            }),
+        }
+        violations
+    }
+
+    fn check_table(&self, prefix: &str, table: &Table, violations: &mut Vec<SchemaViolation>) {
+        for (k, v) in table.iter() {
+            let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+
+            if let Some(&(_, ref replacement)) = self.deprecated.iter().find(|&&(ref d, _)| matches(d, &key)) {
+                violations.push(SchemaViolation::Deprecated {
+                    key: key,
+                    replacement: replacement.clone(),
+                });
+                continue;
+            }
+
+            let has_keys_below = self.keys.iter().any(|&(ref p, _)| is_below(p, &key));
+
+            match self.keys.iter().find(|&&(ref p, _)| matches(p, &key)) {
+                Some(&(_, ValueType::Table)) if has_keys_below => match *v {
+                    Value::Table(ref t) => self.check_table(&key, t, violations),
+                    ref other => violations.push(SchemaViolation::WrongType {
+                        key: key,
+                        expected: ValueType::Table,
+                        found: ValueType::of(other),
+                    }),
+                },
+
+                Some(&(_, ref ty)) => if !ty.accepts(v) {
+                    violations.push(SchemaViolation::WrongType {
+                        key: key,
+                        expected: *ty,
+                        found: ValueType::of(v),
+                    });
+                },
+
+                None if has_keys_below => match *v {
+                    Value::Table(ref t) => self.check_table(&key, t, violations),
+                    ref other => violations.push(SchemaViolation::WrongType {
+                        key: key,
+                        expected: ValueType::Table,
+                        found: ValueType::of(other),
+                    }),
+                },
+
+                None => violations.push(SchemaViolation::UnknownKey(key)),
+            }
+        }
+    }
+
+}
+
+/// Whether `pattern` matches `key`
+fn matches(pattern: &str, key: &str) -> bool {
+    let pattern : Vec<&str> = pattern.split('.').collect();
+    let key     : Vec<&str> = key.split('.').collect();
+
+    pattern.len() == key.len() && pattern.iter().zip(key.iter()).all(|(p, k)| *p == "*" || p == k)
+}
+
+/// Whether `pattern` matches a key in the table `key`
+fn is_below(pattern: &str, key: &str) -> bool {
+    let pattern : Vec<&str> = pattern.split('.').collect();
+    let key     : Vec<&str> = key.split('.').collect();
+
+    pattern.len() > key.len() && pattern.iter().zip(key.iter()).all(|(p, k)| *p == "*" || p == k)
+}
+
+/// Find the line (starting at 1) where `key` is set in the TOML document `content`
+///
+/// If the key is a table which has no header of its own, the first line of a table in it is
+/// returned.
+pub fn key_line(content: &str, key: &str) -> Option<usize> {
+    let below      = format!("{}.", key);
+    let mut table  = String::new();
+    let mut inside = None;
+
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let path = if line.starts_with('[') {
+            table = line
+                .trim_left_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string();
+            table.clone()
+        } else if !line.starts_with('#') && line.contains('=') {
+            let k = line.split('=').next().unwrap_or("").trim().trim_matches('"');
+            if table.is_empty() { String::from(k) } else { format!("{}.{}", table, k) }
+        } else {
+            continue;
+        };
+
+        if path == key {
+            return Some(n + 1);
+        }
+
+        if inside.is_none() && path.starts_with(&below) {
+            inside = Some(n + 1);
+        }
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value;
+    use toml::de::from_str;
+
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let schema = ConfigSchema::new()
+            .key("verbose",               ValueType::Boolean)
+            .key("imag.logging.level",    ValueType::String)
+            .key("store.implicit-create", ValueType::Boolean)
+            .key("store.lock-timeout",    ValueType::Integer)
+            .key("store.hooks.*",         ValueType::Table)
+            .key("store.id-templates.*",  ValueType::String)
+            .key("diary.default_diary",   ValueType::String)
+            .deprecated("store.lock_timeout", "store.lock-timeout");
+
+        let config : Value = from_str(r#"
+            verbose = "yes"
+            unknown = 1
+
+            [store]
+            lock-timeout = 100
+            lock_timeout = 100
+            implicit-create = 1
+
+            [store.hooks.git-update]
+            aspect = "vcs"
+
+            [store.id-templates]
+            notes = "notes/{name}"
+
+            [diary]
+            default_diary = "work"
+            other = true
+
+            [imag]
+            logging = "debug"
+        "#).unwrap();
+
+        let violations = schema.check(&config);
+        assert_eq!(violations, vec![
+            SchemaViolation::UnknownKey(String::from("diary.other")),
+            SchemaViolation::WrongType {
+                key: String::from("imag.logging"),
+                expected: ValueType::Table,
+                found: ValueType::String,
+            },
+            SchemaViolation::WrongType {
+                key: String::from("store.implicit-create"),
+                expected: ValueType::Boolean,
+                found: ValueType::Integer,
+            },
+            SchemaViolation::Deprecated {
+                key: String::from("store.lock_timeout"),
+                replacement: String::from("store.lock-timeout"),
+            },
+            SchemaViolation::UnknownKey(String::from("unknown")),
+            SchemaViolation::WrongType {
+                key: String::from("verbose"),
+                expected: ValueType::Boolean,
+                found: ValueType::String,
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_key_line() {
+        let content = "verbose = true\n\n[store]\n# lock = 1\nlock-timeout = 100\n\n[store.hooks.git-update]\naspect = \"vcs\"\n";
+        assert_eq!(key_line(content, "verbose"), Some(1));
+        assert_eq!(key_line(content, "store"), Some(3));
+        assert_eq!(key_line(content, "store.lock-timeout"), Some(5));
+        assert_eq!(key_line(content, "store.hooks"), Some(7));
+        assert_eq!(key_line(content, "store.hooks.git-update.aspect"), Some(8));
+        assert_eq!(key_line(content, "store.lock"), None);
+    }
+
+    #[test]
+    fn test_check_table_with_keys() {
+        let schema = ConfigSchema::new()
+            .key("store.hooks.*",                          ValueType::Table)
+            .key("store.hooks.search-index.aspect",        ValueType::String)
+            .key("store.hooks.search-index.header-fields", ValueType::Array);
+
+        let config : Value = from_str(r#"
+            [store.hooks.git-update]
+            aspect = "vcs"
+            anything = 1
+
+            [store.hooks.search-index]
+            aspect = "index"
+            header-fields = "note.name"
+            other = 1
+        "#).unwrap();
+
+        let violations = schema.check(&config);
+        assert_eq!(violations, vec![
+            SchemaViolation::WrongType {
+                key: String::from("store.hooks.search-index.header-fields"),
+                expected: ValueType::Array,
+                found: ValueType::String,
+            },
+            SchemaViolation::UnknownKey(String::from("store.hooks.search-index.other")),
+        ]);
+    }
+
+}